@@ -7,9 +7,11 @@ pub use assets::{
     SamplingDef, ScatterKindDef, ScatterLayerDef, ScatterPlanAsset, ScatterPlanAssetLoader,
     SelectionStrategyDef,
 };
+use bevy::asset::AssetEvent;
 use bevy::prelude::*;
 use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
 pub use events::{ChannelSink, ScatterBus, ScatterBusConfig, ScatterEventFilter, ScatterMessage};
+use map_scatter::fieldgraph::ChunkId;
 use map_scatter::prelude::*;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
@@ -17,7 +19,7 @@ pub use streaming::{
     MapScatterStreamingPlugin, ScatterStreamChunk, ScatterStreamChunks, ScatterStreamPlaced,
     ScatterStreamPlacement, ScatterStreamSettings,
 };
-pub use textures::ImageTexture;
+pub use textures::{ImageTexture, LiveImageTexture, ScatterLiveTextures};
 
 mod assets;
 mod events;
@@ -39,7 +41,7 @@ pub mod prelude {
         MapScatterStreamingPlugin, ScatterStreamChunk, ScatterStreamChunks, ScatterStreamPlaced,
         ScatterStreamPlacement, ScatterStreamSettings,
     };
-    pub use crate::textures::ImageTexture;
+    pub use crate::textures::{ImageTexture, LiveImageTexture, ScatterLiveTextures};
     pub use crate::{MapScatterPlugin, ScatterFinished, ScatterRequest, ScatterTextureRegistry};
 }
 
@@ -82,6 +84,8 @@ pub struct ScatterRequest {
     pub config: RunConfig,
     /// RNG seed for deterministic scattering.
     pub seed: u64,
+    /// World chunk to scatter instead of the configured domain, see [`run_world_chunk`].
+    pub world_chunk: Option<(ChunkId, WorldChunkConfig)>,
 }
 
 impl ScatterRequest {
@@ -96,8 +100,16 @@ impl ScatterRequest {
             plan,
             config,
             seed,
+            world_chunk: None,
         }
     }
+
+    /// Scatters a single chunk of an unbounded world, so neighbouring chunks requested with
+    /// the same seed and world configuration line up without seams.
+    pub fn with_world_chunk(mut self, chunk: ChunkId, world: WorldChunkConfig) -> Self {
+        self.world_chunk = Some((chunk, world));
+        self
+    }
 }
 
 /// Component holding an async scatter job task.
@@ -126,6 +138,9 @@ impl Plugin for MapScatterPlugin {
             .init_resource::<ScatterBus>()
             .init_resource::<ScatterTextureRegistry>()
             .init_resource::<ScatterCache>()
+            .init_resource::<ScatterLiveTextures>()
+            .add_message::<AssetEvent<Image>>()
+            .add_systems(PreUpdate, textures::sync_live_textures)
             .add_systems(Update, poll_scatter_jobs)
             .add_systems(Update, drain_scatter_messages)
             .add_observer(spawn_scatter_job);
@@ -145,7 +160,11 @@ fn spawn_scatter_job(
     let filter = bus.filter().clone();
     let entity = request.entity;
 
-    if let Err(err) = request.config.validate() {
+    let validation = match &request.world_chunk {
+        Some((_, world)) => world.validate(),
+        None => request.config.validate(),
+    };
+    if let Err(err) = validation {
         warn!(
             "ScatterRequest config invalid for {:?}: {}",
             request.entity, err
@@ -162,6 +181,7 @@ fn spawn_scatter_job(
     let plan = plan.into();
     let config = request.config.clone();
     let seed = request.seed;
+    let world_chunk = request.world_chunk.clone();
     let textures = textures.0.clone();
     let cache = cache.0.clone();
     let tx = tx.clone();
@@ -169,8 +189,6 @@ fn spawn_scatter_job(
 
     // Spawn async job returning the RunResult
     let task = pool.spawn(async move {
-        let mut rng = StdRng::seed_from_u64(seed);
-
         // Stream events through channel sink
         let mut sink = ChannelSink {
             request: entity,
@@ -178,6 +196,20 @@ fn spawn_scatter_job(
             filter,
         };
 
+        if let Some((chunk, world)) = world_chunk {
+            return run_world_chunk_with_events(
+                &plan,
+                chunk,
+                &world,
+                &textures,
+                cache.as_ref(),
+                seed,
+                &mut sink,
+            );
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
         let Ok(mut runner) = ScatterRunner::try_new(config.clone(), &textures, cache.as_ref())
         else {
             warn!("Scatter runner failed to initialize for {:?}", entity);
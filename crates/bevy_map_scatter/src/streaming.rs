@@ -4,9 +4,9 @@ use bevy::asset::AssetEvent;
 use bevy::prelude::*;
 use bevy::transform::TransformSystems;
 use map_scatter::fieldgraph::ChunkId;
-use map_scatter::prelude::{seed_for_chunk, KindId, Placement, RunConfig};
+use map_scatter::prelude::{KindId, Placement, PlacementAttributes, WorldChunkConfig};
 
-use crate::{ScatterFinished, ScatterPlanAsset, ScatterRequest};
+use crate::{ScatterFinished, ScatterPlanAsset, ScatterRequest, ScatterTextureRegistry};
 
 /// Settings for streaming scatter chunks around an anchor entity.
 #[non_exhaustive]
@@ -18,8 +18,11 @@ pub struct ScatterStreamSettings {
     pub chunk_size: Vec2,
     /// View radius (in chunks) around the anchor.
     pub view_radius: IVec2,
-    /// Base RNG seed for chunk seeding.
+    /// RNG seed shared by all chunks of the world.
     pub seed: u64,
+    /// Distance in world units around each chunk within which neighbouring chunks are read,
+    /// so spacing and overlays line up across chunk borders.
+    pub halo: f32,
     /// Chunk extent used for evaluation in world units.
     pub chunk_extent: f32,
     /// Raster cell size used for field sampling.
@@ -45,6 +48,7 @@ impl ScatterStreamSettings {
             chunk_size,
             view_radius,
             seed,
+            halo: 0.0,
             chunk_extent,
             raster_cell_size: 1.0,
             grid_halo: 2,
@@ -53,6 +57,11 @@ impl ScatterStreamSettings {
         }
     }
 
+    pub fn with_halo(mut self, halo: f32) -> Self {
+        self.halo = halo;
+        self
+    }
+
     pub fn with_chunk_extent(mut self, chunk_extent: f32) -> Self {
         self.chunk_extent = chunk_extent;
         self
@@ -107,6 +116,8 @@ pub struct ScatterStreamPlacement {
     pub kind_id: KindId,
     /// World-space position of the placement.
     pub world_position: Vec2,
+    /// Attribute values resolved for the placement.
+    pub attributes: PlacementAttributes,
 }
 
 /// [`EntityEvent`] emitted when a streamed placement entity is spawned.
@@ -141,6 +152,7 @@ fn update_streams(
     mut commands: Commands,
     assets: Res<Assets<ScatterPlanAsset>>,
     mut plan_events: MessageReader<AssetEvent<ScatterPlanAsset>>,
+    textures: Option<Res<ScatterTextureRegistry>>,
     mut anchors: Query<(
         Entity,
         &GlobalTransform,
@@ -161,6 +173,9 @@ fn update_streams(
         }
     }
 
+    // Chunks scattered with replaced textures are stale.
+    let textures_changed = textures.is_some_and(|textures| textures.is_changed());
+
     for (anchor_entity, transform, settings, chunks_opt) in anchors.iter_mut() {
         let Some(mut chunks) = chunks_opt else {
             commands
@@ -169,7 +184,8 @@ fn update_streams(
             continue;
         };
 
-        if settings.is_changed() || changed_plans.contains(&settings.plan.id()) {
+        if settings.is_changed() || textures_changed || changed_plans.contains(&settings.plan.id())
+        {
             for &entity in chunks.0.values() {
                 commands.entity(entity).despawn();
             }
@@ -233,13 +249,14 @@ fn update_streams(
             }
 
             let center = chunk_center(chunk_id, settings.chunk_size);
-            let config = RunConfig::new(settings.chunk_size)
-                .with_domain_center(center)
+            let world = WorldChunkConfig::new(settings.chunk_size)
+                .with_halo(settings.halo)
                 .with_chunk_extent(settings.chunk_extent)
                 .with_raster_cell_size(settings.raster_cell_size)
                 .with_grid_halo(settings.grid_halo);
+            let chunk = ChunkId(chunk_id.x, chunk_id.y);
 
-            if let Err(err) = config.validate() {
+            if let Err(err) = world.validate() {
                 warn!("Scatter stream config invalid for {:?}: {}", chunk_id, err);
                 continue;
             }
@@ -258,13 +275,15 @@ fn update_streams(
             chunks.0.insert(chunk_id, chunk_entity);
             spawned += 1;
 
-            let seed = seed_for_chunk(settings.seed, ChunkId(chunk_id.x, chunk_id.y));
-            commands.trigger(ScatterRequest::new(
-                chunk_entity,
-                settings.plan.clone(),
-                config,
-                seed,
-            ));
+            commands.trigger(
+                ScatterRequest::new(
+                    chunk_entity,
+                    settings.plan.clone(),
+                    world.run_config(chunk),
+                    settings.seed,
+                )
+                .with_world_chunk(chunk, world),
+            );
         }
     }
 }
@@ -288,8 +307,13 @@ fn handle_scatter_finished(
                     ScatterStreamPlacement {
                         kind_id: placement.kind_id.clone(),
                         world_position: placement.position,
+                        attributes: placement.attributes.clone(),
                     },
-                    Transform::from_translation(Vec3::new(local.x, local.y, 0.0)),
+                    Transform::from_translation(Vec3::new(local.x, local.y, 0.0))
+                        .with_rotation(Quat::from_rotation_z(
+                            placement.attributes.rotation.unwrap_or(0.0),
+                        ))
+                        .with_scale(Vec3::splat(placement.attributes.scale.unwrap_or(1.0))),
                 ))
                 .id();
             placed_events.push(ScatterStreamPlaced {
@@ -331,7 +355,7 @@ mod tests {
         );
 
         let mut assets = Assets::<ScatterPlanAsset>::default();
-        let plan = assets.add(ScatterPlanAsset { layers: Vec::new() });
+        let plan = assets.add(ScatterPlanAsset::default());
         app.world_mut().insert_resource(assets);
 
         let chunk_size = Vec2::splat(10.0);
@@ -400,4 +424,24 @@ mod tests {
         assert_eq!(chunk.center, Vec2::new(chunk_size.x, 0.0));
         assert!(app.world().get_entity(old_chunk_entity).is_err());
     }
+
+    #[test]
+    fn rescatters_chunks_when_textures_change() {
+        let (mut app, anchor, _chunk_size) = setup_app();
+
+        app.update();
+        app.update();
+
+        let chunks = app.world().get::<ScatterStreamChunks>(anchor).unwrap();
+        let old_chunk_entity = chunks.0[&IVec2::ZERO];
+
+        app.world_mut()
+            .insert_resource(ScatterTextureRegistry::default());
+        app.update();
+
+        let chunks = app.world().get::<ScatterStreamChunks>(anchor).unwrap();
+        assert_eq!(chunks.0.len(), 1);
+        assert_ne!(chunks.0[&IVec2::ZERO], old_chunk_entity);
+        assert!(app.world().get_entity(old_chunk_entity).is_err());
+    }
 }
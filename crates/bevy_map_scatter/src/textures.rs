@@ -1,22 +1,77 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use bevy::prelude::Image;
+use bevy::asset::AssetEvent;
+use bevy::prelude::*;
 use bevy::render::render_resource::TextureFormat;
 use glam::Vec2;
-use map_scatter::prelude::{Texture, TextureChannel};
+use half::f16;
+use map_scatter::prelude::{Texture, TextureChannel, TextureRegistry, TextureSampler};
+
+use crate::ScatterTextureRegistry;
 
 /// CPU-side adapter that snapshots a Bevy [`Image`] and implements [`Texture`].
 /// This copies the pixel data into memory. Re-create the [`ImageTexture`] when the source
-///   [`Image`] changes.
+///   [`Image`] changes, or register it with [`ScatterLiveTextures`] to have that done
+///   automatically.
+///
+/// Channels missing from the format follow the rule of the core `DynamicImageTexture`:
+/// single-channel formats return their value for `R`, `G`, and `B`, formats without alpha
+/// return `1.0` for `A`, and two-channel formats return `0.0` for `B`.
 pub struct ImageTexture {
     domain_extent: Vec2,
     domain_center: Vec2,
     format: TextureFormat,
+    component: Component,
+    components: usize,
     pixels: Arc<Vec<u8>>,
     width: u32,
     height: u32,
 }
 
+/// Storage type of the components of a texel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Component {
+    U8,
+    U16,
+    F16,
+    F32,
+}
+
+impl Component {
+    #[inline]
+    fn size(self) -> usize {
+        match self {
+            Component::U8 => 1,
+            Component::U16 | Component::F16 => 2,
+            Component::F32 => 4,
+        }
+    }
+}
+
+/// Component type and count of the supported formats.
+fn texel_layout(format: TextureFormat) -> Option<(Component, usize)> {
+    let layout = match format {
+        TextureFormat::R8Unorm => (Component::U8, 1),
+        TextureFormat::Rg8Unorm => (Component::U8, 2),
+        TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Bgra8UnormSrgb => (Component::U8, 4),
+        TextureFormat::R16Unorm => (Component::U16, 1),
+        TextureFormat::Rg16Unorm => (Component::U16, 2),
+        TextureFormat::Rgba16Unorm => (Component::U16, 4),
+        TextureFormat::R16Float => (Component::F16, 1),
+        TextureFormat::Rg16Float => (Component::F16, 2),
+        TextureFormat::Rgba16Float => (Component::F16, 4),
+        TextureFormat::R32Float => (Component::F32, 1),
+        TextureFormat::Rg32Float => (Component::F32, 2),
+        TextureFormat::Rgba32Float => (Component::F32, 4),
+        _ => return None,
+    };
+    Some(layout)
+}
+
 impl ImageTexture {
     /// Creates an [`ImageTexture`] snapshot from a Bevy [`Image`] and maps it to a specified domain extent.
     pub fn from_image(image: &Image, domain_extent: Vec2) -> Option<Self> {
@@ -24,27 +79,21 @@ impl ImageTexture {
     }
 
     /// Creates an [`ImageTexture`] snapshot from a Bevy [`Image`] and maps it to a specified domain.
+    ///
+    /// Supported formats are the 8-bit `R8Unorm`, `Rg8Unorm`, `Rgba8Unorm`, and `Bgra8Unorm`
+    /// (and their sRGB variants, sampled as stored), the 16-bit `R16Unorm`, `Rg16Unorm`, and
+    /// `Rgba16Unorm`, and the float `R16Float`, `Rg16Float`, `Rgba16Float`, `R32Float`,
+    /// `Rg32Float`, and `Rgba32Float`. Returns `None` for other formats, including
+    /// block-compressed ones, and for images without CPU-side data.
     pub fn from_image_with_center(
         image: &Image,
         domain_extent: Vec2,
         domain_center: Vec2,
     ) -> Option<Self> {
         let format = image.texture_descriptor.format;
+        let (component, components) = texel_layout(format)?;
 
-        let supported = matches!(
-            format,
-            TextureFormat::R8Unorm
-                | TextureFormat::Rgba8Unorm
-                | TextureFormat::Rgba8UnormSrgb
-                | TextureFormat::Bgra8Unorm
-                | TextureFormat::Bgra8UnormSrgb
-        );
-
-        if !supported {
-            return None;
-        }
-
-        let pixels = Arc::new(image.data.clone().unwrap());
+        let pixels = Arc::new(image.data.clone()?);
         let width = image.texture_descriptor.size.width;
         let height = image.texture_descriptor.size.height;
 
@@ -52,55 +101,57 @@ impl ImageTexture {
             domain_extent,
             domain_center,
             format,
+            component,
+            components,
             pixels,
             width,
             height,
         })
     }
 
-    #[inline]
-    fn bytes_per_pixel(&self) -> usize {
-        match self.format {
-            TextureFormat::R8Unorm => 1,
-            TextureFormat::Rgba8Unorm
-            | TextureFormat::Rgba8UnormSrgb
-            | TextureFormat::Bgra8Unorm
-            | TextureFormat::Bgra8UnormSrgb => 4,
-            _ => 0,
-        }
-    }
-
+    /// Returns the offset of a channel within a texel, or `None` for a missing `B` or `A`.
     #[inline]
     fn channel_offset(&self, channel: TextureChannel) -> Option<usize> {
         match self.format {
-            TextureFormat::R8Unorm => match channel {
-                TextureChannel::R => Some(0),
-                _ => None,
-            },
-            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => match channel {
-                TextureChannel::R => Some(0),
-                TextureChannel::G => Some(1),
-                TextureChannel::B => Some(2),
-                TextureChannel::A => Some(3),
-            },
             TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => match channel {
                 TextureChannel::B => Some(0),
                 TextureChannel::G => Some(1),
                 TextureChannel::R => Some(2),
                 TextureChannel::A => Some(3),
             },
-            _ => None,
+            _ => match (self.components, channel) {
+                (1, TextureChannel::R | TextureChannel::G | TextureChannel::B) => Some(0),
+                (_, TextureChannel::R) => Some(0),
+                (_, TextureChannel::G) => Some(1),
+                (3 | 4, TextureChannel::B) => Some(2),
+                (4, TextureChannel::A) => Some(3),
+                _ => None,
+            },
+        }
+    }
+
+    #[inline]
+    fn texel(&self, offset: usize, x: usize, y: usize) -> f32 {
+        let size = self.component.size();
+        let idx = (y * (self.width as usize) + x) * self.components + offset;
+        let Some(bytes) = self.pixels.get(idx * size..(idx + 1) * size) else {
+            return 0.0;
+        };
+        match self.component {
+            Component::U8 => bytes[0] as f32 / 255.0,
+            Component::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+            Component::F16 => f16::from_le_bytes([bytes[0], bytes[1]]).to_f32(),
+            Component::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
         }
     }
 }
 
 impl Texture for ImageTexture {
     fn sample(&self, channel: TextureChannel, p: Vec2) -> f32 {
-        let bpp = self.bytes_per_pixel();
-        if bpp == 0 {
-            return 0.0;
-        }
+        self.sample_with(channel, p, &TextureSampler::default())
+    }
 
+    fn sample_with(&self, channel: TextureChannel, p: Vec2, sampler: &TextureSampler) -> f32 {
         // Map world/domain coordinates to image texels using a centered domain, like overlays.
         let (w, h) = (self.width, self.height);
         if w == 0 || h == 0 {
@@ -110,20 +161,270 @@ impl Texture for ImageTexture {
         if dw == 0.0 || dh == 0.0 {
             return 0.0;
         }
+        let Some(co) = self.channel_offset(channel) else {
+            return if channel == TextureChannel::A {
+                1.0
+            } else {
+                0.0
+            };
+        };
+
         let local = p - self.domain_center;
-        let u = ((local.x / dw) + 0.5).clamp(0.0, 1.0);
-        let v = ((local.y / dh) + 0.5).clamp(0.0, 1.0);
-        let x = ((u * w as f32) as u32).min(w.saturating_sub(1));
-        let y = ((v * h as f32) as u32).min(h.saturating_sub(1));
+        let x = ((local.x / dw) + 0.5) * w as f32;
+        let y = ((local.y / dh) + 0.5) * h as f32;
+        sampler.sample_texels(w as usize, h as usize, x, y, |ix, iy| {
+            self.texel(co, ix, iy)
+        })
+    }
+}
 
-        let idx = (y as usize) * (self.width as usize) + (x as usize);
-        let base = idx * bpp;
+/// Image asset registered as a texture through [`ScatterLiveTextures`].
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct LiveImageTexture {
+    /// Source image asset.
+    pub image: Handle<Image>,
+    /// Domain extent the image is mapped to.
+    pub domain_extent: Vec2,
+    /// Domain center the image is mapped to.
+    pub domain_center: Vec2,
+}
 
-        let Some(co) = self.channel_offset(channel) else {
-            return 0.0;
+impl LiveImageTexture {
+    pub fn new(image: Handle<Image>, domain_extent: Vec2) -> Self {
+        Self {
+            image,
+            domain_extent,
+            domain_center: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_domain_center(mut self, domain_center: Vec2) -> Self {
+        self.domain_center = domain_center;
+        self
+    }
+}
+
+/// Image assets kept in sync with the [`ScatterTextureRegistry`].
+///
+/// Whenever a registered [`Image`] is loaded or modified, a new [`ImageTexture`] snapshot
+/// replaces the texture in the registry. Runs already in progress keep the previous snapshot,
+/// and streamed chunks are scattered again. Images need CPU-side data, so keep
+/// `RenderAssetUsages::MAIN_WORLD` on them.
+#[derive(Resource, Default)]
+pub struct ScatterLiveTextures {
+    textures: HashMap<String, LiveImageTexture>,
+    pending: HashSet<String>,
+}
+
+impl ScatterLiveTextures {
+    /// Registers an image under the given texture id, replacing any previous one.
+    pub fn insert(&mut self, id: impl Into<String>, texture: LiveImageTexture) {
+        let id = id.into();
+        self.pending.insert(id.clone());
+        self.textures.insert(id, texture);
+    }
+
+    /// Removes an image and its texture from the registry.
+    pub fn remove(&mut self, id: &str) -> Option<LiveImageTexture> {
+        let texture = self.textures.remove(id)?;
+        self.pending.insert(id.to_string());
+        Some(texture)
+    }
+
+    /// Returns the image registered under the given texture id.
+    pub fn get(&self, id: &str) -> Option<&LiveImageTexture> {
+        self.textures.get(id)
+    }
+
+    /// Iterates over the registered texture ids and images.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &LiveImageTexture)> {
+        self.textures
+            .iter()
+            .map(|(id, texture)| (id.as_str(), texture))
+    }
+}
+
+/// Re-snapshots live textures whose image changed and swaps in the updated registry.
+pub(crate) fn sync_live_textures(
+    mut live: ResMut<ScatterLiveTextures>,
+    mut image_events: MessageReader<AssetEvent<Image>>,
+    images: Option<Res<Assets<Image>>>,
+    mut registry: ResMut<ScatterTextureRegistry>,
+) {
+    let mut changed_images = HashSet::new();
+    for event in image_events.read() {
+        match *event {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::Removed { id }
+            | AssetEvent::LoadedWithDependencies { id } => {
+                changed_images.insert(id);
+            }
+            AssetEvent::Unused { .. } => {}
+        }
+    }
+
+    let live = live.as_mut();
+    let mut dirty: HashSet<String> = live.pending.drain().collect();
+    for (id, texture) in &live.textures {
+        if changed_images.contains(&texture.image.id()) {
+            dirty.insert(id.clone());
+        }
+    }
+    if dirty.is_empty() {
+        return;
+    }
+
+    // Runs in progress keep the previous registry, so the update goes into a copy.
+    let mut textures = TextureRegistry::with_capacity(registry.0.len());
+    textures.extend_from(&registry.0);
+    for id in dirty {
+        let image = live.textures.get(&id).and_then(|texture| {
+            let image = images.as_ref()?.get(&texture.image)?;
+            Some((texture, image))
+        });
+        let snapshot = image.and_then(|(texture, image)| {
+            let snapshot = ImageTexture::from_image_with_center(
+                image,
+                texture.domain_extent,
+                texture.domain_center,
+            );
+            if snapshot.is_none() {
+                warn!(
+                    "Live texture '{}' has an unsupported format ({:?}) or no CPU-side data",
+                    id, image.texture_descriptor.format
+                );
+            }
+            snapshot
+        });
+        match snapshot {
+            Some(snapshot) => textures.register(id, snapshot),
+            None => {
+                textures.unregister(&id);
+            }
+        }
+    }
+    registry.0 = Arc::new(textures);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension};
+
+    use super::*;
+
+    fn image(width: u32, height: u32, format: TextureFormat, data: Vec<u8>) -> Image {
+        Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            format,
+            RenderAssetUsages::MAIN_WORLD,
+        )
+    }
+
+    fn bytes<T: Copy, const N: usize>(values: &[T], to_le: fn(T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(|&v| to_le(v)).collect()
+    }
+
+    #[test]
+    fn samples_16_bit_and_float_formats() {
+        let extent = Vec2::new(2.0, 1.0);
+        let left = Vec2::new(-0.5, 0.0);
+        let right = Vec2::new(0.5, 0.0);
+
+        let r16 = image(
+            2,
+            1,
+            TextureFormat::R16Unorm,
+            bytes(&[0u16, 65535], u16::to_le_bytes),
+        );
+        let texture = ImageTexture::from_image(&r16, extent).unwrap();
+        assert_eq!(texture.sample(TextureChannel::R, left), 0.0);
+        assert_eq!(texture.sample(TextureChannel::R, right), 1.0);
+        assert_eq!(texture.sample(TextureChannel::G, right), 1.0);
+        assert_eq!(texture.sample(TextureChannel::B, left), 0.0);
+        assert_eq!(texture.sample(TextureChannel::A, left), 1.0);
+
+        let r32 = image(
+            2,
+            1,
+            TextureFormat::R32Float,
+            bytes(&[-2.5f32, 40.0], f32::to_le_bytes),
+        );
+        let texture = ImageTexture::from_image(&r32, extent).unwrap();
+        assert_eq!(texture.sample(TextureChannel::R, left), -2.5);
+        assert_eq!(texture.sample(TextureChannel::R, right), 40.0);
+
+        let rgba16f = image(
+            1,
+            1,
+            TextureFormat::Rgba16Float,
+            bytes(
+                &[0.25f32, 0.5, 2.0, 1.0].map(f16::from_f32),
+                f16::to_le_bytes,
+            ),
+        );
+        let texture = ImageTexture::from_image(&rgba16f, Vec2::ONE).unwrap();
+        assert_eq!(texture.sample(TextureChannel::G, Vec2::ZERO), 0.5);
+        assert_eq!(texture.sample(TextureChannel::B, Vec2::ZERO), 2.0);
+
+        let rg8 = image(1, 1, TextureFormat::Rg8Unorm, vec![51, 255]);
+        let texture = ImageTexture::from_image(&rg8, Vec2::ONE).unwrap();
+        assert_eq!(texture.sample(TextureChannel::R, Vec2::ZERO), 0.2);
+        assert_eq!(texture.sample(TextureChannel::G, Vec2::ZERO), 1.0);
+        assert_eq!(texture.sample(TextureChannel::B, Vec2::ZERO), 0.0);
+        assert_eq!(texture.sample(TextureChannel::A, Vec2::ZERO), 1.0);
+
+        let bc1 = image(4, 4, TextureFormat::Bc1RgbaUnorm, vec![0; 8]);
+        assert!(ImageTexture::from_image(&bc1, Vec2::ONE).is_none());
+    }
+
+    #[test]
+    fn live_textures_follow_their_image() {
+        let mut app = App::new();
+        app.add_message::<AssetEvent<Image>>()
+            .init_resource::<ScatterTextureRegistry>()
+            .init_resource::<ScatterLiveTextures>()
+            .add_systems(Update, sync_live_textures);
+
+        let mut images = Assets::<Image>::default();
+        let handle = images.add(image(1, 1, TextureFormat::R8Unorm, vec![0]));
+        app.world_mut().insert_resource(images);
+        app.world_mut()
+            .resource_mut::<ScatterLiveTextures>()
+            .insert("height", LiveImageTexture::new(handle.clone(), Vec2::ONE));
+        app.update();
+
+        let sample = |app: &App| {
+            let registry = &app.world().resource::<ScatterTextureRegistry>().0;
+            registry.sample("height", TextureChannel::R, Vec2::ZERO)
         };
+        assert_eq!(sample(&app), 0.0);
+
+        app.world_mut()
+            .resource_mut::<Assets<Image>>()
+            .insert(&handle, image(1, 1, TextureFormat::R8Unorm, vec![255]))
+            .unwrap();
+        app.world_mut()
+            .write_message(AssetEvent::Modified { id: handle.id() });
+        app.update();
+        assert_eq!(sample(&app), 1.0);
 
-        let byte = self.pixels.get(base + co).copied().unwrap_or(0);
-        (byte as f32) / 255.0
+        app.world_mut()
+            .resource_mut::<ScatterLiveTextures>()
+            .remove("height");
+        app.update();
+        assert!(!app
+            .world()
+            .resource::<ScatterTextureRegistry>()
+            .0
+            .contains("height"));
     }
 }
@@ -168,6 +168,7 @@ fn setup(
         Transform::from_translation(Vec3::ZERO),
         ScatterStreamSettings::new(plan, chunk_size, view_radius, 7)
             .with_focus_offset(Vec2::new(0.0, STREAM_FOCUS_Y))
+            .with_halo(24.0)
             .with_raster_cell_size(1.0)
             .with_grid_halo(1),
     ));
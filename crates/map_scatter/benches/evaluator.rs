@@ -4,13 +4,17 @@ use std::hint::black_box;
 
 use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use glam::Vec2;
+use map_scatter::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
+use map_scatter::fieldgraph::runtime::FieldRuntime;
 use map_scatter::prelude::{
-    FieldGraphSpec, FieldProgramCache, FieldSemantics, Kind, NodeSpec, TextureRegistry,
+    FieldGraphSpec, FieldProgramCache, FieldSemantics, FractalKind, Kind, NodeSpec, NoiseKind,
+    TextureRegistry,
 };
 use map_scatter::scatter::chunk::chunk_id_and_grid_for_position_centered;
 use map_scatter::scatter::evaluator::Evaluator;
 
 const TYPE_COUNTS: [usize; 6] = [1, 4, 16, 64, 128, 256];
+const BATCH_SIZES: [usize; 4] = [1, 64, 1024, 4096];
 
 fn make_type_spec(probability: f32) -> FieldGraphSpec {
     let mut spec = FieldGraphSpec::default();
@@ -55,6 +59,29 @@ fn make_type_spec_complex(probability: f32) -> FieldGraphSpec {
     spec
 }
 
+fn make_positional_spec() -> FieldGraphSpec {
+    let mut spec = FieldGraphSpec::default();
+
+    // Position-dependent graph that the optimizer cannot fold away.
+    spec.add(
+        "noise",
+        NodeSpec::fractal_noise(NoiseKind::Perlin, FractalKind::Fbm, 7, 0.02, 4),
+    );
+    spec.add(
+        "ramp",
+        NodeSpec::linear_gradient(Vec2::new(-64.0, 0.0), Vec2::new(64.0, 0.0)),
+    );
+    spec.add("x", NodeSpec::position_x());
+    spec.add("x_scaled", NodeSpec::scale("x".into(), 1.0 / 64.0));
+    spec.add("mix", NodeSpec::mul(vec!["noise".into(), "ramp".into()]));
+    spec.add("sum", NodeSpec::add(vec!["mix".into(), "x_scaled".into()]));
+    spec.add("shaped", NodeSpec::smoothstep("sum".into(), 0.1, 0.9));
+    spec.add("probability", NodeSpec::clamp("shaped".into(), 0.0, 1.0));
+    spec.set_semantics("probability", FieldSemantics::Probability);
+
+    spec
+}
+
 fn make_kinds_complex(count: usize) -> Vec<Kind> {
     (0..count)
         .map(|i| {
@@ -196,6 +223,60 @@ fn evaluator_run_benches(c: &mut Criterion) {
     group.finish();
 }
 
+fn field_runtime_benches(c: &mut Criterion) {
+    let domain_extent = Vec2::new(128.0, 128.0);
+    let (chunk, grid) =
+        chunk_id_and_grid_for_position_centered(Vec2::ZERO, domain_extent, 128.0, 1.0, 2);
+    let textures = TextureRegistry::new();
+    let program = FieldGraphCompiler::compile(&make_positional_spec(), &CompileOptions::default())
+        .expect("compile ok");
+    let program = std::sync::Arc::new(program);
+
+    let mut group = c.benchmark_group("evaluator/field_runtime");
+    for &batch_size in &BATCH_SIZES {
+        let side = (batch_size as f32).sqrt().ceil() as usize;
+        let mut positions = generate_grid_positions(domain_extent, side, side);
+        positions.truncate(batch_size);
+        let mut out = vec![0.0; positions.len()];
+
+        group.throughput(common::elements_throughput(batch_size));
+
+        group.bench_with_input(
+            BenchmarkId::new("sample", batch_size),
+            &batch_size,
+            |b, _| {
+                let mut runtime = FieldRuntime::new(program.clone(), &textures);
+                b.iter(|| {
+                    for (&pos, value) in positions.iter().zip(out.iter_mut()) {
+                        *value = runtime.sample("probability", black_box(pos), chunk, &grid);
+                    }
+                    black_box(&out);
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("sample_batch", batch_size),
+            &batch_size,
+            |b, _| {
+                let mut runtime = FieldRuntime::new(program.clone(), &textures);
+                b.iter(|| {
+                    runtime.sample_batch(
+                        "probability",
+                        black_box(&positions),
+                        chunk,
+                        &grid,
+                        &mut out,
+                    );
+                    black_box(&out);
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn evaluator_compile_benches(c: &mut Criterion) {
     let mut group = c.benchmark_group("evaluator/compile");
 
@@ -324,6 +405,6 @@ fn evaluator_compile_benches(c: &mut Criterion) {
 criterion_group! {
     name = benches;
     config = common::default_criterion();
-    targets = evaluator_compile_benches, evaluator_run_benches
+    targets = evaluator_compile_benches, evaluator_run_benches, field_runtime_benches
 }
 criterion_main!(benches);
@@ -6,7 +6,8 @@ use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use glam::Vec2;
 use map_scatter::prelude::{
     run_plan, FieldGraphSpec, FieldProgramCache, FieldSemantics, JitterGridSampling, Layer,
-    NodeSpec, Plan, PoissonDiskSampling, PositionSampling, RunConfig, TextureRegistry,
+    NodeSpec, Plan, PoissonDiskSampling, PositionSampling, RunConfig, Texture, TextureChannel,
+    TextureRegistry,
 };
 use rand::rngs::StdRng;
 use rand::SeedableRng;
@@ -23,6 +24,36 @@ fn make_scatter_kind_spec(probability: f32) -> FieldGraphSpec {
     spec
 }
 
+struct WaveTexture;
+
+impl Texture for WaveTexture {
+    fn sample(&self, _channel: TextureChannel, p: Vec2) -> f32 {
+        ((p.x * 0.05).sin() * (p.y * 0.03).cos()) * 0.5 + 0.5
+    }
+}
+
+/// Kind reading the same texture and distance field as its siblings, with its own thresholds.
+fn make_shared_kind_spec(threshold: f32, probability: f32) -> FieldGraphSpec {
+    let mut spec = FieldGraphSpec::default();
+
+    spec.add("height", NodeSpec::texture("height", TextureChannel::R));
+    spec.add("near", NodeSpec::edt_normalize("height".into(), 0.5, 16.0));
+    spec.add_with_semantics(
+        "gate",
+        NodeSpec::step("near".into(), threshold),
+        FieldSemantics::Gate,
+    );
+
+    spec.add("base", NodeSpec::constant(probability));
+    spec.add_with_semantics(
+        "probability",
+        NodeSpec::mul(vec!["near".into(), "base".into()]),
+        FieldSemantics::Probability,
+    );
+
+    spec
+}
+
 fn build_plan(
     num_stages: usize,
     layers_per_stage: usize,
@@ -144,6 +175,53 @@ fn bench_with_sampling_params(
     group.finish();
 }
 
+/// One layer whose kinds share a texture and a baked distance field, which the layer evaluates
+/// once per chunk for all kinds.
+fn bench_shared_fields(c: &mut Criterion, bench_name: &str, kinds_per_layer: usize) {
+    let kinds = (0..kinds_per_layer)
+        .map(|t| {
+            let threshold = 0.1 + t as f32 / kinds_per_layer as f32 * 0.5;
+            let probability = 0.2 + (t as f32 + 1.0) / (kinds_per_layer as f32 + 1.0) * 0.8;
+            map_scatter::scatter::Kind::new(
+                format!("shared_t{t}"),
+                make_shared_kind_spec(threshold, probability),
+            )
+        })
+        .collect();
+    let sampling = Box::new(JitterGridSampling::new(0.75, 6.0));
+    let plan = Plan::new().with_layer(Layer::new("shared", kinds, sampling));
+
+    let config = RunConfig::new(Vec2::new(1024.0, 1024.0))
+        .with_chunk_extent(128.0)
+        .with_raster_cell_size(1.0)
+        .with_grid_halo(2);
+
+    let mut textures = TextureRegistry::new();
+    textures.register("height", WaveTexture);
+    let cache = FieldProgramCache::new();
+
+    let mut group = c.benchmark_group(bench_name);
+
+    // Preview a run to set meaningful throughput in "placements per iteration".
+    let mut rng_preview = StdRng::seed_from_u64(0x5EED5EED);
+    let preview = run_plan(&plan, &config, &textures, &cache, &mut rng_preview, None);
+    group.throughput(common::elements_throughput(preview.placements.len()));
+
+    group.bench_function("run_plan", |b| {
+        b.iter_batched(
+            || StdRng::seed_from_u64(12345),
+            |mut rng| {
+                let result = run_plan(&plan, &config, &textures, &cache, &mut rng, None);
+                black_box(result.positions_evaluated);
+                black_box(result.placements.len());
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
 pub fn scatter_benches(c: &mut Criterion) {
     // Default size, simple plan
     bench_with_sampling(c, "scatter/jitter_grid/1024/simple", |sep| {
@@ -220,6 +298,8 @@ pub fn scatter_benches(c: &mut Criterion) {
         6.0,
         |sep| Box::new(PoissonDiskSampling { radius: sep }),
     );
+
+    bench_shared_fields(c, "scatter/shared_fields/1024/8_kinds", 8);
 }
 
 criterion_group! {
@@ -2,7 +2,7 @@
 //!
 //! This module defines [`enum@crate::error::Error`] and the crate-wide [Result] alias. Variants cover
 //! invalid configuration, field graph compile/runtime failures, missing resources,
-//! IO, and generic errors.
+//! IO, image decoding, and generic errors.
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -47,6 +47,14 @@ pub enum Error {
         std::io::Error,
     ),
 
+    #[cfg(feature = "image")]
+    #[error(transparent)]
+    Image(
+        /// Source image decoding error.
+        #[from]
+        image::ImageError,
+    ),
+
     #[error("{0}")]
     Other(
         /// Generic error message.
@@ -0,0 +1,350 @@
+//! Flat, index-addressed instruction form of compiled field programs.
+//!
+//! [`Bytecode`] assigns every node of a [`crate::fieldgraph::FieldProgram`] a slot and stores
+//! its operation with the slots of its inputs. For each sampled field, a [`Routine`] lists the
+//! steps needed to compute it in dependency order, with inputs addressed by row. The
+//! [`crate::fieldgraph::runtime::FieldRuntime`] executes routines over batches of positions,
+//! one contiguous row of values per step.
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use tracing::warn;
+
+use crate::fieldgraph::filter::RasterFilter;
+use crate::fieldgraph::math::MathOp;
+use crate::fieldgraph::program::NodeMeta;
+use crate::fieldgraph::{
+    Axis, CurveParams, DistanceParams, FieldId, LinearGradientParams, LutParams, NodeSpec,
+    NoiseParams, RadialGradientParams, RemapParams, TextureChannel, TextureSampler,
+};
+
+/// Operation of a single instruction.
+#[derive(Clone, Debug)]
+pub(crate) enum Op {
+    Constant(f32),
+    Texture(String, TextureChannel, TextureSampler),
+    Noise(NoiseParams),
+    Position(Axis),
+    Distance(DistanceParams),
+    LinearGradient(LinearGradientParams),
+    RadialGradient(RadialGradientParams),
+    /// Samples the source slot at the position offset by the two offset rows.
+    DomainWarp {
+        source: u32,
+        amplitude: f32,
+    },
+    Add,
+    Sub,
+    Mul,
+    Min,
+    Max,
+    Invert,
+    /// Elementwise math over all input rows.
+    Math(MathOp),
+    Scale(f32),
+    Clamp(f32, f32),
+    SmoothStep(f32, f32),
+    Pow(f32),
+    Remap(RemapParams),
+    Curve(CurveParams),
+    Step(f32),
+    Lut(LutParams),
+    /// Reads the raster computed by a filter over the baked input slot.
+    Filter(RasterFilter),
+}
+
+/// Instruction computing the value of one slot.
+#[derive(Clone, Debug)]
+pub(crate) struct Instr {
+    pub(crate) op: Op,
+    /// Input slots in node order.
+    pub(crate) inputs: Vec<u32>,
+    /// Whether the slot is sampled from a raster baked per chunk.
+    pub(crate) baked: bool,
+    /// Sampler used to read the baked raster.
+    pub(crate) sampler: TextureSampler,
+}
+
+/// Step of a [`Routine`].
+#[derive(Clone, Debug)]
+pub(crate) struct Step {
+    pub(crate) slot: u32,
+    /// Rows of the step's arguments; the offset rows for domain warps.
+    pub(crate) args: Vec<u32>,
+    /// Whether the step reads the baked raster of its slot instead of computing it.
+    pub(crate) lookup: bool,
+}
+
+/// Steps computing a slot from its inputs; the last step computes the slot itself.
+#[derive(Clone, Debug)]
+pub(crate) struct Routine {
+    pub(crate) steps: Vec<Step>,
+}
+
+/// Index-addressed instructions of a field program.
+#[derive(Clone, Debug)]
+pub struct Bytecode {
+    instrs: Vec<Instr>,
+    slots: HashMap<FieldId, u32>,
+    routines: Vec<OnceLock<Routine>>,
+}
+
+impl Bytecode {
+    /// Builds the instructions for `nodes`, assigning slots in the order of `topo`.
+    pub fn new(nodes: &HashMap<FieldId, NodeMeta>, topo: &[FieldId]) -> Self {
+        let mut slots: HashMap<FieldId, u32> = HashMap::with_capacity(nodes.len());
+        for id in topo
+            .iter()
+            .filter(|id| nodes.contains_key(*id))
+            .chain(nodes.keys())
+        {
+            let next = slots.len() as u32;
+            slots.entry(id.clone()).or_insert(next);
+        }
+
+        let mut ordered: Vec<(&FieldId, u32)> = slots.iter().map(|(id, s)| (id, *s)).collect();
+        ordered.sort_unstable_by_key(|(_, s)| *s);
+
+        let mut instrs: Vec<Instr> = ordered
+            .iter()
+            .map(|(id, _)| {
+                let meta = &nodes[*id];
+                Instr {
+                    op: Op::Constant(0.0),
+                    inputs: Vec::new(),
+                    baked: meta.force_bake || meta.spec.is_raster_filter(),
+                    sampler: meta.raster_sampler,
+                }
+            })
+            .collect();
+
+        // Unknown inputs read as zero, like unknown fields.
+        let mut missing: Option<u32> = None;
+        let mut slot_of = |instrs: &mut Vec<Instr>, id: &FieldId| -> u32 {
+            if let Some(slot) = slots.get(id) {
+                return *slot;
+            }
+            warn!("Unknown field '{}'.", id);
+            *missing.get_or_insert_with(|| {
+                instrs.push(Instr {
+                    op: Op::Constant(0.0),
+                    inputs: Vec::new(),
+                    baked: false,
+                    sampler: TextureSampler::default(),
+                });
+                (instrs.len() - 1) as u32
+            })
+        };
+
+        for (id, slot) in &ordered {
+            let spec = &nodes[*id].spec;
+            let inputs: Vec<u32> = spec
+                .inputs()
+                .iter()
+                .map(|input| slot_of(&mut instrs, input))
+                .collect();
+            let op = match spec {
+                NodeSpec::Constant { params } => Op::Constant(params.value),
+                NodeSpec::Texture { params } => {
+                    Op::Texture(params.texture_id.clone(), params.channel, params.sampler)
+                }
+                NodeSpec::Noise { params } => Op::Noise(params.clone()),
+                NodeSpec::Position { params } => Op::Position(params.axis),
+                NodeSpec::Distance { params } => Op::Distance(params.clone()),
+                NodeSpec::LinearGradient { params } => Op::LinearGradient(params.clone()),
+                NodeSpec::RadialGradient { params } => Op::RadialGradient(params.clone()),
+                NodeSpec::Expr { .. } | NodeSpec::Subgraph { .. } => {
+                    unreachable!("expression and subgraph nodes are inlined by the compiler")
+                }
+                NodeSpec::DomainWarp { params, .. } => Op::DomainWarp {
+                    source: inputs[0],
+                    amplitude: params.amplitude,
+                },
+                NodeSpec::Add { .. } => Op::Add,
+                NodeSpec::Sub { .. } => Op::Sub,
+                NodeSpec::Mul { .. } => Op::Mul,
+                NodeSpec::Min { .. } => Op::Min,
+                NodeSpec::Max { .. } => Op::Max,
+                NodeSpec::Invert { .. } => Op::Invert,
+                NodeSpec::Div { .. }
+                | NodeSpec::Mod { .. }
+                | NodeSpec::Abs { .. }
+                | NodeSpec::Sqrt { .. }
+                | NodeSpec::Sin { .. }
+                | NodeSpec::Cos { .. }
+                | NodeSpec::Tan { .. }
+                | NodeSpec::Atan2 { .. }
+                | NodeSpec::Floor { .. }
+                | NodeSpec::Fract { .. }
+                | NodeSpec::Sign { .. }
+                | NodeSpec::Lerp { .. }
+                | NodeSpec::Select { .. }
+                | NodeSpec::Greater { .. }
+                | NodeSpec::Less { .. }
+                | NodeSpec::And { .. }
+                | NodeSpec::Or { .. }
+                | NodeSpec::Not { .. } => Op::Math(MathOp::from_spec(spec).expect("math node")),
+                NodeSpec::Scale { params, .. } => Op::Scale(params.factor),
+                NodeSpec::Clamp { params, .. } => Op::Clamp(params.min, params.max),
+                NodeSpec::SmoothStep { params, .. } => Op::SmoothStep(params.edge0, params.edge1),
+                NodeSpec::Pow { params, .. } => Op::Pow(params.exp),
+                NodeSpec::Remap { params, .. } => Op::Remap(params.clone()),
+                NodeSpec::Curve { params, .. } => Op::Curve(params.clone()),
+                NodeSpec::Step { params, .. } => Op::Step(params.threshold),
+                NodeSpec::Lut { params, .. } => Op::Lut(params.clone()),
+                NodeSpec::EdtNormalize { params, .. } => Op::Filter(RasterFilter::EdtNormalize {
+                    threshold: params.threshold,
+                    d_max: params.d_max,
+                }),
+                NodeSpec::EdtSigned { params, .. } => Op::Filter(RasterFilter::EdtSigned {
+                    threshold: params.threshold,
+                    d_max: params.d_max,
+                }),
+                NodeSpec::Blur { params, .. } => Op::Filter(RasterFilter::Blur(params.clone())),
+                NodeSpec::Morphology { params, .. } => {
+                    Op::Filter(RasterFilter::Morphology(params.clone()))
+                }
+                NodeSpec::Convolve { params, .. } => {
+                    Op::Filter(RasterFilter::Convolve(params.clone()))
+                }
+                NodeSpec::Derivative { params, .. } => {
+                    Op::Filter(RasterFilter::Derivative(params.clone()))
+                }
+            };
+            let instr = &mut instrs[*slot as usize];
+            instr.op = op;
+            instr.inputs = inputs;
+        }
+
+        let routines = (0..instrs.len()).map(|_| OnceLock::new()).collect();
+        Self {
+            instrs,
+            slots,
+            routines,
+        }
+    }
+
+    /// Returns the slot of a field.
+    #[inline]
+    pub fn slot(&self, field: &str) -> Option<u32> {
+        self.slots.get(field).copied()
+    }
+
+    /// Returns the number of instructions.
+    pub fn len(&self) -> usize {
+        self.instrs.len()
+    }
+
+    /// Returns `true` if there are no instructions.
+    pub fn is_empty(&self) -> bool {
+        self.instrs.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn instr(&self, slot: u32) -> &Instr {
+        &self.instrs[slot as usize]
+    }
+
+    /// Returns the routine computing `slot` from its inputs, building it on first use.
+    pub(crate) fn routine(&self, slot: u32) -> &Routine {
+        self.routines[slot as usize].get_or_init(|| self.build_routine(slot))
+    }
+
+    /// Orders the dependencies of `target` depth-first. Baked inputs are read from rasters,
+    /// and domain warp sources are evaluated separately at the warped positions.
+    fn build_routine(&self, target: u32) -> Routine {
+        let mut rows: HashMap<u32, u32> = HashMap::new();
+        let mut steps: Vec<Step> = Vec::new();
+        let mut stack: Vec<(u32, bool)> = vec![(target, false)];
+
+        while let Some((slot, expanded)) = stack.pop() {
+            if rows.contains_key(&slot) {
+                continue;
+            }
+            let instr = self.instr(slot);
+            let lookup = slot != target && instr.baked;
+            let deps: &[u32] = match (&instr.op, lookup) {
+                (_, true) => &[],
+                (Op::DomainWarp { .. }, false) => &instr.inputs[1..],
+                _ => &instr.inputs,
+            };
+
+            if expanded {
+                let args = deps.iter().map(|dep| rows[dep]).collect();
+                rows.insert(slot, steps.len() as u32);
+                steps.push(Step { slot, args, lookup });
+            } else {
+                stack.push((slot, true));
+                stack.extend(
+                    deps.iter()
+                        .rev()
+                        .filter(|dep| !rows.contains_key(dep))
+                        .map(|dep| (*dep, false)),
+                );
+            }
+        }
+
+        Routine { steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
+    use crate::prelude::FieldGraphSpec;
+
+    #[test]
+    fn routines_compute_inputs_before_consumers() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("half", NodeSpec::scale("x".into(), 0.5));
+        spec.add(
+            "sum",
+            NodeSpec::add(vec!["x".into(), "half".into(), "x".into()]),
+        );
+        spec.add("unrelated", NodeSpec::constant(1.0));
+
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+        let code = program.bytecode();
+        let sum = code.slot("sum").unwrap();
+        let routine = code.routine(sum);
+
+        let slots: Vec<u32> = routine.steps.iter().map(|s| s.slot).collect();
+        assert_eq!(
+            slots,
+            vec![code.slot("x").unwrap(), code.slot("half").unwrap(), sum]
+        );
+        assert_eq!(routine.steps[2].args, vec![0, 1, 0]);
+        assert!(routine.steps.iter().all(|s| !s.lookup));
+    }
+
+    #[test]
+    fn routines_stop_at_baked_inputs_and_warp_sources() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("mask", NodeSpec::scale("x".into(), 2.0));
+        spec.add("edt", NodeSpec::edt_normalize("mask".into(), 0.5, 4.0));
+        spec.add("offset", NodeSpec::constant(1.0));
+        spec.add(
+            "warped",
+            NodeSpec::domain_warp("mask".into(), "offset".into(), "edt".into(), 1.0),
+        );
+
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+        let code = program.bytecode();
+        let routine = code.routine(code.slot("warped").unwrap());
+
+        let slots: Vec<u32> = routine.steps.iter().map(|s| s.slot).collect();
+        assert_eq!(
+            slots,
+            vec![
+                code.slot("offset").unwrap(),
+                code.slot("edt").unwrap(),
+                code.slot("warped").unwrap()
+            ]
+        );
+        assert!(routine.steps[1].lookup);
+        assert_eq!(routine.steps[2].args, vec![0, 1]);
+    }
+}
@@ -7,6 +7,9 @@
 //! - Look up a program with [`FieldProgramCache::get_or_compile`] by passing a [`Kind`]
 //!   and [`CompileOptions`].
 //! - Reuse cached programs across scatter runs to avoid recompilation.
+//!
+//! Subgraphs instantiated by several kinds are expanded once and shared between their
+//! compilations, as long as the kinds define the same subgraph library.
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
@@ -14,7 +17,12 @@ use std::sync::{Arc, RwLock};
 
 use crate::error::Result;
 use crate::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
-use crate::fieldgraph::FieldProgram;
+use crate::fieldgraph::spec::SubgraphSpec;
+use crate::fieldgraph::subgraph::FragmentCache;
+use crate::fieldgraph::{
+    Axis, BlurKernel, CurveInterpolation, DerivativeKind, DistanceShape, FieldProgram, FractalKind,
+    MorphologyOp, NoiseKind,
+};
 use crate::prelude::{FieldGraphSpec, FieldSemantics, NodeSpec, TextureChannel};
 use crate::scatter::{Kind, KindId};
 
@@ -27,6 +35,7 @@ struct ProgramEntry {
 /// This cache is thread-safe and can be shared across runs.
 pub struct FieldProgramCache {
     entries: RwLock<HashMap<KindId, ProgramEntry>>,
+    fragments: FragmentCache,
 }
 
 impl FieldProgramCache {
@@ -34,6 +43,7 @@ impl FieldProgramCache {
     pub fn new() -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
+            fragments: FragmentCache::default(),
         }
     }
 
@@ -82,6 +92,7 @@ impl FieldProgramCache {
             .write()
             .expect("FieldProgramCache lock poisoned");
         entries.clear();
+        self.fragments.clear();
     }
 
     /// Gets the compiled program for the given [`Kind`], compiling and caching it if necessary.
@@ -101,7 +112,9 @@ impl FieldProgramCache {
             }
         }
 
-        let program = Arc::new(FieldGraphCompiler::compile(&kind.spec, opts)?);
+        let (program, _) =
+            FieldGraphCompiler::compile_with_fragments(&kind.spec, opts, Some(&self.fragments))?;
+        let program = Arc::new(program);
 
         let mut entries = self
             .entries
@@ -139,81 +152,304 @@ fn fingerprint(spec: &FieldGraphSpec, opts: &CompileOptions) -> u64 {
         id.hash(&mut hasher);
         let node = &spec.nodes[id];
 
-        let kind_tag: u8 = match node {
-            NodeSpec::Constant { .. } => 1,
-            NodeSpec::Texture { .. } => 2,
-            NodeSpec::Add { .. } => 3,
-            NodeSpec::Mul { .. } => 4,
-            NodeSpec::Min { .. } => 5,
-            NodeSpec::Max { .. } => 6,
-            NodeSpec::Invert { .. } => 7,
-            NodeSpec::Clamp { .. } => 8,
-            NodeSpec::SmoothStep { .. } => 9,
-            NodeSpec::Pow { .. } => 10,
-            NodeSpec::EdtNormalize { .. } => 11,
-            NodeSpec::Sub { .. } => 12,
-            NodeSpec::Scale { .. } => 13,
-        };
-        kind_tag.hash(&mut hasher);
-
         let semantics_tag: u8 = match spec.semantics.get(id) {
             Some(s) => match s {
                 FieldSemantics::Gate => 0,
                 FieldSemantics::Probability => 1,
+                FieldSemantics::Scale => 2,
+                FieldSemantics::Rotation => 3,
+                FieldSemantics::Variant => 4,
+                FieldSemantics::Attribute(name) => {
+                    name.hash(&mut hasher);
+                    5
+                }
             },
             None => 255,
         };
         semantics_tag.hash(&mut hasher);
 
-        for input in node.inputs() {
-            input.hash(&mut hasher);
+        if let Some(sampler) = spec.raster_samplers.get(id) {
+            sampler.key().hash(&mut hasher);
         }
 
-        match node {
-            NodeSpec::Constant { params } => {
-                params.value.to_bits().hash(&mut hasher);
-            }
-            NodeSpec::Texture { params } => {
-                params.texture_id.hash(&mut hasher);
-                let channel_tag: u8 = match params.channel {
-                    TextureChannel::R => 0,
-                    TextureChannel::G => 1,
-                    TextureChannel::B => 2,
-                    TextureChannel::A => 3,
+        hash_node(node, &mut hasher);
+    }
+
+    hash_subgraphs(&spec.subgraphs, &mut hasher);
+
+    if !opts.force_bake.is_empty() {
+        let mut bake_ids: Vec<&str> = opts.force_bake.iter().map(|s| s.as_str()).collect();
+        bake_ids.sort_unstable();
+        for id in bake_ids {
+            id.hash(&mut hasher);
+        }
+    }
+
+    if opts.optimize {
+        opts.optimize.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+/// Hashes the kind, inputs, and parameters of a node.
+pub(crate) fn hash_node(node: &NodeSpec, hasher: &mut DefaultHasher) {
+    let kind_tag: u8 = match node {
+        NodeSpec::Constant { .. } => 1,
+        NodeSpec::Texture { .. } => 2,
+        NodeSpec::Add { .. } => 3,
+        NodeSpec::Mul { .. } => 4,
+        NodeSpec::Min { .. } => 5,
+        NodeSpec::Max { .. } => 6,
+        NodeSpec::Invert { .. } => 7,
+        NodeSpec::Clamp { .. } => 8,
+        NodeSpec::SmoothStep { .. } => 9,
+        NodeSpec::Pow { .. } => 10,
+        NodeSpec::EdtNormalize { .. } => 11,
+        NodeSpec::Sub { .. } => 12,
+        NodeSpec::Scale { .. } => 13,
+        NodeSpec::Noise { .. } => 14,
+        NodeSpec::Position { .. } => 15,
+        NodeSpec::Distance { .. } => 16,
+        NodeSpec::LinearGradient { .. } => 17,
+        NodeSpec::RadialGradient { .. } => 18,
+        NodeSpec::DomainWarp { .. } => 19,
+        NodeSpec::EdtSigned { .. } => 20,
+        NodeSpec::Blur { .. } => 21,
+        NodeSpec::Morphology { .. } => 22,
+        NodeSpec::Convolve { .. } => 23,
+        NodeSpec::Derivative { .. } => 24,
+        NodeSpec::Remap { .. } => 25,
+        NodeSpec::Curve { .. } => 26,
+        NodeSpec::Step { .. } => 27,
+        NodeSpec::Lut { .. } => 28,
+        NodeSpec::Div { .. } => 29,
+        NodeSpec::Mod { .. } => 30,
+        NodeSpec::Abs { .. } => 31,
+        NodeSpec::Sqrt { .. } => 32,
+        NodeSpec::Sin { .. } => 33,
+        NodeSpec::Cos { .. } => 34,
+        NodeSpec::Tan { .. } => 35,
+        NodeSpec::Atan2 { .. } => 36,
+        NodeSpec::Floor { .. } => 37,
+        NodeSpec::Fract { .. } => 38,
+        NodeSpec::Sign { .. } => 39,
+        NodeSpec::Lerp { .. } => 40,
+        NodeSpec::Select { .. } => 41,
+        NodeSpec::Greater { .. } => 42,
+        NodeSpec::Less { .. } => 43,
+        NodeSpec::And { .. } => 44,
+        NodeSpec::Or { .. } => 45,
+        NodeSpec::Not { .. } => 46,
+        NodeSpec::Expr { .. } => 47,
+        NodeSpec::Subgraph { .. } => 48,
+    };
+    kind_tag.hash(hasher);
+
+    for input in node.inputs() {
+        input.hash(hasher);
+    }
+
+    match node {
+        NodeSpec::Constant { params } => {
+            params.value.to_bits().hash(hasher);
+        }
+        NodeSpec::Texture { params } => {
+            params.texture_id.hash(hasher);
+            let channel_tag: u8 = match params.channel {
+                TextureChannel::R => 0,
+                TextureChannel::G => 1,
+                TextureChannel::B => 2,
+                TextureChannel::A => 3,
+            };
+            channel_tag.hash(hasher);
+            params.sampler.key().hash(hasher);
+        }
+        NodeSpec::Noise { params } => {
+            let noise_tag: u8 = match params.noise {
+                NoiseKind::Value => 0,
+                NoiseKind::Perlin => 1,
+                NoiseKind::OpenSimplex => 2,
+                NoiseKind::Worley => 3,
+            };
+            noise_tag.hash(hasher);
+            params.seed.hash(hasher);
+            params.frequency.to_bits().hash(hasher);
+            if let Some(fractal) = &params.fractal {
+                let fractal_tag: u8 = match fractal.kind {
+                    FractalKind::Fbm => 0,
+                    FractalKind::Ridged => 1,
+                    FractalKind::Billow => 2,
                 };
-                channel_tag.hash(&mut hasher);
-            }
-            NodeSpec::Scale { params, .. } => {
-                params.factor.to_bits().hash(&mut hasher);
+                fractal_tag.hash(hasher);
+                fractal.octaves.hash(hasher);
+                fractal.lacunarity.to_bits().hash(hasher);
+                fractal.gain.to_bits().hash(hasher);
             }
-            NodeSpec::Clamp { params, .. } => {
-                params.min.to_bits().hash(&mut hasher);
-                params.max.to_bits().hash(&mut hasher);
+        }
+        NodeSpec::Position { params } => {
+            let axis_tag: u8 = match params.axis {
+                Axis::X => 0,
+                Axis::Y => 1,
+            };
+            axis_tag.hash(hasher);
+        }
+        NodeSpec::Distance { params } => {
+            match &params.shape {
+                DistanceShape::Point { point } => {
+                    0u8.hash(hasher);
+                    hash_point(point, hasher);
+                }
+                DistanceShape::Segment { a, b } => {
+                    1u8.hash(hasher);
+                    hash_point(a, hasher);
+                    hash_point(b, hasher);
+                }
+                DistanceShape::Polyline { points } => {
+                    2u8.hash(hasher);
+                    points.len().hash(hasher);
+                    for point in points {
+                        hash_point(point, hasher);
+                    }
+                }
             }
-            NodeSpec::SmoothStep { params, .. } => {
-                params.edge0.to_bits().hash(&mut hasher);
-                params.edge1.to_bits().hash(&mut hasher);
+            params.d_max.to_bits().hash(hasher);
+        }
+        NodeSpec::LinearGradient { params } => {
+            hash_point(&params.start, hasher);
+            hash_point(&params.end, hasher);
+        }
+        NodeSpec::RadialGradient { params } => {
+            hash_point(&params.center, hasher);
+            params.inner_radius.to_bits().hash(hasher);
+            params.outer_radius.to_bits().hash(hasher);
+        }
+        NodeSpec::DomainWarp { params, .. } => {
+            params.amplitude.to_bits().hash(hasher);
+        }
+        NodeSpec::Scale { params, .. } => {
+            params.factor.to_bits().hash(hasher);
+        }
+        NodeSpec::Clamp { params, .. } => {
+            params.min.to_bits().hash(hasher);
+            params.max.to_bits().hash(hasher);
+        }
+        NodeSpec::SmoothStep { params, .. } => {
+            params.edge0.to_bits().hash(hasher);
+            params.edge1.to_bits().hash(hasher);
+        }
+        NodeSpec::Pow { params, .. } => {
+            params.exp.to_bits().hash(hasher);
+        }
+        NodeSpec::EdtNormalize { params, .. } => {
+            params.threshold.to_bits().hash(hasher);
+            params.d_max.to_bits().hash(hasher);
+        }
+        NodeSpec::EdtSigned { params, .. } => {
+            params.threshold.to_bits().hash(hasher);
+            params.d_max.to_bits().hash(hasher);
+        }
+        NodeSpec::Blur { params, .. } => {
+            let kernel_tag: u8 = match params.kernel {
+                BlurKernel::Box => 0,
+                BlurKernel::Gaussian => 1,
+            };
+            kernel_tag.hash(hasher);
+            params.radius.to_bits().hash(hasher);
+        }
+        NodeSpec::Morphology { params, .. } => {
+            let op_tag: u8 = match params.op {
+                MorphologyOp::Dilate => 0,
+                MorphologyOp::Erode => 1,
+            };
+            op_tag.hash(hasher);
+            params.radius.to_bits().hash(hasher);
+        }
+        NodeSpec::Convolve { params, .. } => {
+            params.width.hash(hasher);
+            params.height.hash(hasher);
+            params.normalize.hash(hasher);
+            for weight in &params.weights {
+                weight.to_bits().hash(hasher);
             }
-            NodeSpec::Pow { params, .. } => {
-                params.exp.to_bits().hash(&mut hasher);
+        }
+        NodeSpec::Derivative { params, .. } => {
+            let kind_tag: u8 = match params.kind {
+                DerivativeKind::GradientMagnitude => 0,
+                DerivativeKind::Slope => 1,
+                DerivativeKind::Aspect => 2,
+                DerivativeKind::Curvature => 3,
+                DerivativeKind::NormalX => 4,
+                DerivativeKind::NormalY => 5,
+                DerivativeKind::NormalZ => 6,
+            };
+            kind_tag.hash(hasher);
+            params.height_scale.to_bits().hash(hasher);
+        }
+        NodeSpec::Remap { params, .. } => {
+            params.in_min.to_bits().hash(hasher);
+            params.in_max.to_bits().hash(hasher);
+            params.out_min.to_bits().hash(hasher);
+            params.out_max.to_bits().hash(hasher);
+            params.clamp.hash(hasher);
+        }
+        NodeSpec::Curve { params, .. } => {
+            let interpolation_tag: u8 = match params.interpolation {
+                CurveInterpolation::Linear => 0,
+                CurveInterpolation::Spline => 1,
+            };
+            interpolation_tag.hash(hasher);
+            params.points.len().hash(hasher);
+            for (x, y) in &params.points {
+                x.to_bits().hash(hasher);
+                y.to_bits().hash(hasher);
             }
-            NodeSpec::EdtNormalize { params, .. } => {
-                params.threshold.to_bits().hash(&mut hasher);
-                params.d_max.to_bits().hash(&mut hasher);
+        }
+        NodeSpec::Step { params, .. } => {
+            params.threshold.to_bits().hash(hasher);
+        }
+        NodeSpec::Lut { params, .. } => {
+            params.in_min.to_bits().hash(hasher);
+            params.in_max.to_bits().hash(hasher);
+            params.values.len().hash(hasher);
+            for value in &params.values {
+                value.to_bits().hash(hasher);
             }
-            _ => {}
         }
+        NodeSpec::Expr { params } => {
+            params.source.hash(hasher);
+        }
+        NodeSpec::Subgraph { params, .. } => {
+            params.name.hash(hasher);
+        }
+        _ => {}
     }
+}
 
-    if !opts.force_bake.is_empty() {
-        let mut bake_ids: Vec<&str> = opts.force_bake.iter().map(|s| s.as_str()).collect();
-        bake_ids.sort_unstable();
-        for id in bake_ids {
-            id.hash(&mut hasher);
+/// Hashes subgraph definitions by name, so kinds sharing a library hash alike.
+pub(crate) fn hash_subgraphs(
+    subgraphs: &HashMap<String, SubgraphSpec>,
+    hasher: &mut DefaultHasher,
+) {
+    let mut names: Vec<&String> = subgraphs.keys().collect();
+    names.sort();
+    for name in names {
+        let subgraph = &subgraphs[name];
+        name.hash(hasher);
+        subgraph.inputs.hash(hasher);
+        subgraph.output.hash(hasher);
+        let mut ids: Vec<&String> = subgraph.nodes.keys().collect();
+        ids.sort();
+        for id in ids {
+            id.hash(hasher);
+            hash_node(&subgraph.nodes[id], hasher);
         }
     }
+}
 
-    hasher.finish()
+fn hash_point(point: &(f32, f32), hasher: &mut DefaultHasher) {
+    point.0.to_bits().hash(hasher);
+    point.1.to_bits().hash(hasher);
 }
 
 #[cfg(test)]
@@ -293,6 +529,39 @@ mod tests {
         assert!(cache.get_for_kind(&kind.id).is_none());
     }
 
+    #[test]
+    fn kinds_share_subgraph_fragments() {
+        let mut falloff = SubgraphSpec::new(["moisture"], "out");
+        falloff.add("out", NodeSpec::expr("smoothstep(0.2, 0.8, moisture)"));
+        let kind = |id: &str, moisture: f32| {
+            let mut spec = FieldGraphSpec::default();
+            spec.add_subgraph("falloff", falloff.clone());
+            spec.add("moisture", NodeSpec::constant(moisture));
+            spec.add_with_semantics(
+                "prob",
+                NodeSpec::subgraph("falloff", vec!["moisture".into()]),
+                FieldSemantics::Probability,
+            );
+            Kind::new(id, spec)
+        };
+        let cache = FieldProgramCache::new();
+        let opts = CompileOptions::default();
+        cache.get_or_compile(&kind("fern", 0.5), &opts).unwrap();
+        cache.get_or_compile(&kind("moss", 0.9), &opts).unwrap();
+        assert_eq!(cache.fragments.len(), 1);
+
+        let mut changed = kind("moss", 0.9);
+        changed
+            .spec
+            .subgraphs
+            .get_mut("falloff")
+            .unwrap()
+            .add("out", NodeSpec::expr("step(0.5, moisture)"));
+        let program = cache.get_or_compile(&changed, &opts).unwrap();
+        assert!(matches!(program.nodes["prob"].spec, NodeSpec::Step { .. }));
+        assert_eq!(cache.fragments.len(), 2);
+    }
+
     #[test]
     fn recompiles_when_compile_options_change() {
         let cache = FieldProgramCache::new();
@@ -2,14 +2,18 @@
 //!
 //! This module turns a [`FieldGraphSpec`] into a runnable [`FieldProgram`].
 //! It performs input validation, marks nodes requested for baking
-//! via [`CompileOptions`], and computes a topological order for evaluation.
+//! via [`CompileOptions`], optionally runs the [`crate::fieldgraph::optimizer`] passes,
+//! and computes a topological order for evaluation.
 //!
 //! Typical usage:
 //! - [`FieldGraphCompiler`] with [`FieldGraphCompiler::compile`]
+//! - [`FieldGraphCompiler::compile_with_report`] to inspect what the optimizer changed
 use std::collections::{HashMap, HashSet};
 
 use crate::error::{Error, Result};
-use crate::fieldgraph::{FieldId, FieldProgram, NodeMeta, NodeSpec};
+use crate::fieldgraph::optimizer::{self, OptimizationReport};
+use crate::fieldgraph::subgraph::{self, FragmentCache};
+use crate::fieldgraph::{DistanceShape, FieldId, FieldProgram, NodeMeta, NodeSpec};
 use crate::prelude::FieldGraphSpec;
 
 /// Options for compiling a field graph.
@@ -17,6 +21,17 @@ use crate::prelude::FieldGraphSpec;
 pub struct CompileOptions {
     /// Set of field IDs that should be forced to be baked.
     pub force_bake: HashSet<FieldId>,
+    /// Whether to run the optimization passes. Optimized programs only keep fields tagged
+    /// with semantics and the fields they depend on.
+    pub optimize: bool,
+}
+
+impl CompileOptions {
+    /// Enables or disables the optimization passes.
+    pub fn with_optimize(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
 }
 
 /// Compiler for field graph specifications into executable programs.
@@ -25,6 +40,33 @@ pub struct FieldGraphCompiler;
 impl FieldGraphCompiler {
     /// Compiles a field graph specification into a [`FieldProgram`], applying the given options.
     pub fn compile(spec: &FieldGraphSpec, opts: &CompileOptions) -> Result<FieldProgram> {
+        Self::compile_with_report(spec, opts).map(|(program, _)| program)
+    }
+
+    /// Compiles a field graph specification like [`FieldGraphCompiler::compile`] and returns
+    /// a report of the optimizations applied. The report is empty if optimization is disabled.
+    pub fn compile_with_report(
+        spec: &FieldGraphSpec,
+        opts: &CompileOptions,
+    ) -> Result<(FieldProgram, OptimizationReport)> {
+        Self::compile_with_fragments(spec, opts, None)
+    }
+
+    /// Compiles like [`FieldGraphCompiler::compile_with_report`], reusing expanded subgraphs
+    /// from `fragments`.
+    pub(crate) fn compile_with_fragments(
+        spec: &FieldGraphSpec,
+        opts: &CompileOptions,
+        fragments: Option<&FragmentCache>,
+    ) -> Result<(FieldProgram, OptimizationReport)> {
+        let inlined;
+        let spec = if subgraph::needs_inlining(spec) {
+            inlined = subgraph::inline(spec, fragments)?;
+            &inlined
+        } else {
+            spec
+        };
+
         let mut nodes: HashMap<FieldId, NodeMeta> = HashMap::new();
 
         for (id, node_spec) in &spec.nodes {
@@ -37,6 +79,7 @@ impl FieldGraphCompiler {
             }
 
             validate_node_inputs(id, node_spec)?;
+            validate_node_params(id, node_spec)?;
 
             let force_bake = opts.force_bake.contains(id);
 
@@ -46,13 +89,25 @@ impl FieldGraphCompiler {
                     id: id.clone(),
                     spec: node_spec.clone(),
                     force_bake,
+                    raster_sampler: spec.raster_samplers.get(id).copied().unwrap_or_default(),
                     semantics: spec.semantics.get(id).cloned(),
                 },
             );
         }
 
-        let topo = topo_sort(&nodes)?;
-        Ok(FieldProgram { nodes, topo })
+        let mut topo = topo_sort(&nodes)?;
+        let report = if opts.optimize {
+            let report = optimizer::optimize(&mut nodes);
+            topo = topo_sort(&nodes)?;
+            report
+        } else {
+            OptimizationReport {
+                nodes_before: nodes.len(),
+                nodes_after: nodes.len(),
+                ..Default::default()
+            }
+        };
+        Ok((FieldProgram::new(nodes, topo), report))
     }
 }
 
@@ -69,12 +124,13 @@ fn validate_node_inputs(id: &str, node_spec: &NodeSpec) -> Result<()> {
         }
     };
 
-    let ensure_exactly_one = |variant: &str| {
-        if inputs.len() != 1 {
+    let ensure_exactly = |variant: &str, count: usize| {
+        if inputs.len() != count {
             Err(Error::Compile(format!(
-                "Node '{}' ({}) requires exactly one input but found {}",
+                "Node '{}' ({}) requires exactly {} input(s) but found {}",
                 id,
                 variant,
+                count,
                 inputs.len()
             )))
         } else {
@@ -83,21 +139,215 @@ fn validate_node_inputs(id: &str, node_spec: &NodeSpec) -> Result<()> {
     };
 
     match node_spec {
-        NodeSpec::Constant { .. } | NodeSpec::Texture { .. } => Ok(()),
+        NodeSpec::Constant { .. }
+        | NodeSpec::Texture { .. }
+        | NodeSpec::Noise { .. }
+        | NodeSpec::Position { .. }
+        | NodeSpec::Distance { .. }
+        | NodeSpec::LinearGradient { .. }
+        | NodeSpec::RadialGradient { .. }
+        | NodeSpec::Expr { .. }
+        | NodeSpec::Subgraph { .. } => Ok(()),
         NodeSpec::Add { .. } => ensure_at_least_one("Add"),
         NodeSpec::Sub { .. } => ensure_at_least_one("Sub"),
         NodeSpec::Mul { .. } => ensure_at_least_one("Mul"),
         NodeSpec::Min { .. } => ensure_at_least_one("Min"),
         NodeSpec::Max { .. } => ensure_at_least_one("Max"),
-        NodeSpec::Invert { .. } => ensure_exactly_one("Invert"),
-        NodeSpec::Scale { .. } => ensure_exactly_one("Scale"),
-        NodeSpec::Clamp { .. } => ensure_exactly_one("Clamp"),
-        NodeSpec::SmoothStep { .. } => ensure_exactly_one("SmoothStep"),
-        NodeSpec::Pow { .. } => ensure_exactly_one("Pow"),
-        NodeSpec::EdtNormalize { .. } => ensure_exactly_one("EdtNormalize"),
+        NodeSpec::Invert { .. } => ensure_exactly("Invert", 1),
+        NodeSpec::Div { .. } => ensure_exactly("Div", 2),
+        NodeSpec::Mod { .. } => ensure_exactly("Mod", 2),
+        NodeSpec::Abs { .. } => ensure_exactly("Abs", 1),
+        NodeSpec::Sqrt { .. } => ensure_exactly("Sqrt", 1),
+        NodeSpec::Sin { .. } => ensure_exactly("Sin", 1),
+        NodeSpec::Cos { .. } => ensure_exactly("Cos", 1),
+        NodeSpec::Tan { .. } => ensure_exactly("Tan", 1),
+        NodeSpec::Atan2 { .. } => ensure_exactly("Atan2", 2),
+        NodeSpec::Floor { .. } => ensure_exactly("Floor", 1),
+        NodeSpec::Fract { .. } => ensure_exactly("Fract", 1),
+        NodeSpec::Sign { .. } => ensure_exactly("Sign", 1),
+        NodeSpec::Lerp { .. } => ensure_exactly("Lerp", 3),
+        NodeSpec::Select { .. } => ensure_exactly("Select", 3),
+        NodeSpec::Greater { .. } => ensure_exactly("Greater", 2),
+        NodeSpec::Less { .. } => ensure_exactly("Less", 2),
+        NodeSpec::And { .. } => ensure_at_least_one("And"),
+        NodeSpec::Or { .. } => ensure_at_least_one("Or"),
+        NodeSpec::Not { .. } => ensure_exactly("Not", 1),
+        NodeSpec::Scale { .. } => ensure_exactly("Scale", 1),
+        NodeSpec::Clamp { .. } => ensure_exactly("Clamp", 1),
+        NodeSpec::SmoothStep { .. } => ensure_exactly("SmoothStep", 1),
+        NodeSpec::Pow { .. } => ensure_exactly("Pow", 1),
+        NodeSpec::EdtNormalize { .. } => ensure_exactly("EdtNormalize", 1),
+        NodeSpec::EdtSigned { .. } => ensure_exactly("EdtSigned", 1),
+        NodeSpec::Blur { .. } => ensure_exactly("Blur", 1),
+        NodeSpec::Morphology { .. } => ensure_exactly("Morphology", 1),
+        NodeSpec::Convolve { .. } => ensure_exactly("Convolve", 1),
+        NodeSpec::Derivative { .. } => ensure_exactly("Derivative", 1),
+        NodeSpec::Remap { .. } => ensure_exactly("Remap", 1),
+        NodeSpec::Curve { .. } => ensure_exactly("Curve", 1),
+        NodeSpec::Step { .. } => ensure_exactly("Step", 1),
+        NodeSpec::Lut { .. } => ensure_exactly("Lut", 1),
+        NodeSpec::DomainWarp { .. } => ensure_exactly("DomainWarp", 3),
+    }
+}
+
+fn validate_node_params(id: &str, node_spec: &NodeSpec) -> Result<()> {
+    match node_spec {
+        NodeSpec::Noise { params } => {
+            if !params.frequency.is_finite() || params.frequency <= 0.0 {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Noise) requires a finite frequency > 0 but found {}",
+                    params.frequency
+                )));
+            }
+            if let Some(fractal) = &params.fractal {
+                if fractal.octaves == 0 {
+                    return Err(Error::Compile(format!(
+                        "Node '{id}' (Noise) requires at least one octave"
+                    )));
+                }
+                if !fractal.lacunarity.is_finite() || !fractal.gain.is_finite() {
+                    return Err(Error::Compile(format!(
+                        "Node '{id}' (Noise) requires finite lacunarity and gain"
+                    )));
+                }
+            }
+            Ok(())
+        }
+        NodeSpec::Distance { params } => {
+            if let DistanceShape::Polyline { points } = &params.shape {
+                if points.is_empty() {
+                    return Err(Error::Compile(format!(
+                        "Node '{id}' (Distance) requires at least one polyline point"
+                    )));
+                }
+            }
+            if !params.d_max.is_finite() || params.d_max < 0.0 {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Distance) requires a finite d_max >= 0 but found {}",
+                    params.d_max
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::LinearGradient { params } => {
+            if params.start == params.end {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (LinearGradient) requires distinct start and end points"
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::RadialGradient { params } => {
+            if params.outer_radius < params.inner_radius {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (RadialGradient) requires outer_radius >= inner_radius"
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::EdtSigned { params, .. } => {
+            if !params.d_max.is_finite() || params.d_max < 0.0 {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (EdtSigned) requires a finite d_max >= 0 but found {}",
+                    params.d_max
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::Blur { params, .. } => ensure_radius(id, "Blur", params.radius),
+        NodeSpec::Morphology { params, .. } => ensure_radius(id, "Morphology", params.radius),
+        NodeSpec::Convolve { params, .. } => {
+            if params.width % 2 == 0 || params.height % 2 == 0 {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Convolve) requires an odd kernel size but found {}x{}",
+                    params.width, params.height
+                )));
+            }
+            let expected = params.width as usize * params.height as usize;
+            if params.weights.len() != expected {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Convolve) requires {expected} weights but found {}",
+                    params.weights.len()
+                )));
+            }
+            if params.weights.iter().any(|w| !w.is_finite()) {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Convolve) requires finite weights"
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::Derivative { params, .. } => {
+            if !params.height_scale.is_finite() {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Derivative) requires a finite height_scale but found {}",
+                    params.height_scale
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::Remap { params, .. } => {
+            let bounds = [params.in_min, params.in_max, params.out_min, params.out_max];
+            if bounds.iter().any(|v| !v.is_finite()) || params.in_min == params.in_max {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Remap) requires finite ranges and in_min != in_max but found [{}, {}]",
+                    params.in_min, params.in_max
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::Curve { params, .. } => {
+            if params.points.is_empty() {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Curve) requires at least one control point"
+                )));
+            }
+            if params
+                .points
+                .iter()
+                .any(|(x, y)| !x.is_finite() || !y.is_finite())
+            {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Curve) requires finite control points"
+                )));
+            }
+            if params.points.windows(2).any(|pair| pair[1].0 <= pair[0].0) {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Curve) requires strictly increasing control point inputs"
+                )));
+            }
+            Ok(())
+        }
+        NodeSpec::Lut { params, .. } => {
+            if params.values.is_empty() || params.values.iter().any(|v| !v.is_finite()) {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Lut) requires at least one finite table value"
+                )));
+            }
+            if !params.in_min.is_finite()
+                || !params.in_max.is_finite()
+                || params.in_min == params.in_max
+            {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' (Lut) requires a finite input range with in_min != in_max but found [{}, {}]",
+                    params.in_min, params.in_max
+                )));
+            }
+            Ok(())
+        }
+        _ => Ok(()),
     }
 }
 
+fn ensure_radius(id: &str, kind: &str, radius: f32) -> Result<()> {
+    if !radius.is_finite() || radius < 0.0 {
+        return Err(Error::Compile(format!(
+            "Node '{id}' ({kind}) requires a finite radius >= 0 but found {radius}"
+        )));
+    }
+    Ok(())
+}
+
 fn topo_sort(nodes: &HashMap<FieldId, NodeMeta>) -> Result<Vec<FieldId>> {
     let mut indeg: HashMap<&str, usize> = HashMap::new();
     let mut dependents: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
@@ -148,7 +398,10 @@ fn topo_sort(nodes: &HashMap<FieldId, NodeMeta>) -> Result<Vec<FieldId>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::fieldgraph::node::{PowParams, ScaleParams};
+    use crate::fieldgraph::node::{
+        BlurKernel, CurveInterpolation, DerivativeKind, DerivativeParams, DomainWarpParams,
+        FractalKind, NoiseKind, PowParams, ScaleParams, StepParams,
+    };
     use crate::prelude::{FieldSemantics, NodeSpec};
 
     #[test]
@@ -226,6 +479,216 @@ mod tests {
             .expect("compile error");
     }
 
+    #[test]
+    fn compile_validates_noise_params() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("ok", NodeSpec::noise(NoiseKind::Perlin, 1, 0.1));
+        FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect("valid noise compiles");
+
+        spec.add("bad_freq", NodeSpec::noise(NoiseKind::Value, 1, 0.0));
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("zero frequency should fail");
+        assert!(matches!(err, Error::Compile(_)));
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add(
+            "bad_octaves",
+            NodeSpec::fractal_noise(NoiseKind::Worley, FractalKind::Fbm, 1, 0.1, 0),
+        );
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("zero octaves should fail");
+        assert!(matches!(err, Error::Compile(_)));
+    }
+
+    #[test]
+    fn compile_validates_coordinate_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add(
+            "warp",
+            NodeSpec::domain_warp("x".into(), "x".into(), "x".into(), 2.0),
+        );
+        FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect("valid coordinate nodes compile");
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add(
+            "bad_warp",
+            NodeSpec::DomainWarp {
+                inputs: vec!["x".into(), "x".into()],
+                params: DomainWarpParams { amplitude: 1.0 },
+            },
+        );
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("warp needs three inputs");
+        assert!(matches!(err, Error::Compile(_)));
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add(
+            "bad_gradient",
+            NodeSpec::linear_gradient(glam::Vec2::ONE, glam::Vec2::ONE),
+        );
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("degenerate gradient should fail");
+        assert!(matches!(err, Error::Compile(_)));
+    }
+
+    #[test]
+    fn compile_expands_expression_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("height", NodeSpec::position_x());
+        spec.add("prob", NodeSpec::expr("smoothstep(0.2, 0.6, height)"));
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect("expression compiles");
+        assert!(matches!(
+            program.nodes["prob"].spec,
+            NodeSpec::SmoothStep { .. }
+        ));
+
+        spec.add("prob", NodeSpec::expr("smoothstep(0.2, height)"));
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("wrong argument count should fail");
+        assert!(
+            matches!(err, Error::Compile(ref msg) if msg.starts_with("Node 'prob' (Expr) at 1:1:"))
+        );
+
+        spec.add("prob", NodeSpec::expr("1 - missing"));
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("unknown reference should fail");
+        assert!(matches!(err, Error::Compile(ref msg) if msg.contains("'missing'")));
+
+        spec.add("prob", NodeSpec::expr("height * 2"));
+        spec.add("prob/0", NodeSpec::constant(1.0));
+        let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect_err("clashing ids should fail");
+        assert!(matches!(err, Error::Compile(ref msg) if msg.contains("already defined")));
+    }
+
+    #[test]
+    fn compile_validates_math_node_arity() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("y", NodeSpec::position_y());
+        spec.add("angle", NodeSpec::atan2("y".into(), "x".into()));
+        spec.add(
+            "mix",
+            NodeSpec::lerp("x".into(), "y".into(), "angle".into()),
+        );
+        spec.add("both", NodeSpec::and(vec!["x".into(), "y".into()]));
+        FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect("valid math nodes compile");
+
+        for bad in [
+            NodeSpec::Div {
+                inputs: vec!["x".into()],
+            },
+            NodeSpec::Sin {
+                inputs: vec!["x".into(), "x".into()],
+            },
+            NodeSpec::Select {
+                inputs: vec!["x".into(), "x".into()],
+            },
+            NodeSpec::Or { inputs: Vec::new() },
+        ] {
+            let mut spec = FieldGraphSpec::default();
+            spec.add("x", NodeSpec::position_x());
+            spec.add("bad", bad);
+            let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+                .expect_err("wrong arity should fail");
+            assert!(matches!(err, Error::Compile(_)));
+        }
+    }
+
+    #[test]
+    fn compile_validates_shaping_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add(
+            "remapped",
+            NodeSpec::remap("x".into(), (0.0, 10.0), (1.0, 0.0)),
+        );
+        spec.add(
+            "shaped",
+            NodeSpec::curve(
+                "x".into(),
+                vec![(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)],
+                CurveInterpolation::Spline,
+            ),
+        );
+        spec.add("stepped", NodeSpec::step("x".into(), 0.5));
+        spec.add("table", NodeSpec::lut("x".into(), 0.0, 1.0, vec![0.0, 1.0]));
+        FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect("valid shaping nodes compile");
+
+        for bad in [
+            NodeSpec::remap("x".into(), (1.0, 1.0), (0.0, 1.0)),
+            NodeSpec::curve("x".into(), Vec::new(), CurveInterpolation::Linear),
+            NodeSpec::curve(
+                "x".into(),
+                vec![(1.0, 0.0), (0.0, 1.0)],
+                CurveInterpolation::Linear,
+            ),
+            NodeSpec::lut("x".into(), 0.0, 1.0, Vec::new()),
+            NodeSpec::lut("x".into(), 0.0, f32::NAN, vec![1.0]),
+            NodeSpec::Step {
+                inputs: Vec::new(),
+                params: StepParams { threshold: 0.5 },
+            },
+        ] {
+            let mut spec = FieldGraphSpec::default();
+            spec.add("x", NodeSpec::position_x());
+            spec.add("bad", bad);
+            let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+                .expect_err("invalid shaping node should fail");
+            assert!(matches!(err, Error::Compile(_)));
+        }
+    }
+
+    #[test]
+    fn compile_validates_filter_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("signed", NodeSpec::edt_signed("x".into(), 0.5, 4.0));
+        spec.add(
+            "soft",
+            NodeSpec::blur("x".into(), BlurKernel::Gaussian, 2.0),
+        );
+        spec.add("grown", NodeSpec::dilate("x".into(), 1.0));
+        spec.add(
+            "slope",
+            NodeSpec::derivative("x".into(), DerivativeKind::Slope),
+        );
+        spec.add(
+            "edges",
+            NodeSpec::convolve("x".into(), 3, 1, vec![-1.0, 0.0, 1.0], false),
+        );
+        FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+            .expect("valid filter nodes compile");
+
+        for bad in [
+            NodeSpec::blur("x".into(), BlurKernel::Box, -1.0),
+            NodeSpec::erode("x".into(), f32::NAN),
+            NodeSpec::convolve("x".into(), 2, 1, vec![1.0, 1.0], true),
+            NodeSpec::convolve("x".into(), 3, 3, vec![1.0; 3], true),
+            NodeSpec::Derivative {
+                inputs: vec!["x".into()],
+                params: DerivativeParams {
+                    kind: DerivativeKind::Slope,
+                    height_scale: f32::INFINITY,
+                },
+            },
+        ] {
+            let mut spec = FieldGraphSpec::default();
+            spec.add("x", NodeSpec::position_x());
+            spec.add("bad", bad);
+            let err = FieldGraphCompiler::compile(&spec, &CompileOptions::default())
+                .expect_err("invalid filter should fail");
+            assert!(matches!(err, Error::Compile(_)));
+        }
+    }
+
     #[test]
     fn compile_detects_cycles() {
         let mut spec = FieldGraphSpec::default();
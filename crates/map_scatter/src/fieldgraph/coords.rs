@@ -0,0 +1,139 @@
+//! Coordinate-domain helpers for position, distance, and gradient nodes.
+//!
+//! These functions evaluate purely geometric fields of the sample position, so spatial
+//! rules such as "near the river" or "fade towards the east" can be authored directly in a
+//! [`crate::fieldgraph::spec::FieldGraphSpec`] without custom textures.
+use glam::Vec2;
+
+use crate::fieldgraph::node::{
+    Axis, DistanceParams, DistanceShape, LinearGradientParams, RadialGradientParams,
+};
+
+/// Returns the world coordinate of `p` along `axis`.
+#[inline]
+pub fn position(axis: Axis, p: Vec2) -> f32 {
+    match axis {
+        Axis::X => p.x,
+        Axis::Y => p.y,
+    }
+}
+
+/// Distance from `p` to the configured shape, normalized by `d_max` when it is positive.
+pub fn distance(params: &DistanceParams, p: Vec2) -> f32 {
+    let d = match &params.shape {
+        DistanceShape::Point { point } => p.distance(Vec2::from(*point)),
+        DistanceShape::Segment { a, b } => distance_to_segment(p, Vec2::from(*a), Vec2::from(*b)),
+        DistanceShape::Polyline { points } => match points.as_slice() {
+            [] => 0.0,
+            [single] => p.distance(Vec2::from(*single)),
+            _ => points
+                .windows(2)
+                .map(|w| distance_to_segment(p, Vec2::from(w[0]), Vec2::from(w[1])))
+                .fold(f32::INFINITY, f32::min),
+        },
+    };
+
+    if params.d_max > 0.0 {
+        (d / params.d_max).min(1.0)
+    } else {
+        d
+    }
+}
+
+/// Projects `p` onto the gradient axis: `0` at `start`, `1` at `end`, clamped in between.
+pub fn linear_gradient(params: &LinearGradientParams, p: Vec2) -> f32 {
+    let start = Vec2::from(params.start);
+    let axis = Vec2::from(params.end) - start;
+    let len2 = axis.length_squared();
+    if len2 <= f32::EPSILON {
+        return 0.0;
+    }
+    ((p - start).dot(axis) / len2).clamp(0.0, 1.0)
+}
+
+/// Radial ramp: `0` inside `inner_radius`, `1` beyond `outer_radius`, linear in between.
+pub fn radial_gradient(params: &RadialGradientParams, p: Vec2) -> f32 {
+    let d = p.distance(Vec2::from(params.center));
+    let span = params.outer_radius - params.inner_radius;
+    if span.abs() <= f32::EPSILON {
+        return if d >= params.outer_radius { 1.0 } else { 0.0 };
+    }
+    ((d - params.inner_radius) / span).clamp(0.0, 1.0)
+}
+
+fn distance_to_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len2 = ab.length_squared();
+    if len2 <= f32::EPSILON {
+        return p.distance(a);
+    }
+    let t = ((p - a).dot(ab) / len2).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn distance_to_point_is_normalized_by_d_max() {
+        let params = DistanceParams {
+            shape: DistanceShape::Point { point: (1.0, 1.0) },
+            d_max: 10.0,
+        };
+        approx_eq(distance(&params, Vec2::new(4.0, 5.0)), 0.5);
+        approx_eq(distance(&params, Vec2::new(100.0, 1.0)), 1.0);
+    }
+
+    #[test]
+    fn distance_to_segment_clamps_to_endpoints() {
+        let params = DistanceParams {
+            shape: DistanceShape::Segment {
+                a: (0.0, 0.0),
+                b: (10.0, 0.0),
+            },
+            d_max: 0.0,
+        };
+        approx_eq(distance(&params, Vec2::new(5.0, 3.0)), 3.0);
+        approx_eq(distance(&params, Vec2::new(13.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn distance_to_polyline_uses_nearest_segment() {
+        let params = DistanceParams {
+            shape: DistanceShape::Polyline {
+                points: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)],
+            },
+            d_max: 0.0,
+        };
+        approx_eq(distance(&params, Vec2::new(12.0, 5.0)), 2.0);
+        approx_eq(distance(&params, Vec2::new(5.0, -1.0)), 1.0);
+    }
+
+    #[test]
+    fn linear_gradient_projects_onto_axis() {
+        let params = LinearGradientParams {
+            start: (-10.0, 0.0),
+            end: (10.0, 0.0),
+        };
+        approx_eq(linear_gradient(&params, Vec2::new(0.0, 7.0)), 0.5);
+        approx_eq(linear_gradient(&params, Vec2::new(-20.0, 0.0)), 0.0);
+        approx_eq(linear_gradient(&params, Vec2::new(20.0, 0.0)), 1.0);
+    }
+
+    #[test]
+    fn radial_gradient_ramps_between_radii() {
+        let params = RadialGradientParams {
+            center: (0.0, 0.0),
+            inner_radius: 2.0,
+            outer_radius: 6.0,
+        };
+        approx_eq(radial_gradient(&params, Vec2::new(1.0, 0.0)), 0.0);
+        approx_eq(radial_gradient(&params, Vec2::new(0.0, 4.0)), 0.5);
+        approx_eq(radial_gradient(&params, Vec2::new(8.0, 0.0)), 1.0);
+    }
+}
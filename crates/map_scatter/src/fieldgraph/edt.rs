@@ -1,6 +1,7 @@
 //! Euclidean Distance Transform (EDT) utilities for the field graph runtime.
 //!
-//! Computes EDT rasters from thresholded fields and normalizes distances.
+//! Computes EDT rasters from thresholded fields and normalizes distances, either unsigned
+//! inside the mask or signed across its edge.
 //!
 //! This implementation is based on the Felzenszwalb-Huttenlocher algorithm,
 //! which computes exact Euclidean distances using a separable approach with
@@ -19,18 +20,33 @@ pub fn bake_edt_normalize_params(
     grid: &ChunkGrid,
 ) -> Raster {
     let (tw, th) = (grid.total_width(), grid.total_height());
-    let mut mask: Vec<u8> = vec![0; tw * th];
-
-    // Create binary mask from input field
+    let mut positions = Vec::with_capacity(tw * th);
     for iy in 0..th as isize {
         for ix in 0..tw as isize {
-            let p = grid.index_to_world(ix, iy);
-            let v = runtime.sample(input_field, p, chunk, grid);
-            let idx = (iy as usize) * tw + ix as usize;
-            mask[idx] = if v >= threshold { 1 } else { 0 };
+            positions.push(grid.index_to_world(ix, iy));
         }
     }
 
+    let mut values = vec![0.0; positions.len()];
+    runtime.sample_batch(input_field, &positions, chunk, grid, &mut values);
+    edt_normalize_raster(&values, threshold, d_max, grid)
+}
+
+/// Computes the normalized EDT of input values sampled at the cells of `grid` in row-major order.
+pub fn edt_normalize_raster(
+    values: &[f32],
+    threshold: f32,
+    d_max: f32,
+    grid: &ChunkGrid,
+) -> Raster {
+    let (tw, th) = (grid.total_width(), grid.total_height());
+
+    // Create binary mask from input field
+    let mask: Vec<u8> = values
+        .iter()
+        .map(|v| if *v >= threshold { 1 } else { 0 })
+        .collect();
+
     // Compute EDT
     let edt = edt_unsigned(&mask, tw, th);
 
@@ -48,6 +64,42 @@ pub fn bake_edt_normalize_params(
     raster
 }
 
+/// Computes the signed EDT of input values sampled at the cells of `grid` in row-major order.
+///
+/// Cells at or above `threshold` are inside the mask and get positive distances to the
+/// nearest outside cell; outside cells get negative distances to the nearest inside cell.
+/// Distances are measured from the edge halfway between cells, divided by `d_max`, and
+/// clamped to `[-1, 1]`.
+pub fn edt_signed_raster(values: &[f32], threshold: f32, d_max: f32, grid: &ChunkGrid) -> Raster {
+    let (tw, th) = (grid.total_width(), grid.total_height());
+
+    let inside: Vec<u8> = values.iter().map(|v| u8::from(*v >= threshold)).collect();
+    let outside: Vec<u8> = inside.iter().map(|m| 1 - m).collect();
+    let has_inside = inside.contains(&1);
+    let has_outside = outside.contains(&1);
+
+    let to_outside = edt_unsigned(&inside, tw, th);
+    let to_inside = edt_unsigned(&outside, tw, th);
+    let scale = if d_max > 0.0 { 1.0 / d_max } else { 1.0 };
+
+    let mut raster = Raster::new(grid.clone());
+    for (i, value) in raster.data.iter_mut().enumerate() {
+        let signed = if inside[i] == 1 {
+            if has_outside {
+                to_outside[i] - 0.5
+            } else {
+                f32::INFINITY
+            }
+        } else if has_inside {
+            0.5 - to_inside[i]
+        } else {
+            f32::NEG_INFINITY
+        };
+        *value = (signed * scale).clamp(-1.0, 1.0);
+    }
+    raster
+}
+
 /// Computes the 1D Euclidean Distance Transform using the lower envelope algorithm.
 fn edt_1d(f: &[f32], output: &mut [f32]) {
     let n = f.len();
@@ -314,6 +366,29 @@ mod tests {
         assert_eq!(raster.data, vec![0.0, 1.0]);
     }
 
+    #[test]
+    fn edt_signed_is_positive_inside_and_negative_outside() {
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 1.0,
+            width: 6,
+            height: 1,
+            halo: 0,
+        };
+        let values = [0.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        let raster = edt_signed_raster(&values, 0.5, 0.0, &grid);
+        assert_eq!(raster.data, vec![-1.0, -1.0, -0.5, 0.5, 1.0, 1.0]);
+
+        let raster = edt_signed_raster(&values, 0.5, 4.0, &grid);
+        assert_eq!(
+            raster.data,
+            vec![-0.625, -0.375, -0.125, 0.125, 0.375, 0.625]
+        );
+
+        let raster = edt_signed_raster(&[1.0; 6], 0.5, 4.0, &grid);
+        assert!(raster.data.iter().all(|v| *v == 1.0));
+    }
+
     #[test]
     fn edt_produces_correct_distances_for_simple_pattern() {
         // Create a 5x5 mask with a single background pixel in the center
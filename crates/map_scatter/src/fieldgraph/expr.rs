@@ -0,0 +1,675 @@
+//! Expression language for field graphs.
+//!
+//! An [`NodeSpec::Expr`] node holds a small expression such as
+//! `smoothstep(0.2, 0.6, tex("height").r) * (1 - mask_roads.r)`. The compiler expands it into
+//! regular nodes with [`expand`] before validation: the root node keeps the expression's field
+//! id and intermediate nodes are named `<id>/<n>`.
+//!
+//! Syntax:
+//! - numbers (`0.5`, `1e-3`), field references (`height`, or `field("id")` for ids that are not
+//!   identifiers), and texture channels (`mask_roads.r`, `tex("mask_roads:tree").g`; a bare
+//!   `tex("id")` reads channel R)
+//! - arithmetic `+ - * / %`, comparisons `< > <= >=`, logic `&& || !`, and parentheses;
+//!   comparisons and logic return `1.0` or `0.0`
+//! - `min`, `max` (one or more arguments), `abs`, `sqrt`, `sin`, `cos`, `tan`, `floor`,
+//!   `fract`, `sign`, `invert`, `atan2(y, x)`, `lerp(a, b, t)` (or `mix`), `select(cond, a, b)`,
+//!   `position_x()`, and `position_y()`
+//! - `smoothstep(edge0, edge1, x)`, `step(edge, x)`, `clamp(x, min, max)`, `pow(x, exp)`, and
+//!   `remap(x, in_min, in_max, out_min, out_max)`, whose edges, bounds, and exponents must be
+//!   number literals
+//!
+//! Parse errors are reported as [`Error::Compile`] with the line and column of the offending
+//! token.
+use crate::error::{Error, Result};
+use crate::fieldgraph::{FieldId, NodeSpec, TextureChannel};
+
+/// Parses `source` into nodes for the field `id`. The node computing the whole expression is
+/// named `id`; the others are named `<id>/<n>` and listed before the nodes reading them.
+pub fn expand(id: &str, source: &str) -> Result<Vec<(FieldId, NodeSpec)>> {
+    let tokens = tokenize(id, source)?;
+    let mut parser = Parser {
+        id,
+        source,
+        tokens,
+        next: 0,
+        nodes: Vec::new(),
+        depth: 0,
+    };
+    let value = parser.parse_or()?;
+    let (token, pos) = parser.peek();
+    if token != Token::End {
+        return Err(parser.error(pos, format!("unexpected {}", token.describe())));
+    }
+
+    let mut nodes = parser.nodes;
+    match value {
+        Value::Number(value) => nodes.push((id.to_string(), NodeSpec::constant(value))),
+        Value::Field(field) => match nodes.last_mut() {
+            // The last generated node computes the whole expression and is read by no other.
+            Some(last) if last.0 == field => last.0 = id.to_string(),
+            _ => nodes.push((id.to_string(), NodeSpec::add(vec![field]))),
+        },
+    }
+    Ok(nodes)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Number(f32),
+    Ident(&'a str),
+    Str(&'a str),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    End,
+}
+
+impl Token<'_> {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(value) => format!("number {value}"),
+            Token::Ident(name) => format!("'{name}'"),
+            Token::Str(text) => format!("string \"{text}\""),
+            Token::Op(op) => format!("'{op}'"),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::Comma => "','".to_string(),
+            Token::Dot => "'.'".to_string(),
+            Token::End => "end of expression".to_string(),
+        }
+    }
+}
+
+/// Deepest nesting of unary operators, parentheses, and calls, keeping the recursive descent
+/// well within the stack.
+const MAX_DEPTH: usize = 256;
+
+const OPERATORS: [&str; 12] = [
+    "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!",
+];
+
+fn tokenize<'a>(id: &str, source: &'a str) -> Result<Vec<(Token<'a>, usize)>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let starts_number =
+            c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit));
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        } else if starts_number {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+                let sign = usize::from(matches!(bytes.get(i + 1), Some(b'+' | b'-')));
+                if bytes.get(i + 1 + sign).is_some_and(u8::is_ascii_digit) {
+                    i += 1 + sign;
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text = &source[start..i];
+            let value = text
+                .parse::<f32>()
+                .map_err(|_| syntax_error(id, source, start, format!("invalid number '{text}'")))?;
+            tokens.push((Token::Number(value), start));
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(&source[start..i]), start));
+        } else if c == b'"' {
+            let Some(len) = source[i + 1..].find('"') else {
+                return Err(syntax_error(id, source, start, "unterminated string"));
+            };
+            tokens.push((Token::Str(&source[i + 1..i + 1 + len]), start));
+            i += len + 2;
+        } else {
+            let token = match c {
+                b'(' => Some(Token::LParen),
+                b')' => Some(Token::RParen),
+                b',' => Some(Token::Comma),
+                b'.' => Some(Token::Dot),
+                _ => OPERATORS
+                    .iter()
+                    .find(|op| source[i..].starts_with(**op))
+                    .map(|op| Token::Op(op)),
+            };
+            let Some(token) = token else {
+                let found = source[i..].chars().next().unwrap_or_default();
+                return Err(syntax_error(
+                    id,
+                    source,
+                    start,
+                    format!("unexpected character '{found}'"),
+                ));
+            };
+            i += match token {
+                Token::Op(op) => op.len(),
+                _ => 1,
+            };
+            tokens.push((token, start));
+        }
+    }
+    tokens.push((Token::End, source.len()));
+    Ok(tokens)
+}
+
+fn syntax_error(id: &str, source: &str, pos: usize, message: impl AsRef<str>) -> Error {
+    let before = &source[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |nl| nl + 1)..]
+        .chars()
+        .count()
+        + 1;
+    Error::Compile(format!(
+        "Node '{id}' (Expr) at {line}:{column}: {}",
+        message.as_ref()
+    ))
+}
+
+/// Result of a sub-expression: a number literal or the id of a field computing it.
+#[derive(Clone, Debug)]
+enum Value {
+    Number(f32),
+    Field(FieldId),
+}
+
+/// Function call argument with the position it starts at.
+enum Arg {
+    Value(Value, usize),
+    Str(String, usize),
+}
+
+impl Arg {
+    fn pos(&self) -> usize {
+        match self {
+            Arg::Value(_, pos) | Arg::Str(_, pos) => *pos,
+        }
+    }
+}
+
+struct Parser<'a> {
+    id: &'a str,
+    source: &'a str,
+    tokens: Vec<(Token<'a>, usize)>,
+    next: usize,
+    nodes: Vec<(FieldId, NodeSpec)>,
+    /// Nesting depth of [`Parser::parse_unary`], which every nested expression passes.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> (Token<'a>, usize) {
+        self.tokens[self.next]
+    }
+
+    fn bump(&mut self) -> (Token<'a>, usize) {
+        let token = self.tokens[self.next];
+        if token.0 != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, token: Token) -> bool {
+        if self.peek().0 == token {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<()> {
+        let (found, pos) = self.peek();
+        if found == token {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(
+                pos,
+                format!("expected {what} but found {}", found.describe()),
+            ))
+        }
+    }
+
+    fn error(&self, pos: usize, message: impl AsRef<str>) -> Error {
+        syntax_error(self.id, self.source, pos, message)
+    }
+
+    fn emit(&mut self, spec: NodeSpec) -> Value {
+        let field = format!("{}/{}", self.id, self.nodes.len());
+        self.nodes.push((field.clone(), spec));
+        Value::Field(field)
+    }
+
+    fn field(&mut self, value: Value) -> FieldId {
+        match value {
+            Value::Field(field) => field,
+            Value::Number(value) => match self.emit(NodeSpec::constant(value)) {
+                Value::Field(field) => field,
+                Value::Number(_) => unreachable!("emit returns a field"),
+            },
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Value> {
+        let mut operands = vec![self.parse_and()?];
+        while self.eat(Token::Op("||")) {
+            operands.push(self.parse_and()?);
+        }
+        Ok(self.combine(operands, NodeSpec::or))
+    }
+
+    fn parse_and(&mut self) -> Result<Value> {
+        let mut operands = vec![self.parse_comparison()?];
+        while self.eat(Token::Op("&&")) {
+            operands.push(self.parse_comparison()?);
+        }
+        Ok(self.combine(operands, NodeSpec::and))
+    }
+
+    /// Emits one node over all operands, or passes a single operand through.
+    fn combine(&mut self, mut operands: Vec<Value>, node: fn(Vec<FieldId>) -> NodeSpec) -> Value {
+        if operands.len() == 1 {
+            return operands.remove(0);
+        }
+        let inputs = operands.into_iter().map(|v| self.field(v)).collect();
+        self.emit(node(inputs))
+    }
+
+    fn parse_comparison(&mut self) -> Result<Value> {
+        let lhs = self.parse_additive()?;
+        let Token::Op(op @ ("<" | ">" | "<=" | ">=")) = self.peek().0 else {
+            return Ok(lhs);
+        };
+        self.bump();
+        let rhs = self.parse_additive()?;
+        let (a, b) = (self.field(lhs), self.field(rhs));
+        Ok(match op {
+            "<" => self.emit(NodeSpec::less(a, b)),
+            ">" => self.emit(NodeSpec::greater(a, b)),
+            "<=" => {
+                let greater = self.emit(NodeSpec::greater(a, b));
+                let greater = self.field(greater);
+                self.emit(NodeSpec::not(greater))
+            }
+            _ => {
+                let less = self.emit(NodeSpec::less(a, b));
+                let less = self.field(less);
+                self.emit(NodeSpec::not(less))
+            }
+        })
+    }
+
+    fn parse_additive(&mut self) -> Result<Value> {
+        let mut lhs = self.parse_multiplicative()?;
+        while let Token::Op(op @ ("+" | "-")) = self.peek().0 {
+            self.bump();
+            let rhs = self.parse_multiplicative()?;
+            let inputs = vec![self.field(lhs), self.field(rhs)];
+            lhs = self.emit(if op == "+" {
+                NodeSpec::add(inputs)
+            } else {
+                NodeSpec::sub(inputs)
+            });
+        }
+        Ok(lhs)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Value> {
+        let mut lhs = self.parse_unary()?;
+        while let Token::Op(op @ ("*" | "/" | "%")) = self.peek().0 {
+            self.bump();
+            let rhs = self.parse_unary()?;
+            let (a, b) = (self.field(lhs), self.field(rhs));
+            lhs = self.emit(match op {
+                "*" => NodeSpec::mul(vec![a, b]),
+                "/" => NodeSpec::div(a, b),
+                _ => NodeSpec::modulo(a, b),
+            });
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Value> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(self.peek().1, "expression nested too deeply"));
+        }
+        self.depth += 1;
+        let value = self.parse_unary_operand();
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_unary_operand(&mut self) -> Result<Value> {
+        if self.eat(Token::Op("-")) {
+            return Ok(match self.parse_unary()? {
+                Value::Number(value) => Value::Number(-value),
+                Value::Field(field) => self.emit(NodeSpec::scale(field, -1.0)),
+            });
+        }
+        if self.eat(Token::Op("!")) {
+            let value = self.parse_unary()?;
+            let field = self.field(value);
+            return Ok(self.emit(NodeSpec::not(field)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Value> {
+        let (token, pos) = self.bump();
+        match token {
+            Token::Number(value) => Ok(Value::Number(value)),
+            Token::LParen => {
+                let value = self.parse_or()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(value)
+            }
+            Token::Ident(name) if self.eat(Token::LParen) => {
+                let args = self.parse_args()?;
+                self.call(name, pos, args)
+            }
+            Token::Ident(name) if self.peek().0 == Token::Dot => {
+                let channel = self.parse_channel()?;
+                Ok(self.emit(NodeSpec::texture(name, channel)))
+            }
+            Token::Ident(name) => Ok(Value::Field(name.to_string())),
+            other => Err(self.error(
+                pos,
+                format!("expected a value but found {}", other.describe()),
+            )),
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Arg>> {
+        let mut args = Vec::new();
+        if self.eat(Token::RParen) {
+            return Ok(args);
+        }
+        loop {
+            let (token, pos) = self.peek();
+            if let Token::Str(text) = token {
+                self.bump();
+                args.push(Arg::Str(text.to_string(), pos));
+            } else {
+                args.push(Arg::Value(self.parse_or()?, pos));
+            }
+            if self.eat(Token::RParen) {
+                return Ok(args);
+            }
+            self.expect(Token::Comma, "',' or ')'")?;
+        }
+    }
+
+    fn parse_channel(&mut self) -> Result<TextureChannel> {
+        self.expect(Token::Dot, "'.'")?;
+        let (token, pos) = self.bump();
+        match token {
+            Token::Ident("r") => Ok(TextureChannel::R),
+            Token::Ident("g") => Ok(TextureChannel::G),
+            Token::Ident("b") => Ok(TextureChannel::B),
+            Token::Ident("a") => Ok(TextureChannel::A),
+            other => Err(self.error(
+                pos,
+                format!(
+                    "expected channel r, g, b, or a but found {}",
+                    other.describe()
+                ),
+            )),
+        }
+    }
+
+    fn call(&mut self, name: &str, pos: usize, args: Vec<Arg>) -> Result<Value> {
+        let arity = |count: usize| {
+            if args.len() == count {
+                Ok(())
+            } else {
+                Err(self.error(
+                    pos,
+                    format!(
+                        "{name}() takes {count} argument(s) but {} were given",
+                        args.len()
+                    ),
+                ))
+            }
+        };
+        match name {
+            "tex" | "field" => {
+                arity(1)?;
+                let Arg::Str(id, _) = &args[0] else {
+                    return Err(self.error(args[0].pos(), "expected a string"));
+                };
+                if name == "field" {
+                    return Ok(Value::Field(id.clone()));
+                }
+                let channel = if self.peek().0 == Token::Dot {
+                    self.parse_channel()?
+                } else {
+                    TextureChannel::R
+                };
+                Ok(self.emit(NodeSpec::texture(id.as_str(), channel)))
+            }
+            "position_x" | "position_y" => {
+                arity(0)?;
+                Ok(self.emit(if name == "position_x" {
+                    NodeSpec::position_x()
+                } else {
+                    NodeSpec::position_y()
+                }))
+            }
+            "min" | "max" => {
+                if args.is_empty() {
+                    return Err(self.error(pos, format!("{name}() takes at least one argument")));
+                }
+                let inputs = self.fields(args)?;
+                Ok(self.emit(if name == "min" {
+                    NodeSpec::min(inputs)
+                } else {
+                    NodeSpec::max(inputs)
+                }))
+            }
+            "abs" | "sqrt" | "sin" | "cos" | "tan" | "floor" | "fract" | "sign" | "invert" => {
+                arity(1)?;
+                let input = self.fields(args)?.remove(0);
+                Ok(self.emit(match name {
+                    "abs" => NodeSpec::abs(input),
+                    "sqrt" => NodeSpec::sqrt(input),
+                    "sin" => NodeSpec::sin(input),
+                    "cos" => NodeSpec::cos(input),
+                    "tan" => NodeSpec::tan(input),
+                    "floor" => NodeSpec::floor(input),
+                    "fract" => NodeSpec::fract(input),
+                    "sign" => NodeSpec::sign(input),
+                    _ => NodeSpec::invert(input),
+                }))
+            }
+            "atan2" => {
+                arity(2)?;
+                let [y, x]: [FieldId; 2] = self.fields(args)?.try_into().expect("two arguments");
+                Ok(self.emit(NodeSpec::atan2(y, x)))
+            }
+            "lerp" | "mix" | "select" => {
+                arity(3)?;
+                let [a, b, c]: [FieldId; 3] =
+                    self.fields(args)?.try_into().expect("three arguments");
+                Ok(self.emit(if name == "select" {
+                    NodeSpec::select(a, b, c)
+                } else {
+                    NodeSpec::lerp(a, b, c)
+                }))
+            }
+            "smoothstep" => {
+                arity(3)?;
+                let mut args = args.into_iter();
+                let edge0 = self.number(args.next())?;
+                let edge1 = self.number(args.next())?;
+                let input = self.field_arg(args.next())?;
+                Ok(self.emit(NodeSpec::smoothstep(input, edge0, edge1)))
+            }
+            "step" => {
+                arity(2)?;
+                let mut args = args.into_iter();
+                let edge = self.number(args.next())?;
+                let input = self.field_arg(args.next())?;
+                Ok(self.emit(NodeSpec::step(input, edge)))
+            }
+            "clamp" | "pow" | "remap" => {
+                arity(match name {
+                    "clamp" => 3,
+                    "pow" => 2,
+                    _ => 5,
+                })?;
+                let mut args = args.into_iter();
+                let input = self.field_arg(args.next())?;
+                let params = args
+                    .map(|arg| self.number(Some(arg)))
+                    .collect::<Result<Vec<f32>>>()?;
+                Ok(self.emit(match name {
+                    "clamp" => NodeSpec::clamp(input, params[0], params[1]),
+                    "pow" => NodeSpec::pow(input, params[0]),
+                    _ => NodeSpec::remap(input, (params[0], params[1]), (params[2], params[3])),
+                }))
+            }
+            _ => Err(self.error(pos, format!("unknown function '{name}'"))),
+        }
+    }
+
+    fn fields(&mut self, args: Vec<Arg>) -> Result<Vec<FieldId>> {
+        args.into_iter()
+            .map(|arg| self.field_arg(Some(arg)))
+            .collect()
+    }
+
+    fn field_arg(&mut self, arg: Option<Arg>) -> Result<FieldId> {
+        match arg.expect("arity checked") {
+            Arg::Value(value, _) => Ok(self.field(value)),
+            Arg::Str(_, pos) => Err(self.error(pos, "expected a value but found a string")),
+        }
+    }
+
+    fn number(&self, arg: Option<Arg>) -> Result<f32> {
+        match arg.expect("arity checked") {
+            Arg::Value(Value::Number(value), _) => Ok(value),
+            arg => Err(self.error(arg.pos(), "expected a number literal")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(nodes: &[(FieldId, NodeSpec)]) -> Vec<&str> {
+        nodes.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn expands_nested_expression_into_nodes() {
+        let nodes = expand(
+            "prob",
+            "smoothstep(0.2, 0.6, tex(\"height\").r) * (1 - mask_roads.r)",
+        )
+        .unwrap();
+        assert_eq!(
+            ids(&nodes),
+            ["prob/0", "prob/1", "prob/2", "prob/3", "prob/4", "prob"]
+        );
+
+        let (_, smoothstep) = &nodes[1];
+        assert!(matches!(
+            smoothstep,
+            NodeSpec::SmoothStep { inputs, params }
+                if inputs == &["prob/0"] && params.edge0 == 0.2 && params.edge1 == 0.6
+        ));
+        assert!(matches!(
+            &nodes[2].1,
+            NodeSpec::Texture { params } if params.texture_id == "mask_roads"
+        ));
+        assert!(matches!(&nodes[4].1, NodeSpec::Sub { inputs } if inputs == &["prob/3", "prob/2"]));
+        assert!(matches!(&nodes[5].1, NodeSpec::Mul { inputs } if inputs == &["prob/1", "prob/4"]));
+    }
+
+    #[test]
+    fn bare_references_and_numbers_get_a_root_node() {
+        let nodes = expand("out", "height").unwrap();
+        assert!(
+            matches!(&nodes[..], [(id, NodeSpec::Add { inputs })] if id == "out" && inputs == &["height"])
+        );
+        let nodes = expand("out", "-2.5e-1").unwrap();
+        assert!(
+            matches!(&nodes[..], [(_, NodeSpec::Constant { params })] if params.value == -0.25)
+        );
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        let nodes = expand("out", "a + b * c >= 1 || !d").unwrap();
+        let kinds: Vec<&str> = nodes
+            .iter()
+            .map(|(_, spec)| match spec {
+                NodeSpec::Mul { .. } => "mul",
+                NodeSpec::Add { .. } => "add",
+                NodeSpec::Constant { .. } => "const",
+                NodeSpec::Less { .. } => "less",
+                NodeSpec::Not { .. } => "not",
+                NodeSpec::Or { .. } => "or",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["mul", "add", "const", "less", "not", "not", "or"]);
+    }
+
+    #[test]
+    fn reports_positioned_errors() {
+        let message = |source: &str| match expand("out", source) {
+            Err(Error::Compile(message)) => message,
+            other => panic!("expected a compile error, got {other:?}"),
+        };
+        assert_eq!(
+            message("clamp(x, 0, 1"),
+            "Node 'out' (Expr) at 1:14: expected ',' or ')' but found end of expression"
+        );
+        assert_eq!(
+            message("a +\n  pow(b, c)"),
+            "Node 'out' (Expr) at 2:10: expected a number literal"
+        );
+        assert_eq!(
+            message("height.q"),
+            "Node 'out' (Expr) at 1:8: expected channel r, g, b, or a but found 'q'"
+        );
+        assert_eq!(
+            message("noise(x)"),
+            "Node 'out' (Expr) at 1:1: unknown function 'noise'"
+        );
+        assert_eq!(
+            message("a $ b"),
+            "Node 'out' (Expr) at 1:3: unexpected character '$'"
+        );
+        assert_eq!(
+            message("\"héight\" $"),
+            "Node 'out' (Expr) at 1:10: unexpected character '$'"
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_a_compile_error() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(expand("out", &nested(200)).is_ok());
+
+        for source in [nested(100_000), "-".repeat(100_000) + "x"] {
+            let Err(Error::Compile(message)) = expand("out", &source) else {
+                panic!("expected a compile error");
+            };
+            assert!(message.ends_with("expression nested too deeply"));
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Raster filters for baked field graph nodes.
+//!
+//! Filter nodes ([`crate::fieldgraph::NodeSpec::is_raster_filter`]) bake their input over the
+//! chunk grid, including its halo, and compute their output over the whole raster. Kernels
+//! read edge cells beyond the raster, so results within the chunk are exact as long as the
+//! grid halo covers the kernel reach. Scatter runs raise the grid halo to
+//! [`FieldProgram::filter_halo`](crate::fieldgraph::FieldProgram::filter_halo) for this;
+//! distance transforms are not covered and stay approximate near chunk borders.
+use crate::fieldgraph::edt::{edt_normalize_raster, edt_signed_raster};
+use crate::fieldgraph::{
+    BlurKernel, BlurParams, ChunkGrid, ConvolveParams, DerivativeKind, DerivativeParams,
+    MorphologyOp, MorphologyParams, NodeSpec, Raster,
+};
+
+/// Filter computed over the baked raster of a node's input.
+#[derive(Clone, Debug)]
+pub(crate) enum RasterFilter {
+    EdtNormalize { threshold: f32, d_max: f32 },
+    EdtSigned { threshold: f32, d_max: f32 },
+    Blur(BlurParams),
+    Morphology(MorphologyParams),
+    Convolve(ConvolveParams),
+    Derivative(DerivativeParams),
+}
+
+impl RasterFilter {
+    /// Applies the filter to input values sampled at the cells of `grid` in row-major order.
+    pub(crate) fn apply(&self, values: &[f32], grid: &ChunkGrid) -> Raster {
+        match self {
+            RasterFilter::EdtNormalize { threshold, d_max } => {
+                edt_normalize_raster(values, *threshold, *d_max, grid)
+            }
+            RasterFilter::EdtSigned { threshold, d_max } => {
+                edt_signed_raster(values, *threshold, *d_max, grid)
+            }
+            RasterFilter::Blur(params) => blur_raster(values, params, grid),
+            RasterFilter::Morphology(params) => morphology_raster(values, params, grid),
+            RasterFilter::Convolve(params) => convolve_raster(values, params, grid),
+            RasterFilter::Derivative(params) => derivative_raster(values, params, grid),
+        }
+    }
+}
+
+/// Blurs input values sampled at the cells of `grid` with a separable box or Gaussian kernel.
+pub fn blur_raster(values: &[f32], params: &BlurParams, grid: &ChunkGrid) -> Raster {
+    let radius = radius_in_cells(params.radius, grid);
+    let reach = radius.ceil() as usize;
+    let weights: Vec<f32> = match params.kernel {
+        BlurKernel::Box => vec![1.0; 2 * reach + 1],
+        BlurKernel::Gaussian => {
+            let sigma = radius / 3.0;
+            (0..=2 * reach)
+                .map(|i| {
+                    let d = i as f32 - reach as f32;
+                    if sigma > 0.0 {
+                        (-(d * d) / (2.0 * sigma * sigma)).exp()
+                    } else {
+                        f32::from(d == 0.0)
+                    }
+                })
+                .collect()
+        }
+    };
+    let sum: f32 = weights.iter().sum();
+    let weights: Vec<f32> = weights.iter().map(|w| w / sum).collect();
+
+    let (w, h) = (grid.total_width(), grid.total_height());
+    let mut rows = vec![0.0; values.len()];
+    for y in 0..h {
+        for x in 0..w {
+            rows[y * w + x] = weights
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| values[y * w + clamped(x, k, reach, w)] * weight)
+                .sum();
+        }
+    }
+
+    let mut raster = Raster::new(grid.clone());
+    for y in 0..h {
+        for x in 0..w {
+            raster.data[y * w + x] = weights
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| rows[clamped(y, k, reach, h) * w + x] * weight)
+                .sum();
+        }
+    }
+    raster
+}
+
+/// Dilates or erodes input values sampled at the cells of `grid` over a disk.
+pub fn morphology_raster(values: &[f32], params: &MorphologyParams, grid: &ChunkGrid) -> Raster {
+    let radius = radius_in_cells(params.radius, grid);
+    let reach = radius.floor() as isize;
+    let offsets: Vec<(isize, isize)> = (-reach..=reach)
+        .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| ((dx * dx + dy * dy) as f32) <= radius * radius)
+        .collect();
+
+    let (w, h) = (grid.total_width(), grid.total_height());
+    let mut raster = Raster::new(grid.clone());
+    for y in 0..h {
+        for x in 0..w {
+            let neighbours = offsets
+                .iter()
+                .map(|(dx, dy)| values[offset(y, *dy, h) * w + offset(x, *dx, w)]);
+            raster.data[y * w + x] = match params.op {
+                MorphologyOp::Dilate => neighbours.fold(f32::NEG_INFINITY, f32::max),
+                MorphologyOp::Erode => neighbours.fold(f32::INFINITY, f32::min),
+            };
+        }
+    }
+    raster
+}
+
+/// Convolves input values sampled at the cells of `grid` with a kernel centered on each cell.
+pub fn convolve_raster(values: &[f32], params: &ConvolveParams, grid: &ChunkGrid) -> Raster {
+    let (kw, kh) = (params.width as usize, params.height as usize);
+    let (rx, ry) = ((kw / 2) as isize, (kh / 2) as isize);
+    let sum: f32 = params.weights.iter().sum();
+    let scale = if params.normalize && sum != 0.0 {
+        1.0 / sum
+    } else {
+        1.0
+    };
+
+    let (w, h) = (grid.total_width(), grid.total_height());
+    let mut raster = Raster::new(grid.clone());
+    for y in 0..h {
+        for x in 0..w {
+            let mut acc = 0.0;
+            for (ky, row) in params.weights.chunks_exact(kw.max(1)).take(kh).enumerate() {
+                let sy = offset(y, ky as isize - ry, h);
+                for (kx, weight) in row.iter().enumerate() {
+                    acc += values[sy * w + offset(x, kx as isize - rx, w)] * weight;
+                }
+            }
+            raster.data[y * w + x] = acc * scale;
+        }
+    }
+    raster
+}
+
+/// Computes slope, aspect, curvature, or normal components of input values sampled at the
+/// cells of `grid` with central differences over one cell.
+pub fn derivative_raster(values: &[f32], params: &DerivativeParams, grid: &ChunkGrid) -> Raster {
+    let (w, h) = (grid.total_width(), grid.total_height());
+    let mut raster = Raster::new(grid.clone());
+    if grid.cell_size <= 0.0 {
+        return raster;
+    }
+    let scale = params.height_scale;
+    let step = grid.cell_size;
+    for y in 0..h {
+        for x in 0..w {
+            let at = |dx: isize, dy: isize| values[offset(y, dy, h) * w + offset(x, dx, w)] * scale;
+            let gx = (at(1, 0) - at(-1, 0)) / (2.0 * step);
+            let gy = (at(0, 1) - at(0, -1)) / (2.0 * step);
+            raster.data[y * w + x] = match params.kind {
+                DerivativeKind::GradientMagnitude => gx.hypot(gy),
+                DerivativeKind::Slope => gx.hypot(gy).atan(),
+                DerivativeKind::Aspect if gx == 0.0 && gy == 0.0 => 0.0,
+                DerivativeKind::Aspect => (-gy).atan2(-gx),
+                DerivativeKind::Curvature => {
+                    (at(1, 0) + at(-1, 0) + at(0, 1) + at(0, -1) - 4.0 * at(0, 0)) / (step * step)
+                }
+                DerivativeKind::NormalX => -gx / (gx * gx + gy * gy + 1.0).sqrt(),
+                DerivativeKind::NormalY => -gy / (gx * gx + gy * gy + 1.0).sqrt(),
+                DerivativeKind::NormalZ => 1.0 / (gx * gx + gy * gy + 1.0).sqrt(),
+            };
+        }
+    }
+    raster
+}
+
+fn radius_in_cells(radius: f32, grid: &ChunkGrid) -> f32 {
+    cells(radius, grid.cell_size)
+}
+
+fn cells(radius: f32, cell_size: f32) -> f32 {
+    if cell_size > 0.0 {
+        (radius / cell_size).max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// Number of cells a filter node reads beyond each cell with rasters of `cell_size`, or 0 for
+/// nodes that are not local kernels.
+pub(crate) fn kernel_reach(spec: &NodeSpec, cell_size: f32) -> usize {
+    match spec {
+        NodeSpec::Blur { params, .. } => cells(params.radius, cell_size).ceil() as usize,
+        NodeSpec::Morphology { params, .. } => cells(params.radius, cell_size).floor() as usize,
+        NodeSpec::Convolve { params, .. } => (params.width.max(params.height) / 2) as usize,
+        NodeSpec::Derivative { .. } => 1,
+        _ => 0,
+    }
+}
+
+/// Index of kernel tap `k` around `i` for a kernel reaching `reach` cells, clamped to `[0, n)`.
+#[inline]
+fn clamped(i: usize, k: usize, reach: usize, n: usize) -> usize {
+    (i + k).saturating_sub(reach).min(n - 1)
+}
+
+/// Index `i + d`, clamped to `[0, n)`.
+#[inline]
+fn offset(i: usize, d: isize, n: usize) -> usize {
+    (i as isize + d).clamp(0, n as isize - 1) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+    use crate::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
+    use crate::fieldgraph::spec::FieldGraphSpec;
+    use crate::fieldgraph::{TextureFilter, TextureSampler};
+
+    fn line_grid(width: usize) -> ChunkGrid {
+        ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 1.0,
+            width,
+            height: 1,
+            halo: 0,
+        }
+    }
+
+    #[test]
+    fn filter_chains_add_up_their_reach() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("blurred", NodeSpec::blur("x".into(), BlurKernel::Box, 3.0));
+        spec.add("eroded", NodeSpec::erode("blurred".into(), 2.5));
+        spec.add(
+            "sharpened",
+            NodeSpec::convolve("x".into(), 5, 3, vec![1.0; 15], true),
+        );
+        spec.add("edges", NodeSpec::edt_normalize("x".into(), 0.5, 10.0));
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+
+        assert_eq!(program.filter_halo(1.0), 5);
+        assert_eq!(program.filter_halo(0.5), 11);
+
+        spec.set_raster_sampler(
+            "eroded",
+            TextureSampler::default().with_filter(TextureFilter::Bicubic),
+        );
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+        assert_eq!(program.filter_halo(1.0), 7);
+    }
+
+    #[test]
+    fn box_blur_averages_neighbours() {
+        let grid = line_grid(5);
+        let params = BlurParams {
+            kernel: BlurKernel::Box,
+            radius: 1.0,
+        };
+        let raster = blur_raster(&[0.0, 0.0, 3.0, 0.0, 0.0], &params, &grid);
+        let expected = [0.0, 1.0, 1.0, 1.0, 0.0];
+        for (value, expected) in raster.data.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn gaussian_blur_preserves_constant_fields() {
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 0.5,
+            width: 4,
+            height: 4,
+            halo: 1,
+        };
+        let params = BlurParams {
+            kernel: BlurKernel::Gaussian,
+            radius: 1.5,
+        };
+        let raster = blur_raster(&[0.25; 36], &params, &grid);
+        assert!(raster.data.iter().all(|v| (v - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn morphology_grows_and_shrinks_masks() {
+        let grid = line_grid(5);
+        let values = [0.0, 0.0, 1.0, 1.0, 0.0];
+        let dilate = MorphologyParams {
+            op: MorphologyOp::Dilate,
+            radius: 1.0,
+        };
+        let erode = MorphologyParams {
+            op: MorphologyOp::Erode,
+            radius: 1.0,
+        };
+        assert_eq!(
+            morphology_raster(&values, &dilate, &grid).data,
+            vec![0.0, 1.0, 1.0, 1.0, 1.0]
+        );
+        assert_eq!(morphology_raster(&values, &erode, &grid).data, vec![0.0; 5]);
+    }
+
+    #[test]
+    fn convolve_applies_centered_kernel() {
+        let grid = line_grid(4);
+        let params = ConvolveParams {
+            width: 3,
+            height: 1,
+            weights: vec![-1.0, 0.0, 1.0],
+            normalize: false,
+        };
+        let raster = convolve_raster(&[0.0, 1.0, 3.0, 6.0], &params, &grid);
+        assert_eq!(raster.data, vec![1.0, 3.0, 5.0, 3.0]);
+    }
+
+    fn derivative(kind: DerivativeKind, values: &[f32], grid: &ChunkGrid) -> Vec<f32> {
+        let params = DerivativeParams {
+            kind,
+            height_scale: 1.0,
+        };
+        derivative_raster(values, &params, grid).data
+    }
+
+    #[test]
+    fn derivatives_of_a_ramp() {
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 0.5,
+            width: 3,
+            height: 3,
+            halo: 0,
+        };
+        // Height rises by 1 per world unit towards -x.
+        let values: Vec<f32> = (0..9).map(|i| -0.5 * (i % 3) as f32).collect();
+        let center = |kind| derivative(kind, &values, &grid)[4];
+
+        assert!((center(DerivativeKind::GradientMagnitude) - 1.0).abs() < 1e-6);
+        let slope = center(DerivativeKind::Slope);
+        assert!((slope - std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+        assert!((center(DerivativeKind::Aspect)).abs() < 1e-6);
+        assert!(center(DerivativeKind::Curvature).abs() < 1e-6);
+        let n = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((center(DerivativeKind::NormalX) - n).abs() < 1e-6);
+        assert!(center(DerivativeKind::NormalY).abs() < 1e-6);
+        assert!((center(DerivativeKind::NormalZ) - n).abs() < 1e-6);
+    }
+
+    #[test]
+    fn curvature_is_positive_in_valleys() {
+        let grid = line_grid(3);
+        let valley = derivative(DerivativeKind::Curvature, &[1.0, 0.0, 1.0], &grid);
+        assert_eq!(valley[1], 2.0);
+        let flat = derivative(DerivativeKind::Aspect, &[0.5; 3], &grid);
+        assert_eq!(flat, vec![0.0; 3]);
+    }
+}
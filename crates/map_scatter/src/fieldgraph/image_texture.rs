@@ -0,0 +1,298 @@
+//! Image-backed textures, available with the `image` feature.
+//!
+//! [`DynamicImageTexture`] keeps the texels of an [`image::DynamicImage`] in their original
+//! precision (8-bit, 16-bit, or 32-bit float) and maps the image over a domain like
+//! [`OverlayTexture`](crate::scatter::overlay::OverlayTexture): the image spans
+//! `domain_extent` around `domain_center`, and its first row lies at the minimum Y.
+use std::path::Path;
+use std::sync::OnceLock;
+
+use glam::Vec2;
+use image::DynamicImage;
+
+use crate::error::{Error, Result};
+use crate::fieldgraph::texture::{Texture, TextureChannel, TextureSampler};
+
+/// Encoding of the color channels of 8-bit and 16-bit images.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageColorSpace {
+    /// Values are used as stored. Right for data such as heightmaps, masks, and splatmaps.
+    #[default]
+    Linear,
+    /// Color channels are sRGB-encoded and converted to linear values when sampled. Alpha
+    /// and 32-bit float images are always linear.
+    Srgb,
+}
+
+#[derive(Clone, Debug)]
+enum Texels {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+/// Texture sampling the texels of a decoded image over a domain.
+///
+/// Grayscale images return their value for the `R`, `G`, and `B` channels. Images without
+/// alpha return `1.0` for `A`. The `ImageTexture` of `bevy_map_scatter` samples missing
+/// channels by the same rule.
+#[derive(Clone, Debug)]
+pub struct DynamicImageTexture {
+    domain_extent: Vec2,
+    domain_center: Vec2,
+    width: u32,
+    height: u32,
+    channels: usize,
+    texels: Texels,
+    color_space: ImageColorSpace,
+}
+
+impl DynamicImageTexture {
+    /// Creates a texture from a decoded image mapped over the given domain.
+    ///
+    /// Luma, luma-alpha, RGB, and RGBA images keep their 8-bit, 16-bit, or 32-bit float
+    /// texels. Other layouts are converted to 32-bit float RGBA.
+    pub fn from_image(image: DynamicImage, domain_extent: Vec2, domain_center: Vec2) -> Self {
+        let (width, height) = (image.width(), image.height());
+        let (channels, texels) = match image {
+            DynamicImage::ImageLuma8(image) => (1, Texels::U8(image.into_raw())),
+            DynamicImage::ImageLumaA8(image) => (2, Texels::U8(image.into_raw())),
+            DynamicImage::ImageRgb8(image) => (3, Texels::U8(image.into_raw())),
+            DynamicImage::ImageRgba8(image) => (4, Texels::U8(image.into_raw())),
+            DynamicImage::ImageLuma16(image) => (1, Texels::U16(image.into_raw())),
+            DynamicImage::ImageLumaA16(image) => (2, Texels::U16(image.into_raw())),
+            DynamicImage::ImageRgb16(image) => (3, Texels::U16(image.into_raw())),
+            DynamicImage::ImageRgba16(image) => (4, Texels::U16(image.into_raw())),
+            DynamicImage::ImageRgb32F(image) => (3, Texels::F32(image.into_raw())),
+            DynamicImage::ImageRgba32F(image) => (4, Texels::F32(image.into_raw())),
+            image => (4, Texels::F32(image.into_rgba32f().into_raw())),
+        };
+        Self {
+            domain_extent,
+            domain_center,
+            width,
+            height,
+            channels,
+            texels,
+            color_space: ImageColorSpace::Linear,
+        }
+    }
+
+    /// Opens and decodes an image file mapped over the given domain.
+    ///
+    /// The file formats available are those enabled on the `image` crate.
+    pub fn open(path: impl AsRef<Path>, domain_extent: Vec2, domain_center: Vec2) -> Result<Self> {
+        let image = image::open(path)?;
+        Ok(Self::from_image(image, domain_extent, domain_center))
+    }
+
+    /// Creates a single-channel texture from 32-bit float values in row-major order.
+    pub fn from_gray_f32(
+        width: u32,
+        height: u32,
+        data: Vec<f32>,
+        domain_extent: Vec2,
+        domain_center: Vec2,
+    ) -> Result<Self> {
+        Self::from_f32(1, width, height, data, domain_extent, domain_center)
+    }
+
+    /// Creates an RGBA texture from 32-bit float values in row-major order.
+    pub fn from_rgba_f32(
+        width: u32,
+        height: u32,
+        data: Vec<f32>,
+        domain_extent: Vec2,
+        domain_center: Vec2,
+    ) -> Result<Self> {
+        Self::from_f32(4, width, height, data, domain_extent, domain_center)
+    }
+
+    fn from_f32(
+        channels: usize,
+        width: u32,
+        height: u32,
+        data: Vec<f32>,
+        domain_extent: Vec2,
+        domain_center: Vec2,
+    ) -> Result<Self> {
+        let expected = width as usize * height as usize * channels;
+        if data.len() != expected {
+            return Err(Error::InvalidConfig(format!(
+                "{width}x{height} texture with {channels} channel(s) needs {expected} values, got {}",
+                data.len()
+            )));
+        }
+        Ok(Self {
+            domain_extent,
+            domain_center,
+            width,
+            height,
+            channels,
+            texels: Texels::F32(data),
+            color_space: ImageColorSpace::Linear,
+        })
+    }
+
+    /// Sets the encoding of the color channels.
+    pub fn with_color_space(mut self, color_space: ImageColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Returns the size in texels as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the offset of a channel within a texel, or `None` for a missing alpha channel.
+    fn channel_offset(&self, channel: TextureChannel) -> Option<usize> {
+        match (self.channels, channel) {
+            (1 | 2, TextureChannel::R | TextureChannel::G | TextureChannel::B) => Some(0),
+            (2, TextureChannel::A) => Some(1),
+            (_, TextureChannel::R) => Some(0),
+            (_, TextureChannel::G) => Some(1),
+            (_, TextureChannel::B) => Some(2),
+            (4, TextureChannel::A) => Some(3),
+            _ => None,
+        }
+    }
+
+    fn texel(&self, offset: usize, srgb: bool, x: usize, y: usize) -> f32 {
+        let i = (y * self.width as usize + x) * self.channels + offset;
+        match &self.texels {
+            Texels::U8(data) if srgb => srgb8_to_linear()[data[i] as usize],
+            Texels::U8(data) => data[i] as f32 / 255.0,
+            Texels::U16(data) if srgb => srgb_to_linear(data[i] as f32 / 65535.0),
+            Texels::U16(data) => data[i] as f32 / 65535.0,
+            Texels::F32(data) => data[i],
+        }
+    }
+}
+
+impl Texture for DynamicImageTexture {
+    fn sample(&self, channel: TextureChannel, p: Vec2) -> f32 {
+        self.sample_with(channel, p, &TextureSampler::default())
+    }
+
+    fn sample_with(&self, channel: TextureChannel, p: Vec2, sampler: &TextureSampler) -> f32 {
+        let Some(offset) = self.channel_offset(channel) else {
+            return 1.0;
+        };
+        let (dw, dh) = (self.domain_extent.x, self.domain_extent.y);
+        if dw == 0.0 || dh == 0.0 {
+            return 0.0;
+        }
+        let srgb = self.color_space == ImageColorSpace::Srgb && channel != TextureChannel::A;
+
+        let local = p - self.domain_center;
+        let x = (local.x / dw + 0.5) * self.width as f32;
+        let y = (local.y / dh + 0.5) * self.height as f32;
+        sampler.sample_texels(self.width as usize, self.height as usize, x, y, |ix, iy| {
+            self.texel(offset, srgb, ix, iy)
+        })
+    }
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn srgb8_to_linear() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayAlphaImage, ImageBuffer, Luma, LumaA, Rgba, RgbaImage};
+
+    use super::*;
+    use crate::fieldgraph::texture::TextureFilter;
+
+    fn approx_eq(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn images_span_their_domain() {
+        let image: ImageBuffer<Luma<u16>, Vec<u16>> =
+            ImageBuffer::from_raw(2, 2, vec![0, 65535, 32768, 16384]).unwrap();
+        let texture = DynamicImageTexture::from_image(
+            DynamicImage::ImageLuma16(image),
+            Vec2::new(4.0, 2.0),
+            Vec2::new(10.0, 0.0),
+        );
+        assert_eq!(texture.size(), (2, 2));
+        approx_eq(texture.sample(TextureChannel::R, Vec2::new(9.0, -0.5)), 0.0);
+        approx_eq(
+            texture.sample(TextureChannel::G, Vec2::new(11.0, -0.5)),
+            1.0,
+        );
+        approx_eq(
+            texture.sample(TextureChannel::B, Vec2::new(11.0, 0.5)),
+            16384.0 / 65535.0,
+        );
+        approx_eq(texture.sample(TextureChannel::A, Vec2::new(9.0, 0.5)), 1.0);
+    }
+
+    #[test]
+    fn srgb_color_channels_are_linearized() {
+        let image = RgbaImage::from_pixel(1, 1, Rgba([128, 255, 0, 128]));
+        let texture = DynamicImageTexture::from_image(
+            DynamicImage::ImageRgba8(image.clone()),
+            Vec2::ONE,
+            Vec2::ZERO,
+        );
+        approx_eq(texture.sample(TextureChannel::R, Vec2::ZERO), 128.0 / 255.0);
+
+        let texture =
+            DynamicImageTexture::from_image(DynamicImage::ImageRgba8(image), Vec2::ONE, Vec2::ZERO)
+                .with_color_space(ImageColorSpace::Srgb);
+        approx_eq(texture.sample(TextureChannel::R, Vec2::ZERO), 0.21586);
+        approx_eq(texture.sample(TextureChannel::G, Vec2::ZERO), 1.0);
+        approx_eq(texture.sample(TextureChannel::A, Vec2::ZERO), 128.0 / 255.0);
+    }
+
+    #[test]
+    fn gray_alpha_images_expose_alpha() {
+        let image = GrayAlphaImage::from_pixel(1, 1, LumaA([51, 102]));
+        let texture = DynamicImageTexture::from_image(
+            DynamicImage::ImageLumaA8(image),
+            Vec2::ONE,
+            Vec2::ZERO,
+        );
+        approx_eq(texture.sample(TextureChannel::G, Vec2::ZERO), 0.2);
+        approx_eq(texture.sample(TextureChannel::A, Vec2::ZERO), 0.4);
+    }
+
+    #[test]
+    fn float_textures_are_filtered() {
+        let texture = DynamicImageTexture::from_gray_f32(
+            2,
+            1,
+            vec![-1.0, 3.0],
+            Vec2::new(2.0, 1.0),
+            Vec2::ZERO,
+        )
+        .unwrap()
+        .with_color_space(ImageColorSpace::Srgb);
+        let bilinear = TextureSampler::default().with_filter(TextureFilter::Bilinear);
+        approx_eq(
+            texture.sample(TextureChannel::R, Vec2::new(-0.5, 0.0)),
+            -1.0,
+        );
+        approx_eq(
+            texture.sample_with(TextureChannel::R, Vec2::ZERO, &bilinear),
+            1.0,
+        );
+
+        assert!(
+            DynamicImageTexture::from_rgba_f32(2, 2, vec![0.0; 4], Vec2::ONE, Vec2::ZERO).is_err()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Elementwise arithmetic, trigonometric, and logic operations.
+//!
+//! Every node of the math family maps the values of its inputs at one position to one output
+//! value. [`MathOp`] is shared by the runtime and the optimizer, so folded constants match
+//! evaluated values exactly.
+use crate::fieldgraph::NodeSpec;
+
+/// Elementwise operation of a math node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MathOp {
+    Div,
+    Mod,
+    Abs,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Atan2,
+    Floor,
+    Fract,
+    Sign,
+    Lerp,
+    Select,
+    Greater,
+    Less,
+    And,
+    Or,
+    Not,
+}
+
+impl MathOp {
+    /// Returns the operation of a math node, or `None` for other nodes.
+    pub(crate) fn from_spec(spec: &NodeSpec) -> Option<Self> {
+        Some(match spec {
+            NodeSpec::Div { .. } => MathOp::Div,
+            NodeSpec::Mod { .. } => MathOp::Mod,
+            NodeSpec::Abs { .. } => MathOp::Abs,
+            NodeSpec::Sqrt { .. } => MathOp::Sqrt,
+            NodeSpec::Sin { .. } => MathOp::Sin,
+            NodeSpec::Cos { .. } => MathOp::Cos,
+            NodeSpec::Tan { .. } => MathOp::Tan,
+            NodeSpec::Atan2 { .. } => MathOp::Atan2,
+            NodeSpec::Floor { .. } => MathOp::Floor,
+            NodeSpec::Fract { .. } => MathOp::Fract,
+            NodeSpec::Sign { .. } => MathOp::Sign,
+            NodeSpec::Lerp { .. } => MathOp::Lerp,
+            NodeSpec::Select { .. } => MathOp::Select,
+            NodeSpec::Greater { .. } => MathOp::Greater,
+            NodeSpec::Less { .. } => MathOp::Less,
+            NodeSpec::And { .. } => MathOp::And,
+            NodeSpec::Or { .. } => MathOp::Or,
+            NodeSpec::Not { .. } => MathOp::Not,
+            _ => return None,
+        })
+    }
+
+    /// Stable name of the operation.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            MathOp::Div => "div",
+            MathOp::Mod => "modulo",
+            MathOp::Abs => "abs",
+            MathOp::Sqrt => "sqrt",
+            MathOp::Sin => "sin",
+            MathOp::Cos => "cos",
+            MathOp::Tan => "tan",
+            MathOp::Atan2 => "atan2",
+            MathOp::Floor => "floor",
+            MathOp::Fract => "fract",
+            MathOp::Sign => "sign",
+            MathOp::Lerp => "lerp",
+            MathOp::Select => "select",
+            MathOp::Greater => "greater",
+            MathOp::Less => "less",
+            MathOp::And => "and",
+            MathOp::Or => "or",
+            MathOp::Not => "not",
+        }
+    }
+
+    /// Applies the operation to the input values at one position.
+    ///
+    /// Values missing from `args` read as zero.
+    pub(crate) fn apply(self, args: &[f32]) -> f32 {
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0.0);
+        let truth = |b: bool| if b { 1.0 } else { 0.0 };
+        match self {
+            MathOp::Div => {
+                let b = arg(1);
+                if b == 0.0 {
+                    0.0
+                } else {
+                    arg(0) / b
+                }
+            }
+            MathOp::Mod => {
+                let b = arg(1);
+                if b == 0.0 {
+                    0.0
+                } else {
+                    let a = arg(0);
+                    a - b * (a / b).floor()
+                }
+            }
+            MathOp::Abs => arg(0).abs(),
+            MathOp::Sqrt => arg(0).max(0.0).sqrt(),
+            MathOp::Sin => arg(0).sin(),
+            MathOp::Cos => arg(0).cos(),
+            MathOp::Tan => arg(0).tan(),
+            MathOp::Atan2 => arg(0).atan2(arg(1)),
+            MathOp::Floor => arg(0).floor(),
+            MathOp::Fract => {
+                let v = arg(0);
+                v - v.floor()
+            }
+            MathOp::Sign => {
+                let v = arg(0);
+                if v == 0.0 {
+                    0.0
+                } else {
+                    v.signum()
+                }
+            }
+            MathOp::Lerp => {
+                let a = arg(0);
+                a + (arg(1) - a) * arg(2)
+            }
+            MathOp::Select => {
+                if arg(0) > 0.0 {
+                    arg(1)
+                } else {
+                    arg(2)
+                }
+            }
+            MathOp::Greater => truth(arg(0) > arg(1)),
+            MathOp::Less => truth(arg(0) < arg(1)),
+            MathOp::And => truth(args.iter().all(|v| *v > 0.0)),
+            MathOp::Or => truth(args.iter().any(|v| *v > 0.0)),
+            MathOp::Not => truth(arg(0).is_nan() || arg(0) <= 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_handles_zero_divisors_and_signs() {
+        assert_eq!(MathOp::Div.apply(&[3.0, 2.0]), 1.5);
+        assert_eq!(MathOp::Div.apply(&[3.0, 0.0]), 0.0);
+        assert_eq!(MathOp::Mod.apply(&[-1.0, 3.0]), 2.0);
+        assert_eq!(MathOp::Mod.apply(&[1.0, 0.0]), 0.0);
+        assert_eq!(MathOp::Sqrt.apply(&[-4.0]), 0.0);
+        assert_eq!(MathOp::Fract.apply(&[-0.25]), 0.75);
+        assert_eq!(MathOp::Sign.apply(&[0.0]), 0.0);
+        assert_eq!(MathOp::Sign.apply(&[-2.0]), -1.0);
+        assert_eq!(
+            MathOp::Atan2.apply(&[1.0, 0.0]),
+            std::f32::consts::FRAC_PI_2
+        );
+    }
+
+    #[test]
+    fn blend_and_logic_ops() {
+        assert_eq!(MathOp::Lerp.apply(&[2.0, 4.0, 0.25]), 2.5);
+        assert_eq!(MathOp::Select.apply(&[1.0, 2.0, 3.0]), 2.0);
+        assert_eq!(MathOp::Select.apply(&[0.0, 2.0, 3.0]), 3.0);
+        assert_eq!(MathOp::Greater.apply(&[2.0, 1.0]), 1.0);
+        assert_eq!(MathOp::Less.apply(&[2.0, 1.0]), 0.0);
+        assert_eq!(MathOp::And.apply(&[1.0, 0.5]), 1.0);
+        assert_eq!(MathOp::And.apply(&[1.0, 0.0]), 0.0);
+        assert_eq!(MathOp::Or.apply(&[0.0, 0.5]), 1.0);
+        assert_eq!(MathOp::Not.apply(&[0.5]), 0.0);
+        assert_eq!(MathOp::Not.apply(&[f32::NAN]), 1.0);
+    }
+}
@@ -2,24 +2,45 @@
 //!
 //! This module groups types for authoring a directed acyclic graph (DAG) of field nodes,
 //! compiling it into an executable program, and evaluating it over chunked grids at runtime.
+pub mod bytecode;
 pub mod cache;
 pub mod compiler;
+pub mod coords;
 pub mod edt;
+pub mod expr;
+pub mod filter;
 pub mod grid;
+#[cfg(feature = "image")]
+pub mod image_texture;
+pub(crate) mod math;
 pub mod node;
+pub mod noise;
+pub mod optimizer;
 pub mod program;
 pub mod raster;
 pub mod runtime;
+pub mod shaping;
+pub mod shared;
 pub mod spec;
+pub(crate) mod subgraph;
 pub mod texture;
 
 pub use grid::{ChunkGrid, ChunkId};
+#[cfg(feature = "image")]
+pub use image_texture::{DynamicImageTexture, ImageColorSpace};
 pub use node::{
-    ClampParams, ConstantParams, EdtNormalizeParams, NodeSpec, PowParams, ScaleParams,
-    SmoothStepParams, TextureParams,
+    Axis, BlurKernel, BlurParams, ClampParams, ConstantParams, ConvolveParams, CurveInterpolation,
+    CurveParams, DerivativeKind, DerivativeParams, DistanceParams, DistanceShape, DomainWarpParams,
+    EdtNormalizeParams, EdtSignedParams, ExprParams, FractalKind, FractalParams,
+    LinearGradientParams, LutParams, MorphologyOp, MorphologyParams, NodeSpec, NoiseKind,
+    NoiseParams, PositionParams, PowParams, RadialGradientParams, RemapParams, ScaleParams,
+    SmoothStepParams, StepParams, SubgraphParams, TextureParams,
 };
+pub use optimizer::OptimizationReport;
 pub use program::{FieldProgram, NodeMeta};
 pub use raster::Raster;
-pub use texture::{Texture, TextureChannel, TextureRegistry};
+pub use texture::{
+    Texture, TextureAddress, TextureChannel, TextureFilter, TextureRegistry, TextureSampler,
+};
 
 pub type FieldId = String;
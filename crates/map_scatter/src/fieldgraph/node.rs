@@ -3,15 +3,17 @@
 //! This module defines the data model for field nodes used by the field graph
 //! subsystem. Each [`NodeSpec`] represents a typed operation in a DAG.
 
+use glam::Vec2;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::texture::TextureChannel;
+use super::texture::{TextureChannel, TextureSampler};
 use crate::fieldgraph::FieldId;
 
 /// Parameters for a constant value node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ConstantParams {
     /// The constant value.
     pub value: f32,
@@ -19,17 +21,22 @@ pub struct ConstantParams {
 
 /// Parameters for a texture sampling node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TextureParams {
     /// The ID of the texture to sample from.
     pub texture_id: String,
     /// The channel of the texture to sample.
     pub channel: TextureChannel,
+    /// Filter and addressing mode used to sample the texture.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sampler: TextureSampler,
 }
 
 /// Parameters for a clamp node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ClampParams {
     /// Minimum value to clamp to.
     pub min: f32,
@@ -39,7 +46,8 @@ pub struct ClampParams {
 
 /// Parameters for a smoothstep node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SmoothStepParams {
     /// Lower edge of the transition.
     pub edge0: f32,
@@ -49,7 +57,8 @@ pub struct SmoothStepParams {
 
 /// Parameters for a scale node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ScaleParams {
     /// Scaling factor.
     pub factor: f32,
@@ -57,15 +66,100 @@ pub struct ScaleParams {
 
 /// Parameters for a power node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PowParams {
     /// Exponent value.
     pub exp: f32,
 }
 
+/// Parameters for a remap node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemapParams {
+    /// Input value mapped to `out_min`.
+    pub in_min: f32,
+    /// Input value mapped to `out_max`.
+    pub in_max: f32,
+    /// Output value at `in_min`.
+    pub out_min: f32,
+    /// Output value at `in_max`.
+    pub out_max: f32,
+    /// Whether to clamp inputs to the input range before mapping.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clamp: bool,
+}
+
+/// Interpolation between the control points of a curve node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CurveInterpolation {
+    /// Straight segments between control points.
+    #[default]
+    Linear,
+    /// Monotone cubic spline through the control points, which never overshoots them.
+    Spline,
+}
+
+/// Parameters for a curve node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurveParams {
+    /// Control points `(input, output)` with strictly increasing inputs. Inputs outside the
+    /// first and last control point take their outputs.
+    pub points: Vec<(f32, f32)>,
+    /// Interpolation between control points.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub interpolation: CurveInterpolation,
+}
+
+/// Parameters for a step node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepParams {
+    /// Inputs at or above the threshold map to `1.0`, others to `0.0`.
+    pub threshold: f32,
+}
+
+/// Parameters for a lookup-table node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LutParams {
+    /// Input value mapped to the first table entry.
+    pub in_min: f32,
+    /// Input value mapped to the last table entry.
+    pub in_max: f32,
+    /// Evenly spaced table entries, linearly interpolated and clamped at both ends.
+    pub values: Vec<f32>,
+}
+
+/// Parameters for an expression node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExprParams {
+    /// Expression source, see [`crate::fieldgraph::expr`] for the syntax.
+    pub source: String,
+}
+
+/// Parameters for a subgraph instance node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubgraphParams {
+    /// Name of the [`crate::fieldgraph::spec::SubgraphSpec`] to instantiate.
+    pub name: String,
+}
+
 /// Parameters for an EDT normalize node.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub struct EdtNormalizeParams {
     /// Threshold value to avoid division by zero.
     pub threshold: f32,
@@ -73,9 +167,255 @@ pub struct EdtNormalizeParams {
     pub d_max: f32,
 }
 
+/// Parameters for a signed EDT node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EdtSignedParams {
+    /// Input values at or above the threshold are inside the mask.
+    pub threshold: f32,
+    /// Distance in raster cells mapped to `1.0` (inside) and `-1.0` (outside).
+    pub d_max: f32,
+}
+
+/// Kernel of a blur node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlurKernel {
+    /// Unweighted mean over the radius.
+    Box,
+    /// Gaussian weights with a standard deviation of a third of the radius.
+    Gaussian,
+}
+
+/// Parameters for a blur node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlurParams {
+    /// Kernel shape.
+    pub kernel: BlurKernel,
+    /// Kernel radius in world units.
+    pub radius: f32,
+}
+
+/// Operation of a morphology node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MorphologyOp {
+    /// Maximum over a disk, growing high regions.
+    Dilate,
+    /// Minimum over a disk, shrinking high regions.
+    Erode,
+}
+
+/// Parameters for a morphology node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MorphologyParams {
+    /// Dilation or erosion.
+    pub op: MorphologyOp,
+    /// Disk radius in world units.
+    pub radius: f32,
+}
+
+/// Parameters for a convolution node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvolveParams {
+    /// Kernel width in raster cells (odd).
+    pub width: u32,
+    /// Kernel height in raster cells (odd).
+    pub height: u32,
+    /// Kernel weights in row-major order, centered on the sampled cell.
+    pub weights: Vec<f32>,
+    /// Whether to divide by the sum of the weights (ignored if the sum is zero).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub normalize: bool,
+}
+
+/// Quantity computed by a derivative node from finite differences of its input.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DerivativeKind {
+    /// Length of the gradient, in input units per world unit.
+    GradientMagnitude,
+    /// Slope angle in radians, `0` on flat ground and approaching `PI / 2` on cliffs.
+    Slope,
+    /// Downhill direction in radians in `[-PI, PI]`, measured from the `+x` axis towards `+y`.
+    /// Flat cells have an aspect of `0`.
+    Aspect,
+    /// Laplacian of the input, positive in valleys and negative on ridges.
+    Curvature,
+    /// `x` component of the unit surface normal.
+    NormalX,
+    /// `y` component of the unit surface normal.
+    NormalY,
+    /// Vertical component of the unit surface normal, `1` on flat ground.
+    NormalZ,
+}
+
+/// Parameters for a derivative node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivativeParams {
+    /// Quantity to compute.
+    pub kind: DerivativeKind,
+    /// World units per input unit, applied to the input before differentiating.
+    pub height_scale: f32,
+}
+
+/// Base noise function used by a noise node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Smoothly interpolated random lattice values.
+    Value,
+    /// Classic Perlin gradient noise.
+    Perlin,
+    /// OpenSimplex2-style gradient noise on a triangular lattice.
+    OpenSimplex,
+    /// Worley (cellular) noise, distance to the nearest feature point.
+    Worley,
+}
+
+/// Octave combinator applied on top of a base noise.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FractalKind {
+    /// Fractal Brownian motion (sum of octaves).
+    Fbm,
+    /// Ridged multifractal (inverted absolute octaves, squared).
+    Ridged,
+    /// Billow (absolute octaves).
+    Billow,
+}
+
+/// Parameters for combining several octaves of a base noise.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FractalParams {
+    /// Octave combinator.
+    pub kind: FractalKind,
+    /// Number of octaves (at least one).
+    pub octaves: u32,
+    /// Frequency multiplier between octaves.
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves.
+    pub gain: f32,
+}
+
+/// Parameters for a noise source node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseParams {
+    /// Base noise function.
+    pub noise: NoiseKind,
+    /// Seed selecting the noise permutation.
+    pub seed: u32,
+    /// Frequency in cycles per world unit.
+    pub frequency: f32,
+    /// Optional octave combinator.
+    pub fractal: Option<FractalParams>,
+}
+
+/// World axis read by a position node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// Parameters for a position node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionParams {
+    /// Axis of the sample position to output.
+    pub axis: Axis,
+}
+
+/// Shape measured by a distance node, in world coordinates.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DistanceShape {
+    Point {
+        /// Target point.
+        point: (f32, f32),
+    },
+    Segment {
+        /// Segment start.
+        a: (f32, f32),
+        /// Segment end.
+        b: (f32, f32),
+    },
+    Polyline {
+        /// Polyline vertices in order.
+        points: Vec<(f32, f32)>,
+    },
+}
+
+/// Parameters for a distance node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceParams {
+    /// Shape to measure the distance to.
+    pub shape: DistanceShape,
+    /// Distance mapped to `1.0`; values are clamped. Use `0.0` for raw world units.
+    pub d_max: f32,
+}
+
+/// Parameters for a linear gradient node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearGradientParams {
+    /// World position where the gradient is `0.0`.
+    pub start: (f32, f32),
+    /// World position where the gradient is `1.0`.
+    pub end: (f32, f32),
+}
+
+/// Parameters for a radial gradient node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialGradientParams {
+    /// World-space center of the gradient.
+    pub center: (f32, f32),
+    /// Radius up to which the gradient is `0.0`.
+    pub inner_radius: f32,
+    /// Radius from which the gradient is `1.0`.
+    pub outer_radius: f32,
+}
+
+/// Parameters for a domain warp node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DomainWarpParams {
+    /// World units of displacement per unit of the offset fields.
+    pub amplitude: f32,
+}
+
 /// Specification of a node in the field graph.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
 pub enum NodeSpec {
     Constant {
         /// Constant node parameters.
@@ -85,6 +425,32 @@ pub enum NodeSpec {
         /// Texture sampling parameters.
         params: TextureParams,
     },
+    Noise {
+        /// Noise sampling parameters.
+        params: NoiseParams,
+    },
+    Position {
+        /// Position parameters.
+        params: PositionParams,
+    },
+    Distance {
+        /// Distance parameters.
+        params: DistanceParams,
+    },
+    LinearGradient {
+        /// Linear gradient parameters.
+        params: LinearGradientParams,
+    },
+    RadialGradient {
+        /// Radial gradient parameters.
+        params: RadialGradientParams,
+    },
+    DomainWarp {
+        /// Input field ids: the warped source, then the X and Y offset fields.
+        inputs: Vec<FieldId>,
+        /// Domain warp parameters.
+        params: DomainWarpParams,
+    },
     Add {
         /// Input field ids to sum.
         inputs: Vec<FieldId>,
@@ -109,6 +475,78 @@ pub enum NodeSpec {
         /// Input field id to invert.
         inputs: Vec<FieldId>,
     },
+    Div {
+        /// Dividend and divisor field ids; division by zero yields `0.0`.
+        inputs: Vec<FieldId>,
+    },
+    Mod {
+        /// Dividend and divisor field ids; the result has the sign of the divisor, and a zero divisor yields `0.0`.
+        inputs: Vec<FieldId>,
+    },
+    Abs {
+        /// Input field ids to take the absolute value of (first input used).
+        inputs: Vec<FieldId>,
+    },
+    Sqrt {
+        /// Input field ids to take the square root of (first input used); negative inputs yield `0.0`.
+        inputs: Vec<FieldId>,
+    },
+    Sin {
+        /// Input field ids in radians to take the sine of (first input used).
+        inputs: Vec<FieldId>,
+    },
+    Cos {
+        /// Input field ids in radians to take the cosine of (first input used).
+        inputs: Vec<FieldId>,
+    },
+    Tan {
+        /// Input field ids in radians to take the tangent of (first input used).
+        inputs: Vec<FieldId>,
+    },
+    Atan2 {
+        /// Y and X field ids; the result is the angle in radians in `[-PI, PI]`.
+        inputs: Vec<FieldId>,
+    },
+    Floor {
+        /// Input field ids to round down (first input used).
+        inputs: Vec<FieldId>,
+    },
+    Fract {
+        /// Input field ids to take the fractional part `v - floor(v)` of (first input used).
+        inputs: Vec<FieldId>,
+    },
+    Sign {
+        /// Input field ids to take the sign of (first input used); zero yields `0.0`.
+        inputs: Vec<FieldId>,
+    },
+    Lerp {
+        /// Field ids `a`, `b`, and `t`; the result is `a + (b - a) * t` without clamping `t`.
+        inputs: Vec<FieldId>,
+    },
+    Select {
+        /// Field ids `cond`, `a`, and `b`; the result is `a` where `cond > 0` and `b` elsewhere.
+        inputs: Vec<FieldId>,
+    },
+    Greater {
+        /// Field ids `a` and `b`; the result is `1.0` where `a > b` and `0.0` elsewhere.
+        inputs: Vec<FieldId>,
+    },
+    Less {
+        /// Field ids `a` and `b`; the result is `1.0` where `a < b` and `0.0` elsewhere.
+        inputs: Vec<FieldId>,
+    },
+    And {
+        /// Input field ids; the result is `1.0` where all inputs are positive and `0.0` elsewhere.
+        inputs: Vec<FieldId>,
+    },
+    Or {
+        /// Input field ids; the result is `1.0` where any input is positive and `0.0` elsewhere.
+        inputs: Vec<FieldId>,
+    },
+    Not {
+        /// Input field ids (first input used); the result is `1.0` where the input is not positive.
+        inputs: Vec<FieldId>,
+    },
     Scale {
         /// Input field ids to scale (first input used).
         inputs: Vec<FieldId>,
@@ -139,6 +577,73 @@ pub enum NodeSpec {
         /// EDT normalization parameters.
         params: EdtNormalizeParams,
     },
+    EdtSigned {
+        /// Input field ids for the signed EDT (first input used).
+        inputs: Vec<FieldId>,
+        /// Signed EDT parameters.
+        params: EdtSignedParams,
+    },
+    Blur {
+        /// Input field ids to blur (first input used).
+        inputs: Vec<FieldId>,
+        /// Blur parameters.
+        params: BlurParams,
+    },
+    Morphology {
+        /// Input field ids to dilate or erode (first input used).
+        inputs: Vec<FieldId>,
+        /// Morphology parameters.
+        params: MorphologyParams,
+    },
+    Convolve {
+        /// Input field ids to convolve (first input used).
+        inputs: Vec<FieldId>,
+        /// Convolution kernel.
+        params: ConvolveParams,
+    },
+    Derivative {
+        /// Input field ids to differentiate (first input used).
+        inputs: Vec<FieldId>,
+        /// Derivative parameters.
+        params: DerivativeParams,
+    },
+    Remap {
+        /// Input field ids to remap (first input used).
+        inputs: Vec<FieldId>,
+        /// Remap ranges.
+        params: RemapParams,
+    },
+    Curve {
+        /// Input field ids to shape (first input used).
+        inputs: Vec<FieldId>,
+        /// Curve control points.
+        params: CurveParams,
+    },
+    Step {
+        /// Input field ids to threshold (first input used).
+        inputs: Vec<FieldId>,
+        /// Step parameters.
+        params: StepParams,
+    },
+    Lut {
+        /// Input field ids to look up (first input used).
+        inputs: Vec<FieldId>,
+        /// Lookup table.
+        params: LutParams,
+    },
+    /// Expression expanded into nodes by the compiler. Fields it references are not listed
+    /// in [`NodeSpec::inputs`].
+    Expr {
+        /// Expression parameters.
+        params: ExprParams,
+    },
+    /// Instance of a named subgraph, inlined by the compiler.
+    Subgraph {
+        /// Fields bound to the subgraph inputs, in declaration order.
+        inputs: Vec<FieldId>,
+        /// Subgraph parameters.
+        params: SubgraphParams,
+    },
 }
 
 impl NodeSpec {
@@ -151,15 +656,119 @@ impl NodeSpec {
             | NodeSpec::Min { inputs }
             | NodeSpec::Max { inputs }
             | NodeSpec::Invert { inputs }
+            | NodeSpec::Div { inputs }
+            | NodeSpec::Mod { inputs }
+            | NodeSpec::Abs { inputs }
+            | NodeSpec::Sqrt { inputs }
+            | NodeSpec::Sin { inputs }
+            | NodeSpec::Cos { inputs }
+            | NodeSpec::Tan { inputs }
+            | NodeSpec::Atan2 { inputs }
+            | NodeSpec::Floor { inputs }
+            | NodeSpec::Fract { inputs }
+            | NodeSpec::Sign { inputs }
+            | NodeSpec::Lerp { inputs }
+            | NodeSpec::Select { inputs }
+            | NodeSpec::Greater { inputs }
+            | NodeSpec::Less { inputs }
+            | NodeSpec::And { inputs }
+            | NodeSpec::Or { inputs }
+            | NodeSpec::Not { inputs }
+            | NodeSpec::Scale { inputs, .. }
+            | NodeSpec::Clamp { inputs, .. }
+            | NodeSpec::SmoothStep { inputs, .. }
+            | NodeSpec::Pow { inputs, .. }
+            | NodeSpec::EdtNormalize { inputs, .. }
+            | NodeSpec::EdtSigned { inputs, .. }
+            | NodeSpec::Blur { inputs, .. }
+            | NodeSpec::Morphology { inputs, .. }
+            | NodeSpec::Convolve { inputs, .. }
+            | NodeSpec::Derivative { inputs, .. }
+            | NodeSpec::Remap { inputs, .. }
+            | NodeSpec::Curve { inputs, .. }
+            | NodeSpec::Step { inputs, .. }
+            | NodeSpec::Lut { inputs, .. }
+            | NodeSpec::Subgraph { inputs, .. }
+            | NodeSpec::DomainWarp { inputs, .. } => inputs,
+            NodeSpec::Constant { .. }
+            | NodeSpec::Texture { .. }
+            | NodeSpec::Noise { .. }
+            | NodeSpec::Position { .. }
+            | NodeSpec::Distance { .. }
+            | NodeSpec::LinearGradient { .. }
+            | NodeSpec::RadialGradient { .. }
+            | NodeSpec::Expr { .. } => &[],
+        }
+    }
+
+    /// Returns the input field IDs for this node for rewriting.
+    pub(crate) fn inputs_mut(&mut self) -> &mut [FieldId] {
+        match self {
+            NodeSpec::Add { inputs }
+            | NodeSpec::Sub { inputs }
+            | NodeSpec::Mul { inputs }
+            | NodeSpec::Min { inputs }
+            | NodeSpec::Max { inputs }
+            | NodeSpec::Invert { inputs }
+            | NodeSpec::Div { inputs }
+            | NodeSpec::Mod { inputs }
+            | NodeSpec::Abs { inputs }
+            | NodeSpec::Sqrt { inputs }
+            | NodeSpec::Sin { inputs }
+            | NodeSpec::Cos { inputs }
+            | NodeSpec::Tan { inputs }
+            | NodeSpec::Atan2 { inputs }
+            | NodeSpec::Floor { inputs }
+            | NodeSpec::Fract { inputs }
+            | NodeSpec::Sign { inputs }
+            | NodeSpec::Lerp { inputs }
+            | NodeSpec::Select { inputs }
+            | NodeSpec::Greater { inputs }
+            | NodeSpec::Less { inputs }
+            | NodeSpec::And { inputs }
+            | NodeSpec::Or { inputs }
+            | NodeSpec::Not { inputs }
             | NodeSpec::Scale { inputs, .. }
             | NodeSpec::Clamp { inputs, .. }
             | NodeSpec::SmoothStep { inputs, .. }
             | NodeSpec::Pow { inputs, .. }
-            | NodeSpec::EdtNormalize { inputs, .. } => inputs,
-            NodeSpec::Constant { .. } | NodeSpec::Texture { .. } => &[],
+            | NodeSpec::EdtNormalize { inputs, .. }
+            | NodeSpec::EdtSigned { inputs, .. }
+            | NodeSpec::Blur { inputs, .. }
+            | NodeSpec::Morphology { inputs, .. }
+            | NodeSpec::Convolve { inputs, .. }
+            | NodeSpec::Derivative { inputs, .. }
+            | NodeSpec::Remap { inputs, .. }
+            | NodeSpec::Curve { inputs, .. }
+            | NodeSpec::Step { inputs, .. }
+            | NodeSpec::Lut { inputs, .. }
+            | NodeSpec::Subgraph { inputs, .. }
+            | NodeSpec::DomainWarp { inputs, .. } => inputs,
+            NodeSpec::Constant { .. }
+            | NodeSpec::Texture { .. }
+            | NodeSpec::Noise { .. }
+            | NodeSpec::Position { .. }
+            | NodeSpec::Distance { .. }
+            | NodeSpec::LinearGradient { .. }
+            | NodeSpec::RadialGradient { .. }
+            | NodeSpec::Expr { .. } => &mut [],
         }
     }
 
+    /// Returns `true` for nodes computed over a baked raster of their input, such as EDT,
+    /// blur, morphology, convolution, and derivative nodes.
+    pub fn is_raster_filter(&self) -> bool {
+        matches!(
+            self,
+            NodeSpec::EdtNormalize { .. }
+                | NodeSpec::EdtSigned { .. }
+                | NodeSpec::Blur { .. }
+                | NodeSpec::Morphology { .. }
+                | NodeSpec::Convolve { .. }
+                | NodeSpec::Derivative { .. }
+        )
+    }
+
     /// Creates a new constant value node specification.
     pub fn constant(value: f32) -> Self {
         NodeSpec::Constant {
@@ -173,10 +782,147 @@ impl NodeSpec {
             params: TextureParams {
                 texture_id: id.into(),
                 channel,
+                sampler: TextureSampler::default(),
+            },
+        }
+    }
+
+    /// Creates a new texture sampling node specification with a filter and addressing mode.
+    pub fn texture_sampled(
+        id: impl Into<String>,
+        channel: TextureChannel,
+        sampler: TextureSampler,
+    ) -> Self {
+        NodeSpec::Texture {
+            params: TextureParams {
+                texture_id: id.into(),
+                channel,
+                sampler,
+            },
+        }
+    }
+
+    /// Creates a new single-octave noise node specification.
+    pub fn noise(noise: NoiseKind, seed: u32, frequency: f32) -> Self {
+        NodeSpec::Noise {
+            params: NoiseParams {
+                noise,
+                seed,
+                frequency,
+                fractal: None,
+            },
+        }
+    }
+
+    /// Creates a new multi-octave noise node specification with lacunarity 2 and gain 0.5.
+    pub fn fractal_noise(
+        noise: NoiseKind,
+        fractal: FractalKind,
+        seed: u32,
+        frequency: f32,
+        octaves: u32,
+    ) -> Self {
+        NodeSpec::Noise {
+            params: NoiseParams {
+                noise,
+                seed,
+                frequency,
+                fractal: Some(FractalParams {
+                    kind: fractal,
+                    octaves,
+                    lacunarity: 2.0,
+                    gain: 0.5,
+                }),
+            },
+        }
+    }
+
+    /// Creates a new node outputting the world X coordinate of the sample position.
+    pub fn position_x() -> Self {
+        NodeSpec::Position {
+            params: PositionParams { axis: Axis::X },
+        }
+    }
+
+    /// Creates a new node outputting the world Y coordinate of the sample position.
+    pub fn position_y() -> Self {
+        NodeSpec::Position {
+            params: PositionParams { axis: Axis::Y },
+        }
+    }
+
+    /// Creates a new distance-to-point node specification.
+    pub fn distance_to_point(point: Vec2, d_max: f32) -> Self {
+        NodeSpec::Distance {
+            params: DistanceParams {
+                shape: DistanceShape::Point {
+                    point: point.into(),
+                },
+                d_max,
+            },
+        }
+    }
+
+    /// Creates a new distance-to-segment node specification.
+    pub fn distance_to_segment(a: Vec2, b: Vec2, d_max: f32) -> Self {
+        NodeSpec::Distance {
+            params: DistanceParams {
+                shape: DistanceShape::Segment {
+                    a: a.into(),
+                    b: b.into(),
+                },
+                d_max,
+            },
+        }
+    }
+
+    /// Creates a new distance-to-polyline node specification.
+    pub fn distance_to_polyline(points: Vec<Vec2>, d_max: f32) -> Self {
+        NodeSpec::Distance {
+            params: DistanceParams {
+                shape: DistanceShape::Polyline {
+                    points: points.into_iter().map(Into::into).collect(),
+                },
+                d_max,
+            },
+        }
+    }
+
+    /// Creates a new linear gradient node specification.
+    pub fn linear_gradient(start: Vec2, end: Vec2) -> Self {
+        NodeSpec::LinearGradient {
+            params: LinearGradientParams {
+                start: start.into(),
+                end: end.into(),
+            },
+        }
+    }
+
+    /// Creates a new radial gradient node specification.
+    pub fn radial_gradient(center: Vec2, inner_radius: f32, outer_radius: f32) -> Self {
+        NodeSpec::RadialGradient {
+            params: RadialGradientParams {
+                center: center.into(),
+                inner_radius,
+                outer_radius,
             },
         }
     }
 
+    /// Creates a new domain warp node specification that samples `source` at the
+    /// position offset by `amplitude * (offset_x, offset_y)`.
+    pub fn domain_warp(
+        source: FieldId,
+        offset_x: FieldId,
+        offset_y: FieldId,
+        amplitude: f32,
+    ) -> Self {
+        NodeSpec::DomainWarp {
+            inputs: vec![source, offset_x, offset_y],
+            params: DomainWarpParams { amplitude },
+        }
+    }
+
     /// Creates a new addition node specification.
     pub fn add(inputs: Vec<FieldId>) -> Self {
         NodeSpec::Add { inputs }
@@ -209,6 +955,118 @@ impl NodeSpec {
         }
     }
 
+    /// Creates a new division node specification.
+    pub fn div(a: FieldId, b: FieldId) -> Self {
+        NodeSpec::Div { inputs: vec![a, b] }
+    }
+
+    /// Creates a new floored modulo node specification.
+    pub fn modulo(a: FieldId, b: FieldId) -> Self {
+        NodeSpec::Mod { inputs: vec![a, b] }
+    }
+
+    /// Creates a new absolute value node specification.
+    pub fn abs(input: FieldId) -> Self {
+        NodeSpec::Abs {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new square root node specification.
+    pub fn sqrt(input: FieldId) -> Self {
+        NodeSpec::Sqrt {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new sine node specification.
+    pub fn sin(input: FieldId) -> Self {
+        NodeSpec::Sin {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new cosine node specification.
+    pub fn cos(input: FieldId) -> Self {
+        NodeSpec::Cos {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new tangent node specification.
+    pub fn tan(input: FieldId) -> Self {
+        NodeSpec::Tan {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new two-argument arctangent node specification.
+    pub fn atan2(y: FieldId, x: FieldId) -> Self {
+        NodeSpec::Atan2 { inputs: vec![y, x] }
+    }
+
+    /// Creates a new floor node specification.
+    pub fn floor(input: FieldId) -> Self {
+        NodeSpec::Floor {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new fractional part node specification.
+    pub fn fract(input: FieldId) -> Self {
+        NodeSpec::Fract {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new sign node specification.
+    pub fn sign(input: FieldId) -> Self {
+        NodeSpec::Sign {
+            inputs: vec![input],
+        }
+    }
+
+    /// Creates a new linear interpolation node specification.
+    pub fn lerp(a: FieldId, b: FieldId, t: FieldId) -> Self {
+        NodeSpec::Lerp {
+            inputs: vec![a, b, t],
+        }
+    }
+
+    /// Creates a new selection node specification.
+    pub fn select(cond: FieldId, a: FieldId, b: FieldId) -> Self {
+        NodeSpec::Select {
+            inputs: vec![cond, a, b],
+        }
+    }
+
+    /// Creates a new greater-than comparison node specification.
+    pub fn greater(a: FieldId, b: FieldId) -> Self {
+        NodeSpec::Greater { inputs: vec![a, b] }
+    }
+
+    /// Creates a new less-than comparison node specification.
+    pub fn less(a: FieldId, b: FieldId) -> Self {
+        NodeSpec::Less { inputs: vec![a, b] }
+    }
+
+    /// Creates a new logical and node specification.
+    pub fn and(inputs: Vec<FieldId>) -> Self {
+        NodeSpec::And { inputs }
+    }
+
+    /// Creates a new logical or node specification.
+    pub fn or(inputs: Vec<FieldId>) -> Self {
+        NodeSpec::Or { inputs }
+    }
+
+    /// Creates a new logical not node specification.
+    pub fn not(input: FieldId) -> Self {
+        NodeSpec::Not {
+            inputs: vec![input],
+        }
+    }
+
     /// Creates a new scaling node specification.
     pub fn scale(input: FieldId, factor: f32) -> Self {
         NodeSpec::Scale {
@@ -241,6 +1099,74 @@ impl NodeSpec {
         }
     }
 
+    /// Creates a new remap node specification mapping `input_range` linearly onto
+    /// `output_range` without clamping.
+    pub fn remap(input: FieldId, input_range: (f32, f32), output_range: (f32, f32)) -> Self {
+        NodeSpec::Remap {
+            inputs: vec![input],
+            params: RemapParams {
+                in_min: input_range.0,
+                in_max: input_range.1,
+                out_min: output_range.0,
+                out_max: output_range.1,
+                clamp: false,
+            },
+        }
+    }
+
+    /// Creates a new curve node specification from `(input, output)` control points.
+    pub fn curve(
+        input: FieldId,
+        points: Vec<(f32, f32)>,
+        interpolation: CurveInterpolation,
+    ) -> Self {
+        NodeSpec::Curve {
+            inputs: vec![input],
+            params: CurveParams {
+                points,
+                interpolation,
+            },
+        }
+    }
+
+    /// Creates a new step node specification.
+    pub fn step(input: FieldId, threshold: f32) -> Self {
+        NodeSpec::Step {
+            inputs: vec![input],
+            params: StepParams { threshold },
+        }
+    }
+
+    /// Creates a new lookup-table node specification over the input range `[in_min, in_max]`.
+    pub fn lut(input: FieldId, in_min: f32, in_max: f32, values: Vec<f32>) -> Self {
+        NodeSpec::Lut {
+            inputs: vec![input],
+            params: LutParams {
+                in_min,
+                in_max,
+                values,
+            },
+        }
+    }
+
+    /// Creates a new expression node specification, such as
+    /// `smoothstep(0.2, 0.6, tex("height").r) * (1 - mask_roads.r)`.
+    pub fn expr(source: impl Into<String>) -> Self {
+        NodeSpec::Expr {
+            params: ExprParams {
+                source: source.into(),
+            },
+        }
+    }
+
+    /// Creates a new instance of the subgraph `name`, binding `inputs` to its inputs in order.
+    pub fn subgraph(name: impl Into<String>, inputs: Vec<FieldId>) -> Self {
+        NodeSpec::Subgraph {
+            inputs,
+            params: SubgraphParams { name: name.into() },
+        }
+    }
+
     /// Creates a new EDT normalization node specification.
     pub fn edt_normalize(input: FieldId, threshold: f32, d_max: f32) -> Self {
         NodeSpec::EdtNormalize {
@@ -248,4 +1174,72 @@ impl NodeSpec {
             params: EdtNormalizeParams { threshold, d_max },
         }
     }
+
+    /// Creates a new signed EDT node specification.
+    pub fn edt_signed(input: FieldId, threshold: f32, d_max: f32) -> Self {
+        NodeSpec::EdtSigned {
+            inputs: vec![input],
+            params: EdtSignedParams { threshold, d_max },
+        }
+    }
+
+    /// Creates a new blur node specification.
+    pub fn blur(input: FieldId, kernel: BlurKernel, radius: f32) -> Self {
+        NodeSpec::Blur {
+            inputs: vec![input],
+            params: BlurParams { kernel, radius },
+        }
+    }
+
+    /// Creates a new dilation node specification.
+    pub fn dilate(input: FieldId, radius: f32) -> Self {
+        NodeSpec::Morphology {
+            inputs: vec![input],
+            params: MorphologyParams {
+                op: MorphologyOp::Dilate,
+                radius,
+            },
+        }
+    }
+
+    /// Creates a new erosion node specification.
+    pub fn erode(input: FieldId, radius: f32) -> Self {
+        NodeSpec::Morphology {
+            inputs: vec![input],
+            params: MorphologyParams {
+                op: MorphologyOp::Erode,
+                radius,
+            },
+        }
+    }
+
+    /// Creates a new convolution node specification with a `width` x `height` kernel.
+    pub fn convolve(
+        input: FieldId,
+        width: u32,
+        height: u32,
+        weights: Vec<f32>,
+        normalize: bool,
+    ) -> Self {
+        NodeSpec::Convolve {
+            inputs: vec![input],
+            params: ConvolveParams {
+                width,
+                height,
+                weights,
+                normalize,
+            },
+        }
+    }
+
+    /// Creates a new derivative node specification with a height scale of `1.0`.
+    pub fn derivative(input: FieldId, kind: DerivativeKind) -> Self {
+        NodeSpec::Derivative {
+            inputs: vec![input],
+            params: DerivativeParams {
+                kind,
+                height_scale: 1.0,
+            },
+        }
+    }
 }
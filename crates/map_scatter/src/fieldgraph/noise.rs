@@ -0,0 +1,278 @@
+//! Procedural noise functions for noise source nodes.
+//!
+//! All functions are deterministic for a given seed and return values in `[0, 1]`,
+//! so they compose with the other field nodes without extra remapping.
+//!
+//! Supported base noises are described by [`NoiseKind`]; octave combinators by
+//! [`FractalKind`]. Sampling is driven by [`NoiseParams`] via [`sample_noise`].
+use glam::Vec2;
+
+use crate::fieldgraph::node::{FractalKind, FractalParams, NoiseKind, NoiseParams};
+
+/// Samples the noise described by `params` at world position `p`.
+pub fn sample_noise(params: &NoiseParams, p: Vec2) -> f32 {
+    let p = p * params.frequency;
+    match &params.fractal {
+        Some(fractal) => sample_fractal(params.noise, fractal, params.seed, p),
+        None => sample_base(params.noise, params.seed, p),
+    }
+}
+
+/// Samples a single octave of the given base noise in `[0, 1]`.
+pub fn sample_base(noise: NoiseKind, seed: u32, p: Vec2) -> f32 {
+    match noise {
+        NoiseKind::Value => value(seed, p),
+        NoiseKind::Perlin => perlin(seed, p),
+        NoiseKind::OpenSimplex => open_simplex(seed, p),
+        NoiseKind::Worley => worley(seed, p),
+    }
+}
+
+fn sample_fractal(noise: NoiseKind, fractal: &FractalParams, seed: u32, p: Vec2) -> f32 {
+    let octaves = fractal.octaves.max(1);
+    let mut sum = 0.0;
+    let mut norm = 0.0;
+    let mut amplitude = 1.0;
+    let mut q = p;
+
+    for octave in 0..octaves {
+        let octave_seed = seed.wrapping_add(octave.wrapping_mul(0x9E37_79B9));
+        let signed = sample_base(noise, octave_seed, q) * 2.0 - 1.0;
+        let v = match fractal.kind {
+            FractalKind::Fbm => signed * 0.5 + 0.5,
+            FractalKind::Ridged => {
+                let r = 1.0 - signed.abs();
+                r * r
+            }
+            FractalKind::Billow => signed.abs(),
+        };
+        sum += v * amplitude;
+        norm += amplitude;
+        amplitude *= fractal.gain;
+        q *= fractal.lacunarity;
+    }
+
+    if norm > 0.0 {
+        (sum / norm).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Value noise: smoothly interpolated random lattice values.
+fn value(seed: u32, p: Vec2) -> f32 {
+    let x0 = p.x.floor();
+    let y0 = p.y.floor();
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let tx = fade(p.x - x0);
+    let ty = fade(p.y - y0);
+
+    let v00 = lattice01(seed, ix, iy);
+    let v10 = lattice01(seed, ix.wrapping_add(1), iy);
+    let v01 = lattice01(seed, ix, iy.wrapping_add(1));
+    let v11 = lattice01(seed, ix.wrapping_add(1), iy.wrapping_add(1));
+
+    let a = lerp(v00, v10, tx);
+    let b = lerp(v01, v11, tx);
+    lerp(a, b, ty)
+}
+
+/// Classic Perlin gradient noise on a square lattice.
+fn perlin(seed: u32, p: Vec2) -> f32 {
+    let x0 = p.x.floor();
+    let y0 = p.y.floor();
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let fx = p.x - x0;
+    let fy = p.y - y0;
+
+    let g00 = gradient(seed, ix, iy).dot(Vec2::new(fx, fy));
+    let g10 = gradient(seed, ix.wrapping_add(1), iy).dot(Vec2::new(fx - 1.0, fy));
+    let g01 = gradient(seed, ix, iy.wrapping_add(1)).dot(Vec2::new(fx, fy - 1.0));
+    let g11 =
+        gradient(seed, ix.wrapping_add(1), iy.wrapping_add(1)).dot(Vec2::new(fx - 1.0, fy - 1.0));
+
+    let tx = fade(fx);
+    let ty = fade(fy);
+    let v = lerp(lerp(g00, g10, tx), lerp(g01, g11, tx), ty);
+
+    // Unit gradients bound 2D Perlin noise to [-sqrt(0.5), sqrt(0.5)].
+    (v * std::f32::consts::SQRT_2 * 0.5 + 0.5).clamp(0.0, 1.0)
+}
+
+/// OpenSimplex2-style gradient noise on a triangular (skewed) lattice.
+fn open_simplex(seed: u32, p: Vec2) -> f32 {
+    const F2: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+    const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+    const NORMALIZER: f32 = 99.836_85;
+
+    let s = (p.x + p.y) * F2;
+    let i = (p.x + s).floor();
+    let j = (p.y + s).floor();
+    let t = (i + j) * G2;
+    let x0 = p.x - (i - t);
+    let y0 = p.y - (j - t);
+    let (ii, jj) = (i as i32, j as i32);
+
+    let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+    let corners = [
+        (ii, jj, Vec2::new(x0, y0)),
+        (
+            ii.wrapping_add(i1),
+            jj.wrapping_add(j1),
+            Vec2::new(x0 - i1 as f32 + G2, y0 - j1 as f32 + G2),
+        ),
+        (
+            ii.wrapping_add(1),
+            jj.wrapping_add(1),
+            Vec2::new(x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2),
+        ),
+    ];
+
+    let mut v = 0.0;
+    for (cx, cy, d) in corners {
+        let a = 0.5 - d.length_squared();
+        if a > 0.0 {
+            let a2 = a * a;
+            v += a2 * a2 * gradient(seed, cx, cy).dot(d);
+        }
+    }
+
+    (v * NORMALIZER * 0.5 + 0.5).clamp(0.0, 1.0)
+}
+
+/// Worley (cellular) noise: distance to the nearest jittered feature point (F1).
+fn worley(seed: u32, p: Vec2) -> f32 {
+    let x0 = p.x.floor();
+    let y0 = p.y.floor();
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let local = Vec2::new(p.x - x0, p.y - y0);
+
+    let mut best = f32::INFINITY;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let cx = ix.wrapping_add(dx);
+            let cy = iy.wrapping_add(dy);
+            let h = hash2(seed, cx, cy);
+            let feature = Vec2::new(
+                dx as f32 + unit_from_bits(h),
+                dy as f32 + unit_from_bits(mix32(h ^ 0x68E3_1DA4)),
+            );
+            best = best.min(feature.distance_squared(local));
+        }
+    }
+
+    best.sqrt().clamp(0.0, 1.0)
+}
+
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[inline]
+fn lattice01(seed: u32, x: i32, y: i32) -> f32 {
+    unit_from_bits(hash2(seed, x, y))
+}
+
+#[inline]
+fn gradient(seed: u32, x: i32, y: i32) -> Vec2 {
+    let angle = unit_from_bits(hash2(seed, x, y)) * std::f32::consts::TAU;
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+#[inline]
+fn unit_from_bits(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+#[inline]
+fn hash2(seed: u32, x: i32, y: i32) -> u32 {
+    let mut h = seed ^ 0x27D4_EB2D;
+    h = mix32(h ^ (x as u32).wrapping_mul(0x85EB_CA6B));
+    h = mix32(h ^ (y as u32).wrapping_mul(0xC2B2_AE35));
+    h
+}
+
+#[inline]
+fn mix32(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846C_A68B);
+    x ^ (x >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_KINDS: [NoiseKind; 4] = [
+        NoiseKind::Value,
+        NoiseKind::Perlin,
+        NoiseKind::OpenSimplex,
+        NoiseKind::Worley,
+    ];
+
+    fn sample_points() -> impl Iterator<Item = Vec2> {
+        (0..64).map(|i| Vec2::new(i as f32 * 0.37 - 11.0, i as f32 * -0.53 + 7.0))
+    }
+
+    #[test]
+    fn base_noise_is_deterministic_and_in_range() {
+        for kind in ALL_KINDS {
+            for p in sample_points() {
+                let a = sample_base(kind, 7, p);
+                let b = sample_base(kind, 7, p);
+                assert_eq!(a, b, "{kind:?} not deterministic");
+                assert!((0.0..=1.0).contains(&a), "{kind:?} out of range: {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn seed_changes_output() {
+        for kind in ALL_KINDS {
+            let differs =
+                sample_points().any(|p| sample_base(kind, 1, p) != sample_base(kind, 2, p));
+            assert!(differs, "{kind:?} ignores seed");
+        }
+    }
+
+    #[test]
+    fn value_noise_matches_lattice_at_integer_points() {
+        let p = Vec2::new(3.0, -2.0);
+        assert_eq!(value(5, p), lattice01(5, 3, -2));
+    }
+
+    #[test]
+    fn perlin_is_neutral_at_lattice_points() {
+        assert_eq!(perlin(9, Vec2::new(4.0, 4.0)), 0.5);
+    }
+
+    #[test]
+    fn fractal_combinators_stay_in_range() {
+        for kind in [FractalKind::Fbm, FractalKind::Ridged, FractalKind::Billow] {
+            let params = NoiseParams {
+                noise: NoiseKind::Perlin,
+                seed: 3,
+                frequency: 0.1,
+                fractal: Some(FractalParams {
+                    kind,
+                    octaves: 5,
+                    lacunarity: 2.0,
+                    gain: 0.5,
+                }),
+            };
+            for p in sample_points() {
+                let v = sample_noise(&params, p);
+                assert!((0.0..=1.0).contains(&v), "{kind:?} out of range: {v}");
+            }
+        }
+    }
+}
@@ -0,0 +1,606 @@
+//! Compile-time optimization passes for field graphs.
+//!
+//! Enabled with [`crate::fieldgraph::compiler::CompileOptions::optimize`]. The passes keep the
+//! values of all fields tagged with [`FieldSemantics`], which are the fields sampled by the
+//! scatter pipeline:
+//!
+//! - **Constant folding:** nodes whose inputs are all constants become constants.
+//! - **Common-subexpression elimination:** identical texture samples, noise, and operations
+//!   on the same inputs are merged into one node.
+//! - **Dead-node removal:** nodes that no semantic field depends on are removed.
+//! - **Automatic baking:** expensive subgraphs that are only read by raster filter nodes and
+//!   would be evaluated several times per raster cell are baked once per chunk.
+//!
+//! Each pass records its changes in an [`OptimizationReport`].
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::fieldgraph::math::MathOp;
+use crate::fieldgraph::program::NodeMeta;
+use crate::fieldgraph::runtime::smoothstep01;
+use crate::fieldgraph::{
+    shaping, BlurKernel, CurveInterpolation, DerivativeKind, DistanceShape, FieldId, MorphologyOp,
+    NodeSpec, TextureChannel,
+};
+
+/// Minimum estimated per-sample cost of a subgraph to be baked automatically.
+pub const AUTO_BAKE_MIN_COST: u32 = 8;
+
+/// Summary of the changes made by the optimizer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptimizationReport {
+    /// Number of nodes in the graph before optimization.
+    pub nodes_before: usize,
+    /// Number of nodes in the compiled program.
+    pub nodes_after: usize,
+    /// Nodes replaced by a constant, with the folded value.
+    pub folded: Vec<(FieldId, f32)>,
+    /// Removed duplicate nodes and the node their consumers now read instead.
+    pub merged: Vec<(FieldId, FieldId)>,
+    /// Nodes removed because no semantic field depends on them.
+    pub removed: Vec<FieldId>,
+    /// Nodes marked for baking, with their estimated per-sample cost.
+    pub auto_baked: Vec<(FieldId, u32)>,
+}
+
+impl OptimizationReport {
+    /// Returns `true` if the optimizer did not change the graph.
+    pub fn is_empty(&self) -> bool {
+        self.folded.is_empty()
+            && self.merged.is_empty()
+            && self.removed.is_empty()
+            && self.auto_baked.is_empty()
+    }
+}
+
+impl fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes: {} -> {}", self.nodes_before, self.nodes_after)?;
+        for (id, value) in &self.folded {
+            writeln!(f, "folded '{id}' to {value}")?;
+        }
+        for (id, kept) in &self.merged {
+            writeln!(f, "merged '{id}' into '{kept}'")?;
+        }
+        for id in &self.removed {
+            writeln!(f, "removed unused '{id}'")?;
+        }
+        for (id, cost) in &self.auto_baked {
+            writeln!(f, "baking '{id}' (cost {cost})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs all optimization passes on validated, acyclic `nodes`.
+pub(crate) fn optimize(nodes: &mut HashMap<FieldId, NodeMeta>) -> OptimizationReport {
+    let mut report = OptimizationReport {
+        nodes_before: nodes.len(),
+        ..Default::default()
+    };
+
+    fold_and_merge(nodes, &mut report);
+    remove_dead(nodes, &mut report);
+    select_bakes(nodes, &mut report);
+
+    report.nodes_after = nodes.len();
+    report
+}
+
+/// Folds constants and merges duplicates in a single pass over a deterministic topological
+/// order, so folded nodes can be merged and merged inputs can be folded.
+fn fold_and_merge(nodes: &mut HashMap<FieldId, NodeMeta>, report: &mut OptimizationReport) {
+    let mut aliases: HashMap<FieldId, FieldId> = HashMap::new();
+    let mut seen: HashMap<NodeKey, FieldId> = HashMap::new();
+
+    for id in ordered(nodes) {
+        let Some(meta) = nodes.get_mut(&id) else {
+            continue;
+        };
+        for input in meta.spec.inputs_mut() {
+            if let Some(alias) = aliases.get(input) {
+                *input = alias.clone();
+            }
+        }
+
+        let constants: Option<Vec<f32>> = nodes[&id]
+            .spec
+            .inputs()
+            .iter()
+            .map(|input| match nodes.get(input).map(|m| &m.spec) {
+                Some(NodeSpec::Constant { params }) => Some(params.value),
+                _ => None,
+            })
+            .collect();
+        if let Some(value) = constants.and_then(|values| fold(&nodes[&id].spec, &values)) {
+            nodes.get_mut(&id).expect("node exists").spec = NodeSpec::constant(value);
+            report.folded.push((id.clone(), value));
+        }
+
+        let meta = &nodes[&id];
+        let Some(key) = node_key(&meta.spec) else {
+            continue;
+        };
+        match seen.get(&key) {
+            Some(kept)
+                if meta.semantics.is_none()
+                    && !meta.force_bake
+                    && nodes[kept].raster_sampler == meta.raster_sampler =>
+            {
+                report.merged.push((id.clone(), kept.clone()));
+                aliases.insert(id.clone(), kept.clone());
+                nodes.remove(&id);
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(key, id);
+            }
+        }
+    }
+}
+
+fn remove_dead(nodes: &mut HashMap<FieldId, NodeMeta>, report: &mut OptimizationReport) {
+    let mut live: HashSet<FieldId> = HashSet::new();
+    let mut stack: Vec<&FieldId> = nodes
+        .values()
+        .filter(|meta| meta.semantics.is_some())
+        .map(|meta| &meta.id)
+        .collect();
+    while let Some(id) = stack.pop() {
+        if live.insert(id.clone()) {
+            stack.extend(nodes[id].spec.inputs());
+        }
+    }
+
+    let mut dead: Vec<FieldId> = nodes
+        .keys()
+        .filter(|id| !live.contains(*id))
+        .cloned()
+        .collect();
+    dead.sort_unstable();
+    for id in &dead {
+        nodes.remove(id);
+    }
+    report.removed = dead;
+}
+
+/// Marks nodes for baking that are only read at raster cell centers by raster filter nodes,
+/// are read more than once per cell, and are expensive enough for a raster lookup to pay off.
+fn select_bakes(nodes: &mut HashMap<FieldId, NodeMeta>, report: &mut OptimizationReport) {
+    let mut consumers: HashMap<&str, Vec<&NodeMeta>> = HashMap::new();
+    for meta in nodes.values() {
+        for input in meta.spec.inputs() {
+            consumers.entry(input.as_str()).or_default().push(meta);
+        }
+    }
+
+    let order = ordered(nodes);
+    let mut edt_only: HashMap<&str, bool> = HashMap::new();
+    for id in order.iter().rev() {
+        let meta = &nodes[id];
+        let readers = consumers.get(id.as_str()).map(Vec::as_slice).unwrap_or(&[]);
+        let only = meta.semantics.is_none()
+            && !readers.is_empty()
+            && readers.iter().all(|reader| match &reader.spec {
+                spec if spec.is_raster_filter() => true,
+                NodeSpec::DomainWarp { inputs, .. } if inputs[0] == *id => false,
+                _ => edt_only.get(reader.id.as_str()).copied().unwrap_or(false),
+            });
+        edt_only.insert(id, only);
+    }
+
+    let mut bakes = Vec::new();
+    for id in &order {
+        let meta = &nodes[id];
+        let cost = subgraph_cost(id, nodes);
+        let reads = consumers.get(id.as_str()).map_or(0, Vec::len);
+        if edt_only[id.as_str()]
+            && reads > 1
+            && cost >= AUTO_BAKE_MIN_COST
+            && !meta.force_bake
+            && !meta.spec.is_raster_filter()
+        {
+            bakes.push((id.clone(), cost));
+        }
+    }
+
+    for (id, _) in &bakes {
+        nodes.get_mut(id).expect("node exists").force_bake = true;
+    }
+    report.auto_baked = bakes;
+}
+
+/// Estimated cost of sampling a node once, including all distinct nodes it depends on.
+/// Baked nodes cost a raster lookup.
+fn subgraph_cost(id: &str, nodes: &HashMap<FieldId, NodeMeta>) -> u32 {
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut stack = vec![id];
+    let mut total = 0u32;
+    while let Some(current) = stack.pop() {
+        if !visited.insert(current) {
+            continue;
+        }
+        let meta = &nodes[current];
+        if current != id && (meta.force_bake || meta.spec.is_raster_filter()) {
+            total = total.saturating_add(1);
+            continue;
+        }
+        total = total.saturating_add(node_cost(&meta.spec));
+        stack.extend(meta.spec.inputs().iter().map(String::as_str));
+    }
+    total
+}
+
+fn node_cost(spec: &NodeSpec) -> u32 {
+    match spec {
+        NodeSpec::Constant { .. } => 0,
+        NodeSpec::Position { .. } => 1,
+        NodeSpec::Texture { .. } => 2,
+        NodeSpec::LinearGradient { .. } | NodeSpec::RadialGradient { .. } => 2,
+        NodeSpec::Distance { params } => match &params.shape {
+            DistanceShape::Point { .. } => 2,
+            DistanceShape::Segment { .. } => 3,
+            DistanceShape::Polyline { points } => 2 + points.len() as u32,
+        },
+        NodeSpec::Noise { params } => {
+            let octaves = params.fractal.as_ref().map_or(1, |f| f.octaves.max(1));
+            6u32.saturating_mul(octaves)
+        }
+        spec if spec.is_raster_filter() => 1,
+        NodeSpec::DomainWarp { .. } => 2,
+        _ => 1,
+    }
+}
+
+/// Evaluates a node whose inputs are the given constants, mirroring the runtime.
+fn fold(spec: &NodeSpec, values: &[f32]) -> Option<f32> {
+    if let Some(math) = MathOp::from_spec(spec) {
+        return Some(math.apply(values));
+    }
+    let first = values.first().copied();
+    match spec {
+        NodeSpec::Add { .. } => Some(values.iter().fold(0.0, |acc, v| acc + v)),
+        NodeSpec::Sub { .. } => {
+            let (first, rest) = values.split_first()?;
+            Some(rest.iter().fold(*first, |acc, v| acc - v))
+        }
+        NodeSpec::Mul { .. } => Some(values.iter().fold(1.0, |acc, v| acc * v)),
+        NodeSpec::Min { .. } => Some(values.iter().fold(f32::INFINITY, |acc, v| acc.min(*v))),
+        NodeSpec::Max { .. } => Some(values.iter().fold(f32::NEG_INFINITY, |acc, v| acc.max(*v))),
+        NodeSpec::Invert { .. } => Some(1.0 - first?),
+        NodeSpec::Scale { params, .. } => Some(first? * params.factor),
+        // Clamping with inverted bounds panics; leave it to the runtime.
+        NodeSpec::Clamp { params, .. } if params.min <= params.max => {
+            Some(first?.clamp(params.min, params.max))
+        }
+        NodeSpec::SmoothStep { params, .. } => {
+            Some(smoothstep01(params.edge0, params.edge1, first?))
+        }
+        NodeSpec::Pow { params, .. } => Some(first?.powf(params.exp)),
+        NodeSpec::Remap { params, .. } => Some(shaping::remap(params, first?)),
+        NodeSpec::Curve { params, .. } => Some(shaping::curve(params, first?)),
+        NodeSpec::Step { params, .. } => Some(shaping::step(params.threshold, first?)),
+        NodeSpec::Lut { params, .. } => Some(shaping::lut(params, first?)),
+        // A warped constant is the same constant everywhere.
+        NodeSpec::DomainWarp { .. } => first,
+        _ => None,
+    }
+}
+
+/// Structural identity of a node for merging duplicates; `None` for nodes that are not merged.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum NodeKey {
+    Constant(u32),
+    Texture(String, TextureChannel, (u8, u8, u32)),
+    Noise(u8, u32, u32, Option<(u8, u32, u32, u32)>),
+    Position(u8),
+    Op(&'static str, Vec<u32>, Vec<FieldId>),
+}
+
+fn node_key(spec: &NodeSpec) -> Option<NodeKey> {
+    let op = |name: &'static str, params: &[f32], inputs: &[FieldId]| {
+        Some(NodeKey::Op(
+            name,
+            params.iter().map(|p| p.to_bits()).collect(),
+            inputs.to_vec(),
+        ))
+    };
+    match spec {
+        NodeSpec::Constant { params } => Some(NodeKey::Constant(params.value.to_bits())),
+        NodeSpec::Texture { params } => Some(NodeKey::Texture(
+            params.texture_id.clone(),
+            params.channel,
+            params.sampler.key(),
+        )),
+        NodeSpec::Noise { params } => Some(NodeKey::Noise(
+            params.noise as u8,
+            params.seed,
+            params.frequency.to_bits(),
+            params.fractal.as_ref().map(|f| {
+                (
+                    f.kind as u8,
+                    f.octaves,
+                    f.lacunarity.to_bits(),
+                    f.gain.to_bits(),
+                )
+            }),
+        )),
+        NodeSpec::Position { params } => Some(NodeKey::Position(params.axis as u8)),
+        NodeSpec::Add { inputs } => op("add", &[], inputs),
+        NodeSpec::Sub { inputs } => op("sub", &[], inputs),
+        NodeSpec::Mul { inputs } => op("mul", &[], inputs),
+        NodeSpec::Min { inputs } => op("min", &[], inputs),
+        NodeSpec::Max { inputs } => op("max", &[], inputs),
+        NodeSpec::Invert { inputs } => op("invert", &[], inputs),
+        NodeSpec::Div { inputs }
+        | NodeSpec::Mod { inputs }
+        | NodeSpec::Abs { inputs }
+        | NodeSpec::Sqrt { inputs }
+        | NodeSpec::Sin { inputs }
+        | NodeSpec::Cos { inputs }
+        | NodeSpec::Tan { inputs }
+        | NodeSpec::Atan2 { inputs }
+        | NodeSpec::Floor { inputs }
+        | NodeSpec::Fract { inputs }
+        | NodeSpec::Sign { inputs }
+        | NodeSpec::Lerp { inputs }
+        | NodeSpec::Select { inputs }
+        | NodeSpec::Greater { inputs }
+        | NodeSpec::Less { inputs }
+        | NodeSpec::And { inputs }
+        | NodeSpec::Or { inputs }
+        | NodeSpec::Not { inputs } => op(
+            MathOp::from_spec(spec).expect("math node").name(),
+            &[],
+            inputs,
+        ),
+        NodeSpec::Scale { inputs, params } => op("scale", &[params.factor], inputs),
+        NodeSpec::Clamp { inputs, params } => op("clamp", &[params.min, params.max], inputs),
+        NodeSpec::SmoothStep { inputs, params } => {
+            op("smoothstep", &[params.edge0, params.edge1], inputs)
+        }
+        NodeSpec::Pow { inputs, params } => op("pow", &[params.exp], inputs),
+        NodeSpec::Remap { inputs, params } => op(
+            "remap",
+            &[
+                params.in_min,
+                params.in_max,
+                params.out_min,
+                params.out_max,
+                f32::from(u8::from(params.clamp)),
+            ],
+            inputs,
+        ),
+        NodeSpec::Curve { inputs, params } => {
+            let points: Vec<f32> = params.points.iter().flat_map(|(x, y)| [*x, *y]).collect();
+            match params.interpolation {
+                CurveInterpolation::Linear => op("curve_linear", &points, inputs),
+                CurveInterpolation::Spline => op("curve_spline", &points, inputs),
+            }
+        }
+        NodeSpec::Step { inputs, params } => op("step", &[params.threshold], inputs),
+        NodeSpec::Lut { inputs, params } => {
+            let mut values = vec![params.in_min, params.in_max];
+            values.extend(&params.values);
+            op("lut", &values, inputs)
+        }
+        NodeSpec::EdtNormalize { inputs, params } => {
+            op("edt", &[params.threshold, params.d_max], inputs)
+        }
+        NodeSpec::EdtSigned { inputs, params } => {
+            op("edt_signed", &[params.threshold, params.d_max], inputs)
+        }
+        NodeSpec::Blur { inputs, params } => match params.kernel {
+            BlurKernel::Box => op("blur_box", &[params.radius], inputs),
+            BlurKernel::Gaussian => op("blur_gaussian", &[params.radius], inputs),
+        },
+        NodeSpec::Morphology { inputs, params } => match params.op {
+            MorphologyOp::Dilate => op("dilate", &[params.radius], inputs),
+            MorphologyOp::Erode => op("erode", &[params.radius], inputs),
+        },
+        NodeSpec::Convolve { inputs, params } => {
+            let mut values = vec![
+                params.width as f32,
+                params.height as f32,
+                f32::from(u8::from(params.normalize)),
+            ];
+            values.extend(&params.weights);
+            op("convolve", &values, inputs)
+        }
+        NodeSpec::Derivative { inputs, params } => {
+            let name = match params.kind {
+                DerivativeKind::GradientMagnitude => "gradient",
+                DerivativeKind::Slope => "slope",
+                DerivativeKind::Aspect => "aspect",
+                DerivativeKind::Curvature => "curvature",
+                DerivativeKind::NormalX => "normal_x",
+                DerivativeKind::NormalY => "normal_y",
+                DerivativeKind::NormalZ => "normal_z",
+            };
+            op(name, &[params.height_scale], inputs)
+        }
+        NodeSpec::DomainWarp { inputs, params } => op("warp", &[params.amplitude], inputs),
+        NodeSpec::Distance { .. }
+        | NodeSpec::LinearGradient { .. }
+        | NodeSpec::RadialGradient { .. }
+        | NodeSpec::Expr { .. }
+        | NodeSpec::Subgraph { .. } => None,
+    }
+}
+
+/// Topological order that visits ready nodes by id, so results do not depend on hash order.
+fn ordered(nodes: &HashMap<FieldId, NodeMeta>) -> Vec<FieldId> {
+    let mut pending: HashMap<&str, usize> = HashMap::new();
+    let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (id, meta) in nodes {
+        pending.insert(id, meta.spec.inputs().len());
+        for input in meta.spec.inputs() {
+            dependents.entry(input).or_default().push(id);
+        }
+    }
+
+    let mut ready: BTreeSet<&str> = pending
+        .iter()
+        .filter_map(|(id, count)| (*count == 0).then_some(*id))
+        .collect();
+    let mut out = Vec::with_capacity(nodes.len());
+    while let Some(id) = ready.pop_first() {
+        out.push(id.to_string());
+        for dependent in dependents.get(id).into_iter().flatten() {
+            let count = pending.get_mut(dependent).expect("dependent exists");
+            *count -= 1;
+            if *count == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
+    use crate::fieldgraph::NoiseKind;
+    use crate::prelude::{FieldGraphSpec, FieldSemantics};
+
+    fn compile(spec: &FieldGraphSpec) -> (crate::fieldgraph::FieldProgram, OptimizationReport) {
+        FieldGraphCompiler::compile_with_report(
+            spec,
+            &CompileOptions::default().with_optimize(true),
+        )
+        .expect("compile succeeds")
+    }
+
+    #[test]
+    fn folds_constant_chains() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("half", NodeSpec::constant(0.5));
+        spec.add("double", NodeSpec::scale("half".into(), 2.0));
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::mul(vec!["double".into(), "half".into()]),
+            FieldSemantics::Probability,
+        );
+
+        let (program, report) = compile(&spec);
+
+        assert!(matches!(
+            program.nodes["probability"].spec,
+            NodeSpec::Constant { ref params } if params.value == 0.5
+        ));
+        assert_eq!(
+            report.folded,
+            vec![("double".into(), 1.0), ("probability".into(), 0.5)]
+        );
+        assert_eq!(
+            report.removed,
+            vec!["double".to_string(), "half".to_string()]
+        );
+        assert_eq!(program.nodes.len(), 1);
+    }
+
+    #[test]
+    fn merges_identical_texture_samples() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("height_a", NodeSpec::texture("height", TextureChannel::R));
+        spec.add("height_b", NodeSpec::texture("height", TextureChannel::R));
+        spec.add("moisture", NodeSpec::texture("height", TextureChannel::G));
+        spec.add("low", NodeSpec::invert("height_b".into()));
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::mul(vec!["height_a".into(), "low".into(), "moisture".into()]),
+            FieldSemantics::Probability,
+        );
+
+        let (program, report) = compile(&spec);
+
+        assert_eq!(report.merged, vec![("height_b".into(), "height_a".into())]);
+        assert!(!program.nodes.contains_key("height_b"));
+        assert_eq!(program.nodes["low"].spec.inputs(), ["height_a".to_string()]);
+        assert_eq!(program.topo.len(), 4);
+    }
+
+    #[test]
+    fn keeps_semantic_fields_and_their_inputs() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("unused", NodeSpec::noise(NoiseKind::Perlin, 1, 0.1));
+        spec.add_with_semantics(
+            "gate",
+            NodeSpec::scale("x".into(), 1.0),
+            FieldSemantics::Gate,
+        );
+        spec.add_with_semantics(
+            "scale",
+            NodeSpec::scale("x".into(), 1.0),
+            FieldSemantics::Scale,
+        );
+
+        let (program, report) = compile(&spec);
+
+        assert!(program.nodes.contains_key("gate"));
+        assert!(program.nodes.contains_key("scale"));
+        assert!(report.merged.is_empty());
+        assert_eq!(report.removed, vec!["unused".to_string()]);
+    }
+
+    #[test]
+    fn bakes_expensive_subgraphs_read_by_several_edt_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add(
+            "rocks",
+            NodeSpec::fractal_noise(
+                NoiseKind::Worley,
+                crate::fieldgraph::FractalKind::Fbm,
+                3,
+                0.1,
+                4,
+            ),
+        );
+        spec.add("near", NodeSpec::edt_normalize("rocks".into(), 0.5, 4.0));
+        spec.add("far", NodeSpec::edt_normalize("rocks".into(), 0.5, 16.0));
+        spec.add("cheap", NodeSpec::position_x());
+        spec.add(
+            "cheap_edt",
+            NodeSpec::edt_normalize("cheap".into(), 0.5, 4.0),
+        );
+        spec.add(
+            "cheap_edt_2",
+            NodeSpec::edt_normalize("cheap".into(), 0.5, 8.0),
+        );
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::mul(vec![
+                "near".into(),
+                "far".into(),
+                "cheap_edt".into(),
+                "cheap_edt_2".into(),
+            ]),
+            FieldSemantics::Probability,
+        );
+
+        let (program, report) = compile(&spec);
+
+        assert_eq!(report.auto_baked, vec![("rocks".into(), 24)]);
+        assert!(program.nodes["rocks"].force_bake);
+        assert!(!program.nodes["cheap"].force_bake);
+    }
+
+    #[test]
+    fn does_not_bake_fields_sampled_off_grid() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("rocks", NodeSpec::noise(NoiseKind::Worley, 3, 0.1));
+        spec.add("rocks_2", NodeSpec::scale("rocks".into(), 1.5));
+        spec.add("near", NodeSpec::edt_normalize("rocks".into(), 0.5, 4.0));
+        spec.add("far", NodeSpec::edt_normalize("rocks_2".into(), 0.5, 16.0));
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::mul(vec!["near".into(), "far".into(), "rocks".into()]),
+            FieldSemantics::Probability,
+        );
+
+        let (_, report) = compile(&spec);
+        assert!(report.auto_baked.is_empty());
+    }
+}
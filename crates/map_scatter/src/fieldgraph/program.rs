@@ -5,8 +5,11 @@
 //! executable program.
 use std::collections::HashMap;
 
+use crate::fieldgraph::bytecode::Bytecode;
+use crate::fieldgraph::filter::kernel_reach;
 pub use crate::fieldgraph::spec::FieldSemantics;
 pub use crate::fieldgraph::{FieldId, NodeSpec};
+use crate::fieldgraph::{TextureFilter, TextureSampler};
 
 /// Metadata about a node in the field program.
 #[derive(Clone, Debug)]
@@ -17,6 +20,8 @@ pub struct NodeMeta {
     pub spec: NodeSpec,
     /// Whether this node should be baked into a raster.
     pub force_bake: bool,
+    /// Sampler used to read the baked raster of this node.
+    pub raster_sampler: TextureSampler,
     /// Optional semantic tag for this field.
     pub semantics: Option<FieldSemantics>,
 }
@@ -35,11 +40,69 @@ impl NodeMeta {
     }
 }
 
-/// A field program, consisting of nodes and their topological order.
+/// A field program, consisting of nodes, their topological order, and the bytecode
+/// evaluated by the runtime.
+///
+/// The bytecode is built from the nodes on construction; changes to `nodes` after that are
+/// not evaluated.
 #[derive(Clone, Debug)]
 pub struct FieldProgram {
     /// Node metadata keyed by field id.
     pub nodes: HashMap<FieldId, NodeMeta>,
     /// Topological order of node evaluation.
     pub topo: Vec<FieldId>,
+    bytecode: Bytecode,
+}
+
+impl FieldProgram {
+    /// Creates a program from nodes in topological order and builds its bytecode.
+    pub fn new(nodes: HashMap<FieldId, NodeMeta>, topo: Vec<FieldId>) -> Self {
+        let bytecode = Bytecode::new(&nodes, &topo);
+        Self {
+            nodes,
+            topo,
+            bytecode,
+        }
+    }
+
+    /// Returns the bytecode evaluated by the runtime.
+    #[inline]
+    pub fn bytecode(&self) -> &Bytecode {
+        &self.bytecode
+    }
+
+    /// Returns the grid halo, in cells of `cell_size`, that blur, morphology, convolution,
+    /// and derivative nodes need to be exact within a chunk.
+    ///
+    /// Filters reading other filters add up their reach, and reading a baked raster that
+    /// depends on a filter with a bilinear or bicubic raster sampler adds one or two cells.
+    /// Distance transforms are not included.
+    pub fn filter_halo(&self, cell_size: f32) -> usize {
+        let mut reach: HashMap<&str, usize> = HashMap::with_capacity(self.topo.len());
+        let mut halo = 0;
+        for id in &self.topo {
+            let Some(meta) = self.nodes.get(id) else {
+                continue;
+            };
+            let inputs = meta
+                .spec
+                .inputs()
+                .iter()
+                .filter_map(|input| reach.get(input.as_str()))
+                .max()
+                .copied()
+                .unwrap_or(0);
+            let node = inputs + kernel_reach(&meta.spec, cell_size);
+            let baked = meta.force_bake || meta.spec.is_raster_filter();
+            let sampler = match meta.raster_sampler.filter {
+                _ if !baked || node == 0 => 0,
+                TextureFilter::Nearest => 0,
+                TextureFilter::Bilinear => 1,
+                TextureFilter::Bicubic => 2,
+            };
+            halo = halo.max(node + sampler);
+            reach.insert(id.as_str(), node);
+        }
+        halo
+    }
 }
@@ -4,6 +4,7 @@
 use glam::Vec2;
 
 use super::grid::ChunkGrid;
+use super::texture::{TextureFilter, TextureSampler};
 
 /// A raster grid with floating point values and a chunk grid for spatial reference.
 #[derive(Clone, Debug)]
@@ -44,6 +45,26 @@ impl Raster {
         let (ix, iy) = self.grid.world_to_index(p);
         self.get(ix, iy)
     }
+
+    /// Sample the raster at a world position with a filter and addressing mode.
+    ///
+    /// [`TextureFilter::Nearest`] reads the cell containing `p`, like
+    /// [`Raster::sample_domain`]. The other filters interpolate between the world positions
+    /// of the cells, where their values were sampled.
+    pub fn sample_domain_with(&self, p: Vec2, sampler: &TextureSampler) -> f32 {
+        let (w, h) = self.size();
+        let offset = match sampler.filter {
+            TextureFilter::Nearest => 0.0,
+            _ => 0.5,
+        };
+        let x = (p.x - self.grid.origin_domain.x) / self.grid.cell_size
+            + self.grid.halo as f32
+            + offset;
+        let y = (p.y - self.grid.origin_domain.y) / self.grid.cell_size
+            + self.grid.halo as f32
+            + offset;
+        sampler.sample_texels(w, h, x, y, |ix, iy| self.data[iy * w + ix])
+    }
 }
 
 #[cfg(test)]
@@ -85,4 +106,27 @@ mod tests {
         raster.data[idx.1 as usize * w + idx.0 as usize] = 0.75;
         assert_eq!(raster.sample_domain(Vec2::new(0.0, 0.0)), 0.75);
     }
+
+    #[test]
+    fn sample_domain_with_interpolates_between_cells() {
+        let grid = make_grid();
+        let mut raster = Raster::new(grid.clone());
+        let (ix, iy) = grid.world_to_index(Vec2::new(0.0, 0.0));
+        let w = grid.total_width();
+        raster.data[iy as usize * w + ix as usize] = 1.0;
+
+        let nearest = TextureSampler::default();
+        assert_eq!(
+            raster.sample_domain_with(Vec2::new(0.5, 0.5), &nearest),
+            1.0
+        );
+
+        let bilinear = TextureSampler::default().with_filter(TextureFilter::Bilinear);
+        assert_eq!(
+            raster.sample_domain_with(Vec2::new(0.0, 0.0), &bilinear),
+            1.0
+        );
+        assert!((raster.sample_domain_with(Vec2::new(0.5, 0.0), &bilinear) - 0.5).abs() < 1e-6);
+        assert!((raster.sample_domain_with(Vec2::new(0.5, 0.5), &bilinear) - 0.25).abs() < 1e-6);
+    }
 }
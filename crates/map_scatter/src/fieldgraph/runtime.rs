@@ -1,8 +1,8 @@
 //! Runtime for evaluating field programs and baking rasters.
 //!
-//! This module interprets compiled [`FieldProgram`]s,
-//! sampling values on-demand via [`FieldRuntime::sample`] and optionally baking results
-//! into [`Raster`]s aligned to a [`ChunkGrid`].
+//! This module executes the [`Bytecode`] of compiled [`FieldProgram`]s over batches of
+//! positions via [`FieldRuntime::sample_batch`], and optionally bakes results into
+//! [`Raster`]s aligned to a [`ChunkGrid`]. [`FieldRuntime::sample`] evaluates a single position.
 //! It also integrates texture inputs through [`TextureRegistry`].
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -10,9 +10,10 @@ use std::sync::Arc;
 use glam::Vec2;
 use tracing::warn;
 
-use crate::fieldgraph::edt::bake_edt_normalize_params;
+use crate::fieldgraph::bytecode::{Bytecode, Op, Step};
+use crate::fieldgraph::noise::sample_noise;
 use crate::fieldgraph::program::FieldProgram;
-use crate::fieldgraph::{ChunkGrid, ChunkId, FieldId, NodeSpec, Raster, TextureRegistry};
+use crate::fieldgraph::{coords, shaping, ChunkGrid, ChunkId, Raster, TextureRegistry};
 
 /// Runtime for evaluating field programs, managing textures and baked rasters.
 pub struct FieldRuntime<'a> {
@@ -20,7 +21,7 @@ pub struct FieldRuntime<'a> {
     pub program: Arc<FieldProgram>,
     /// Texture registry used for sampling texture nodes.
     pub textures: &'a TextureRegistry,
-    baked_rasters: HashMap<(FieldId, ChunkId), Raster>,
+    baked_rasters: HashMap<(u32, ChunkId), Raster>,
 }
 
 impl<'a> FieldRuntime<'a> {
@@ -35,192 +36,329 @@ impl<'a> FieldRuntime<'a> {
 
     /// Sample the value of a field at a given world position within a chunk and grid.
     pub fn sample(&mut self, field: &str, p: Vec2, chunk: ChunkId, grid: &ChunkGrid) -> f32 {
-        let key = (field.to_string(), chunk);
+        let mut out = [0.0];
+        self.sample_batch(field, std::slice::from_ref(&p), chunk, grid, &mut out);
+        out[0]
+    }
 
-        if let Some(raster) = self.baked_rasters.get(&key) {
-            return raster.sample_domain(p);
-        }
+    /// Sample the values of a field at many world positions within a chunk and grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` and `positions` differ in length.
+    pub fn sample_batch(
+        &mut self,
+        field: &str,
+        positions: &[Vec2],
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+        out: &mut [f32],
+    ) {
+        assert_eq!(
+            positions.len(),
+            out.len(),
+            "positions and output must have the same length"
+        );
+        let program = self.program.clone();
+        let code = program.bytecode();
+        let Some(slot) = code.slot(field) else {
+            warn!("Unknown field '{}'.", field);
+            out.fill(0.0);
+            return;
+        };
+        self.eval_slot(code, slot, positions, chunk, grid, out);
+    }
 
-        if let Some(meta) = self.program.nodes.get(field) {
-            if meta.force_bake {
-                self.bake_raster_if_needed(field, chunk, grid);
-                if let Some(r) = self.baked_rasters.get(&key) {
-                    return r.sample_domain(p);
-                }
-                warn!("Raster for '{}' not found after force bake.", field);
+    /// Sample several fields at many world positions within a chunk and grid. Nodes the
+    /// fields have in common are computed once. `out` receives one row of values per field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` does not hold `fields.len() * positions.len()` values.
+    pub fn sample_batch_many(
+        &mut self,
+        fields: &[&str],
+        positions: &[Vec2],
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+        out: &mut [f32],
+    ) {
+        let n = positions.len();
+        assert_eq!(
+            fields.len() * n,
+            out.len(),
+            "output must hold one row per field"
+        );
+        let program = self.program.clone();
+        let code = program.bytecode();
+
+        // Steps of all routines, each slot computed once; rows of the requested fields.
+        let mut steps: Vec<Step> = Vec::new();
+        let mut row_of: HashMap<u32, u32> = HashMap::new();
+        let mut field_rows: Vec<Option<u32>> = Vec::with_capacity(fields.len());
+        for (field, dst) in fields.iter().zip(out.chunks_exact_mut(n.max(1))) {
+            let Some(slot) = code.slot(field) else {
+                warn!("Unknown field '{}'.", field);
+                dst.fill(0.0);
+                field_rows.push(None);
+                continue;
+            };
+            if code.instr(slot).baked {
+                self.lookup_baked(code, slot, positions, chunk, grid, dst);
+                field_rows.push(None);
+                continue;
+            }
+            let routine = code.routine(slot);
+            let mut rows: Vec<u32> = Vec::with_capacity(routine.steps.len());
+            for step in &routine.steps {
+                let row = *row_of.entry(step.slot).or_insert_with(|| {
+                    steps.push(Step {
+                        slot: step.slot,
+                        args: step.args.iter().map(|arg| rows[*arg as usize]).collect(),
+                        lookup: step.lookup,
+                    });
+                    (steps.len() - 1) as u32
+                });
+                rows.push(row);
             }
+            field_rows.push(rows.last().copied());
         }
 
-        self.eval_field_value(field, p, chunk, grid)
+        let mut values = vec![0.0f32; steps.len() * n];
+        self.run_steps(code, &steps, positions, chunk, grid, &mut values);
+        for (row, dst) in field_rows.iter().zip(out.chunks_exact_mut(n.max(1))) {
+            if let Some(row) = row {
+                let start = *row as usize * n;
+                dst.copy_from_slice(&values[start..start + n]);
+            }
+        }
     }
 
-    fn eval_field_value(&mut self, field: &str, p: Vec2, chunk: ChunkId, grid: &ChunkGrid) -> f32 {
-        enum Op {
-            Constant(f32),
-            Texture(String, crate::fieldgraph::TextureChannel),
-            Add(Vec<String>),
-            Sub(Vec<String>),
-            Scale(Option<String>, f32),
-            Mul(Vec<String>),
-            Min(Vec<String>),
-            Max(Vec<String>),
-            Invert(Option<String>),
-            Clamp(Option<String>, f32, f32),
-            SmoothStep(Option<String>, f32, f32),
-            Pow(Option<String>, f32),
-            Edt,
+    fn eval_slot(
+        &mut self,
+        code: &Bytecode,
+        slot: u32,
+        positions: &[Vec2],
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+        out: &mut [f32],
+    ) {
+        if code.instr(slot).baked {
+            self.lookup_baked(code, slot, positions, chunk, grid, out);
+        } else {
+            self.run_routine(code, slot, positions, chunk, grid, out);
         }
+    }
 
-        let op = {
-            let Some(meta) = self.program.nodes.get(field) else {
-                warn!("Unknown field '{}'.", field);
-                return 0.0;
+    /// Computes `slot` from its inputs, one row of values per routine step.
+    fn run_routine(
+        &mut self,
+        code: &Bytecode,
+        slot: u32,
+        positions: &[Vec2],
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+        out: &mut [f32],
+    ) {
+        let n = positions.len();
+        let steps = &code.routine(slot).steps;
+        let mut rows = vec![0.0f32; steps.len() * n];
+        self.run_steps(code, steps, positions, chunk, grid, &mut rows);
+        out.copy_from_slice(&rows[rows.len() - n..]);
+    }
+
+    /// Computes one row of `rows` per step, reading arguments from the rows of earlier steps.
+    fn run_steps(
+        &mut self,
+        code: &Bytecode,
+        steps: &[Step],
+        positions: &[Vec2],
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+        rows: &mut [f32],
+    ) {
+        let n = positions.len();
+        for (i, step) in steps.iter().enumerate() {
+            let (done, rest) = rows.split_at_mut(i * n);
+            let dst = &mut rest[..n];
+            if step.lookup {
+                self.lookup_baked(code, step.slot, positions, chunk, grid, dst);
+                continue;
+            }
+
+            let arg = |k: usize| -> &[f32] {
+                let row = step.args[k] as usize * n;
+                &done[row..row + n]
             };
-            match &meta.spec {
-                NodeSpec::Constant { params } => Op::Constant(params.value),
-                NodeSpec::Texture { params } => {
-                    Op::Texture(params.texture_id.clone(), params.channel)
+
+            match &code.instr(step.slot).op {
+                Op::Constant(v) => dst.fill(*v),
+                Op::Texture(id, channel, sampler) => match self.textures.get(id) {
+                    Some(texture) => fill_from_positions(dst, positions, |p| {
+                        texture.sample_with(*channel, p, sampler)
+                    }),
+                    None => {
+                        warn!("Unknown texture id '{}'.", id);
+                        dst.fill(0.0);
+                    }
+                },
+                Op::Noise(params) => {
+                    fill_from_positions(dst, positions, |p| sample_noise(params, p))
                 }
-                NodeSpec::Add { inputs } => Op::Add(inputs.clone()),
-                NodeSpec::Sub { inputs } => Op::Sub(inputs.clone()),
-                NodeSpec::Scale { inputs, params } => {
-                    Op::Scale(inputs.first().cloned(), params.factor)
+                Op::Position(axis) => {
+                    fill_from_positions(dst, positions, |p| coords::position(*axis, p))
                 }
-                NodeSpec::Mul { inputs } => Op::Mul(inputs.clone()),
-                NodeSpec::Min { inputs } => Op::Min(inputs.clone()),
-                NodeSpec::Max { inputs } => Op::Max(inputs.clone()),
-                NodeSpec::Invert { inputs } => Op::Invert(inputs.first().cloned()),
-                NodeSpec::Clamp { inputs, params } => {
-                    Op::Clamp(inputs.first().cloned(), params.min, params.max)
+                Op::Distance(params) => {
+                    fill_from_positions(dst, positions, |p| coords::distance(params, p))
                 }
-                NodeSpec::SmoothStep { inputs, params } => {
-                    Op::SmoothStep(inputs.first().cloned(), params.edge0, params.edge1)
+                Op::LinearGradient(params) => {
+                    fill_from_positions(dst, positions, |p| coords::linear_gradient(params, p))
                 }
-                NodeSpec::Pow { inputs, params } => Op::Pow(inputs.first().cloned(), params.exp),
-                NodeSpec::EdtNormalize { .. } => Op::Edt,
-            }
-        };
-
-        match op {
-            Op::Constant(v) => v,
-            Op::Texture(id, ch) => self.textures.sample(&id, ch, p),
-            Op::Add(inputs) => {
-                let mut sum = 0.0;
-                for id in inputs {
-                    sum += self.sample(&id, p, chunk, grid);
+                Op::RadialGradient(params) => {
+                    fill_from_positions(dst, positions, |p| coords::radial_gradient(params, p))
                 }
-                sum
-            }
-            Op::Sub(inputs) => {
-                if inputs.is_empty() {
-                    0.0
-                } else {
-                    let mut iter = inputs.into_iter();
-                    let mut acc = self.sample(&iter.next().unwrap(), p, chunk, grid);
-                    for id in iter {
-                        acc -= self.sample(&id, p, chunk, grid);
+                Op::DomainWarp { source, amplitude } => {
+                    let warped: Vec<Vec2> = positions
+                        .iter()
+                        .zip(arg(0).iter().zip(arg(1)))
+                        .map(|(p, (ox, oy))| *p + Vec2::new(*ox, *oy) * *amplitude)
+                        .collect();
+                    self.eval_slot(code, *source, &warped, chunk, grid, dst);
+                }
+                Op::Add => {
+                    dst.fill(0.0);
+                    for k in 0..step.args.len() {
+                        for (d, v) in dst.iter_mut().zip(arg(k)) {
+                            *d += *v;
+                        }
                     }
-                    acc
                 }
-            }
-            Op::Scale(input, factor) => {
-                let v = if let Some(id) = input {
-                    self.sample(&id, p, chunk, grid)
-                } else {
-                    0.0
-                };
-                v * factor
-            }
-            Op::Mul(inputs) => {
-                let mut product = 1.0;
-                for id in inputs {
-                    product *= self.sample(&id, p, chunk, grid);
+                Op::Sub => {
+                    dst.copy_from_slice(arg(0));
+                    for k in 1..step.args.len() {
+                        for (d, v) in dst.iter_mut().zip(arg(k)) {
+                            *d -= *v;
+                        }
+                    }
                 }
-                product
-            }
-            Op::Min(inputs) => {
-                let mut min_val = f32::INFINITY;
-                for id in inputs {
-                    min_val = min_val.min(self.sample(&id, p, chunk, grid));
+                Op::Mul => {
+                    dst.fill(1.0);
+                    for k in 0..step.args.len() {
+                        for (d, v) in dst.iter_mut().zip(arg(k)) {
+                            *d *= *v;
+                        }
+                    }
                 }
-                min_val
-            }
-            Op::Max(inputs) => {
-                let mut max_val = f32::NEG_INFINITY;
-                for id in inputs {
-                    max_val = max_val.max(self.sample(&id, p, chunk, grid));
+                Op::Min => {
+                    dst.fill(f32::INFINITY);
+                    for k in 0..step.args.len() {
+                        for (d, v) in dst.iter_mut().zip(arg(k)) {
+                            *d = d.min(*v);
+                        }
+                    }
                 }
-                max_val
-            }
-            Op::Invert(input) => 1.0 - self.sample(input.as_deref().unwrap_or(""), p, chunk, grid),
-            Op::Clamp(input, min, max) => {
-                let v = self.sample(input.as_deref().unwrap_or(""), p, chunk, grid);
-                v.clamp(min, max)
-            }
-            Op::SmoothStep(input, e0, e1) => {
-                let v = self.sample(input.as_deref().unwrap_or(""), p, chunk, grid);
-                smoothstep01(e0, e1, v)
-            }
-            Op::Pow(input, exp) => {
-                let v = self.sample(input.as_deref().unwrap_or(""), p, chunk, grid);
-                v.powf(exp)
-            }
-            Op::Edt => {
-                self.bake_raster_if_needed(field, chunk, grid);
-                if let Some(r) = self.baked_rasters.get(&(field.to_string(), chunk)) {
-                    r.sample_domain(p)
-                } else {
-                    warn!("Raster for '{}' not found after baking.", field);
-                    0.0
+                Op::Max => {
+                    dst.fill(f32::NEG_INFINITY);
+                    for k in 0..step.args.len() {
+                        for (d, v) in dst.iter_mut().zip(arg(k)) {
+                            *d = d.max(*v);
+                        }
+                    }
+                }
+                Op::Invert => map_row(dst, arg(0), |v| 1.0 - v),
+                Op::Math(math) => {
+                    let mut values = vec![0.0; step.args.len()];
+                    for (i, d) in dst.iter_mut().enumerate() {
+                        for (k, value) in values.iter_mut().enumerate() {
+                            *value = arg(k)[i];
+                        }
+                        *d = math.apply(&values);
+                    }
+                }
+                Op::Scale(factor) => map_row(dst, arg(0), |v| v * factor),
+                Op::Clamp(min, max) => map_row(dst, arg(0), |v| v.clamp(*min, *max)),
+                Op::SmoothStep(e0, e1) => map_row(dst, arg(0), |v| smoothstep01(*e0, *e1, v)),
+                Op::Pow(exp) => map_row(dst, arg(0), |v| v.powf(*exp)),
+                Op::Remap(params) => map_row(dst, arg(0), |v| shaping::remap(params, v)),
+                Op::Curve(params) => map_row(dst, arg(0), |v| shaping::curve(params, v)),
+                Op::Step(threshold) => map_row(dst, arg(0), |v| shaping::step(*threshold, v)),
+                Op::Lut(params) => map_row(dst, arg(0), |v| shaping::lut(params, v)),
+                Op::Filter(_) => {
+                    // Filter nodes are always baked, so they are only reached as lookups.
+                    self.lookup_baked(code, step.slot, positions, chunk, grid, dst);
                 }
             }
         }
     }
 
-    fn bake_raster_if_needed(&mut self, field: &str, chunk: ChunkId, grid: &ChunkGrid) {
-        let key = (field.to_string(), chunk);
-        if self.baked_rasters.contains_key(&key) {
-            return;
+    fn lookup_baked(
+        &mut self,
+        code: &Bytecode,
+        slot: u32,
+        positions: &[Vec2],
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+        out: &mut [f32],
+    ) {
+        self.bake_raster_if_needed(code, slot, chunk, grid);
+        let raster = &self.baked_rasters[&(slot, chunk)];
+        let sampler = &code.instr(slot).sampler;
+        for (o, p) in out.iter_mut().zip(positions) {
+            *o = raster.sample_domain_with(*p, sampler);
         }
+    }
 
-        let Some(meta_ref) = self.program.nodes.get(field) else {
-            warn!("Cannot bake unknown field '{}'.", field);
-            return;
-        };
-
-        if let Some((input_id, threshold, d_max)) = {
-            if let NodeSpec::EdtNormalize { inputs, params } = &meta_ref.spec {
-                Some((
-                    inputs.first().cloned().unwrap_or_default(),
-                    params.threshold,
-                    params.d_max,
-                ))
-            } else {
-                None
-            }
-        } {
-            let raster = bake_edt_normalize_params(self, &input_id, threshold, d_max, chunk, grid);
-            self.baked_rasters.insert(key, raster);
+    fn bake_raster_if_needed(
+        &mut self,
+        code: &Bytecode,
+        slot: u32,
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+    ) {
+        if self.baked_rasters.contains_key(&(slot, chunk)) {
             return;
         }
 
-        let mut raster = Raster::new(grid.clone());
-        let (tw, th) = raster.size();
-
+        let (tw, th) = (grid.total_width(), grid.total_height());
+        let mut positions = Vec::with_capacity(tw * th);
         for iy in 0..th as isize {
             for ix in 0..tw as isize {
-                let p = grid.index_to_world(ix, iy);
-                let v = self.eval_field_value(field, p, chunk, grid);
-                let idx = (iy as usize) * tw + ix as usize;
-                raster.data[idx] = v;
+                positions.push(grid.index_to_world(ix, iy));
             }
         }
+        let mut values = vec![0.0; positions.len()];
+
+        let instr = code.instr(slot);
+        let raster = if let Op::Filter(filter) = &instr.op {
+            self.eval_slot(code, instr.inputs[0], &positions, chunk, grid, &mut values);
+            filter.apply(&values, grid)
+        } else {
+            self.run_routine(code, slot, &positions, chunk, grid, &mut values);
+            Raster {
+                grid: grid.clone(),
+                data: values,
+            }
+        };
+
+        self.baked_rasters.insert((slot, chunk), raster);
+    }
+}
+
+#[inline]
+fn fill_from_positions(dst: &mut [f32], positions: &[Vec2], f: impl Fn(Vec2) -> f32) {
+    for (d, p) in dst.iter_mut().zip(positions) {
+        *d = f(*p);
+    }
+}
 
-        self.baked_rasters.insert(key, raster);
+#[inline]
+fn map_row(dst: &mut [f32], src: &[f32], f: impl Fn(f32) -> f32) {
+    for (d, v) in dst.iter_mut().zip(src) {
+        *d = f(*v);
     }
 }
 
-fn smoothstep01(e0: f32, e1: f32, x: f32) -> f32 {
+pub(crate) fn smoothstep01(e0: f32, e1: f32, x: f32) -> f32 {
     let denom = e1 - e0;
     if denom.abs() <= f32::EPSILON {
         return if x >= e1 { 1.0 } else { 0.0 };
@@ -233,7 +371,11 @@ fn smoothstep01(e0: f32, e1: f32, x: f32) -> f32 {
 mod tests {
     use super::*;
     use crate::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
-    use crate::prelude::{FieldGraphSpec, Texture, TextureChannel};
+    use crate::fieldgraph::{BlurKernel, DerivativeKind, DerivativeParams, NoiseParams};
+    use crate::prelude::{
+        FieldGraphSpec, FieldSemantics, NodeSpec, Texture, TextureChannel, TextureFilter,
+        TextureSampler,
+    };
 
     struct ConstTexture(f32);
 
@@ -243,6 +385,14 @@ mod tests {
         }
     }
 
+    struct RampTexture(f32);
+
+    impl Texture for RampTexture {
+        fn sample(&self, _channel: TextureChannel, p: Vec2) -> f32 {
+            self.0 * p.x
+        }
+    }
+
     fn grid() -> ChunkGrid {
         ChunkGrid {
             origin_domain: Vec2::ZERO,
@@ -315,11 +465,287 @@ mod tests {
     }
 
     #[test]
-    fn unknown_field_sample_returns_zero() {
-        let program = FieldProgram {
-            nodes: HashMap::new(),
-            topo: Vec::new(),
+    fn runtime_evaluates_noise_nodes() {
+        use crate::fieldgraph::{FractalKind, NoiseKind};
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add("perlin", NodeSpec::noise(NoiseKind::Perlin, 4, 0.25));
+        spec.add(
+            "ridged",
+            NodeSpec::fractal_noise(NoiseKind::OpenSimplex, FractalKind::Ridged, 4, 0.25, 3),
+        );
+
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+        let textures = TextureRegistry::new();
+        let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+        let grid = grid();
+        let chunk = ChunkId(0, 0);
+        let p = Vec2::new(1.3, -2.7);
+
+        let params = NoiseParams {
+            noise: NoiseKind::Perlin,
+            seed: 4,
+            frequency: 0.25,
+            fractal: None,
         };
+        approx_eq(
+            runtime.sample("perlin", p, chunk, &grid),
+            sample_noise(&params, p),
+        );
+        let ridged = runtime.sample("ridged", p, chunk, &grid);
+        assert!((0.0..=1.0).contains(&ridged));
+    }
+
+    #[test]
+    fn runtime_evaluates_coordinate_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("y", NodeSpec::position_y());
+        spec.add(
+            "dist",
+            NodeSpec::distance_to_point(Vec2::new(3.0, 0.0), 10.0),
+        );
+        spec.add(
+            "linear",
+            NodeSpec::linear_gradient(Vec2::new(-4.0, 0.0), Vec2::new(4.0, 0.0)),
+        );
+        spec.add("radial", NodeSpec::radial_gradient(Vec2::ZERO, 0.0, 4.0));
+        spec.add("shift_x", NodeSpec::constant(0.5));
+        spec.add("shift_y", NodeSpec::constant(-1.0));
+        spec.add(
+            "warped_x",
+            NodeSpec::domain_warp("x".into(), "shift_x".into(), "shift_y".into(), 2.0),
+        );
+        spec.add(
+            "warped_y",
+            NodeSpec::domain_warp("y".into(), "shift_x".into(), "shift_y".into(), 2.0),
+        );
+
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+        let textures = TextureRegistry::new();
+        let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+        let grid = grid();
+        let chunk = ChunkId(0, 0);
+        let p = Vec2::new(1.0, 2.0);
+
+        approx_eq(runtime.sample("x", p, chunk, &grid), 1.0);
+        approx_eq(runtime.sample("y", p, chunk, &grid), 2.0);
+        approx_eq(
+            runtime.sample("dist", p, chunk, &grid),
+            8.0_f32.sqrt() / 10.0,
+        );
+        approx_eq(runtime.sample("linear", p, chunk, &grid), 0.625);
+        approx_eq(
+            runtime.sample("radial", p, chunk, &grid),
+            5.0_f32.sqrt() / 4.0,
+        );
+        approx_eq(runtime.sample("warped_x", p, chunk, &grid), 2.0);
+        approx_eq(runtime.sample("warped_y", p, chunk, &grid), 0.0);
+    }
+
+    #[test]
+    fn baked_rasters_use_the_raster_sampler() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("nearest", NodeSpec::position_x());
+        spec.add("bilinear", NodeSpec::position_x());
+        spec.set_raster_sampler(
+            "bilinear",
+            TextureSampler::default().with_filter(TextureFilter::Bilinear),
+        );
+        let mut opts = CompileOptions::default();
+        opts.force_bake.insert("nearest".into());
+        opts.force_bake.insert("bilinear".into());
+        let program = FieldGraphCompiler::compile(&spec, &opts).unwrap();
+
+        let textures = TextureRegistry::new();
+        let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 1.0,
+            width: 4,
+            height: 4,
+            halo: 0,
+        };
+        let p = Vec2::new(1.25, 1.0);
+        approx_eq(runtime.sample("nearest", p, ChunkId(0, 0), &grid), 1.0);
+        approx_eq(runtime.sample("bilinear", p, ChunkId(0, 0), &grid), 1.25);
+    }
+
+    #[test]
+    fn filter_nodes_chain_through_baked_rasters() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("mask", NodeSpec::smoothstep("x".into(), 2.5, 2.5));
+        spec.add("signed", NodeSpec::edt_signed("mask".into(), 0.5, 0.0));
+        spec.add("grown", NodeSpec::dilate("mask".into(), 1.0));
+        spec.add("soft", NodeSpec::blur("grown".into(), BlurKernel::Box, 1.0));
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+
+        let textures = TextureRegistry::new();
+        let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 1.0,
+            width: 6,
+            height: 1,
+            halo: 0,
+        };
+        let chunk = ChunkId(0, 0);
+        let positions: Vec<Vec2> = (0..6).map(|x| Vec2::new(x as f32, 0.0)).collect();
+
+        let mut out = vec![0.0; positions.len()];
+        runtime.sample_batch("signed", &positions, chunk, &grid, &mut out);
+        assert_eq!(out, vec![-1.0, -1.0, -0.5, 0.5, 1.0, 1.0]);
+
+        runtime.sample_batch("grown", &positions, chunk, &grid, &mut out);
+        assert_eq!(out, vec![0.0, 0.0, 1.0, 1.0, 1.0, 1.0]);
+
+        runtime.sample_batch("soft", &positions, chunk, &grid, &mut out);
+        approx_eq(out[1], 1.0 / 3.0);
+        approx_eq(out[2], 2.0 / 3.0);
+        approx_eq(out[4], 1.0);
+    }
+
+    #[test]
+    fn runtime_evaluates_math_nodes_per_position() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("half", NodeSpec::constant(0.5));
+        spec.add("low", NodeSpec::constant(-1.0));
+        spec.add("high", NodeSpec::constant(3.0));
+        spec.add("right", NodeSpec::greater("x".into(), "half".into()));
+        spec.add(
+            "picked",
+            NodeSpec::select("right".into(), "high".into(), "low".into()),
+        );
+        spec.add(
+            "blend",
+            NodeSpec::lerp("low".into(), "high".into(), "x".into()),
+        );
+        spec.add("wrapped", NodeSpec::modulo("x".into(), "half".into()));
+        spec.add("ratio", NodeSpec::div("high".into(), "x".into()));
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+
+        let textures = TextureRegistry::new();
+        let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+        let chunk = ChunkId(0, 0);
+        let positions = [Vec2::ZERO, Vec2::new(0.75, 0.0), Vec2::new(1.0, 0.0)];
+        let mut out = [0.0; 3];
+
+        runtime.sample_batch("picked", &positions, chunk, &grid(), &mut out);
+        assert_eq!(out, [-1.0, 3.0, 3.0]);
+        runtime.sample_batch("blend", &positions, chunk, &grid(), &mut out);
+        assert_eq!(out, [-1.0, 2.0, 3.0]);
+        runtime.sample_batch("wrapped", &positions, chunk, &grid(), &mut out);
+        assert_eq!(out, [0.0, 0.25, 0.0]);
+        runtime.sample_batch("ratio", &positions, chunk, &grid(), &mut out);
+        assert_eq!(out, [0.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    fn sample_batch_many_matches_single_fields() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add("mask", NodeSpec::smoothstep("x".into(), 2.5, 2.5));
+        spec.add("near", NodeSpec::edt_normalize("mask".into(), 0.5, 4.0));
+        spec.add("inv", NodeSpec::invert("near".into()));
+        spec.add("scaled", NodeSpec::mul(vec!["near".into(), "x".into()]));
+        let program =
+            Arc::new(FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap());
+
+        let textures = TextureRegistry::new();
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 1.0,
+            width: 6,
+            height: 1,
+            halo: 0,
+        };
+        let chunk = ChunkId(0, 0);
+        let positions: Vec<Vec2> = (0..6).map(|x| Vec2::new(x as f32, 0.0)).collect();
+        let fields = ["inv", "missing", "near", "scaled"];
+
+        let mut many = vec![1.0; fields.len() * positions.len()];
+        let mut runtime = FieldRuntime::new(program.clone(), &textures);
+        runtime.sample_batch_many(&fields, &positions, chunk, &grid, &mut many);
+
+        let mut runtime = FieldRuntime::new(program, &textures);
+        let mut out = vec![0.0; positions.len()];
+        for (field, row) in fields.iter().zip(many.chunks_exact(positions.len())) {
+            runtime.sample_batch(field, &positions, chunk, &grid, &mut out);
+            assert_eq!(row, out.as_slice(), "field '{field}'");
+        }
+    }
+
+    #[test]
+    fn runtime_evaluates_expression_nodes() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("x", NodeSpec::position_x());
+        spec.add_with_semantics(
+            "band",
+            NodeSpec::expr("select(x > 0.5, 2 * x, -x) + clamp(x, 0, 0.25)"),
+            FieldSemantics::Probability,
+        );
+        for optimize in [false, true] {
+            let opts = CompileOptions::default().with_optimize(optimize);
+            let program = FieldGraphCompiler::compile(&spec, &opts).unwrap();
+            let textures = TextureRegistry::new();
+            let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+            let positions = [Vec2::ZERO, Vec2::new(0.75, 0.0), Vec2::new(1.0, 0.0)];
+            let mut out = [0.0; 3];
+            runtime.sample_batch("band", &positions, ChunkId(0, 0), &grid(), &mut out);
+            assert_eq!(out, [0.0, 1.75, 2.25]);
+        }
+    }
+
+    #[test]
+    fn derivative_nodes_use_the_grid_halo() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("height", NodeSpec::texture("height", TextureChannel::R));
+        spec.add(
+            "slope",
+            NodeSpec::derivative("height".into(), DerivativeKind::Slope),
+        );
+        spec.add(
+            "gradient",
+            NodeSpec::Derivative {
+                inputs: vec!["height".into()],
+                params: DerivativeParams {
+                    kind: DerivativeKind::GradientMagnitude,
+                    height_scale: 0.5,
+                },
+            },
+        );
+        let program = FieldGraphCompiler::compile(&spec, &CompileOptions::default()).unwrap();
+
+        let mut textures = TextureRegistry::new();
+        textures.register("height", RampTexture(2.0));
+        let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
+        let grid = ChunkGrid {
+            origin_domain: Vec2::ZERO,
+            cell_size: 0.5,
+            width: 4,
+            height: 1,
+            halo: 1,
+        };
+        let chunk = ChunkId(0, 0);
+        let positions: Vec<Vec2> = (0..4).map(|x| Vec2::new(x as f32 * 0.5, 0.0)).collect();
+
+        let mut out = vec![0.0; positions.len()];
+        runtime.sample_batch("slope", &positions, chunk, &grid, &mut out);
+        for value in &out {
+            approx_eq(*value, 2.0f32.atan());
+        }
+
+        runtime.sample_batch("gradient", &positions, chunk, &grid, &mut out);
+        for value in &out {
+            approx_eq(*value, 1.0);
+        }
+    }
+
+    #[test]
+    fn unknown_field_sample_returns_zero() {
+        let program = FieldProgram::new(HashMap::new(), Vec::new());
         let textures = TextureRegistry::new();
         let mut runtime = FieldRuntime::new(Arc::new(program), &textures);
         let grid = grid();
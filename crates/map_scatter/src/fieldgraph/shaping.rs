@@ -0,0 +1,179 @@
+//! Value shaping helpers for remap, curve, step, and lookup-table nodes.
+//!
+//! These functions map one input value to one output value, so probability responses and
+//! falloffs can be authored as data in a [`crate::fieldgraph::spec::FieldGraphSpec`] instead
+//! of chains of scale, clamp, and power nodes.
+use crate::fieldgraph::node::{CurveInterpolation, CurveParams, LutParams, RemapParams};
+
+/// Maps `v` linearly from the input range onto the output range.
+#[inline]
+pub fn remap(params: &RemapParams, v: f32) -> f32 {
+    let mut t = (v - params.in_min) / (params.in_max - params.in_min);
+    if params.clamp {
+        t = t.clamp(0.0, 1.0);
+    }
+    params.out_min + t * (params.out_max - params.out_min)
+}
+
+/// Returns `1.0` if `v` is at or above `threshold`, `0.0` otherwise.
+#[inline]
+pub fn step(threshold: f32, v: f32) -> f32 {
+    if v >= threshold {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Evaluates the curve at `x`, holding the end values outside the control points.
+pub fn curve(params: &CurveParams, x: f32) -> f32 {
+    let points = params.points.as_slice();
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return 0.0;
+    };
+    if x.is_nan() || x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+
+    let k = points.partition_point(|p| p.0 <= x) - 1;
+    let (x0, y0) = points[k];
+    let (x1, y1) = points[k + 1];
+    let h = x1 - x0;
+    let t = (x - x0) / h;
+    match params.interpolation {
+        CurveInterpolation::Linear => y0 + t * (y1 - y0),
+        CurveInterpolation::Spline => {
+            let (m0, m1) = monotone_tangents(points, k);
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                + (t3 - 2.0 * t2 + t) * h * m0
+                + (-2.0 * t3 + 3.0 * t2) * y1
+                + (t3 - t2) * h * m1
+        }
+    }
+}
+
+/// Looks up `v` in the table, interpolating linearly between entries.
+pub fn lut(params: &LutParams, v: f32) -> f32 {
+    let values = params.values.as_slice();
+    let Some(last) = values.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let t = ((v - params.in_min) / (params.in_max - params.in_min)).clamp(0.0, 1.0);
+    let pos = t * last as f32;
+    let i = (pos.floor() as usize).min(last);
+    let j = (i + 1).min(last);
+    values[i] + (pos - i as f32) * (values[j] - values[i])
+}
+
+/// Fritsch-Carlson tangents at both ends of segment `k`, limited so the segment stays
+/// monotone between its control points.
+fn monotone_tangents(points: &[(f32, f32)], k: usize) -> (f32, f32) {
+    let secant = |i: usize| (points[i + 1].1 - points[i].1) / (points[i + 1].0 - points[i].0);
+    let width = |i: usize| points[i + 1].0 - points[i].0;
+    let tangent = |i: usize| {
+        if i == 0 {
+            return secant(0);
+        }
+        if i == points.len() - 1 {
+            return secant(i - 1);
+        }
+        let (d0, d1) = (secant(i - 1), secant(i));
+        if d0 * d1 <= 0.0 {
+            return 0.0;
+        }
+        let (h0, h1) = (width(i - 1), width(i));
+        3.0 * (h0 + h1) / ((2.0 * h1 + h0) / d0 + (h1 + 2.0 * h0) / d1)
+    };
+
+    let d = secant(k);
+    if d == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (m0, m1) = (tangent(k), tangent(k + 1));
+    let (a, b) = (m0 / d, m1 / d);
+    let norm = a * a + b * b;
+    if norm > 9.0 {
+        let tau = 3.0 / norm.sqrt();
+        (m0 * tau, m1 * tau)
+    } else {
+        (m0, m1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve_params(points: Vec<(f32, f32)>, interpolation: CurveInterpolation) -> CurveParams {
+        CurveParams {
+            points,
+            interpolation,
+        }
+    }
+
+    #[test]
+    fn remap_maps_and_optionally_clamps() {
+        let mut params = RemapParams {
+            in_min: 2.0,
+            in_max: 4.0,
+            out_min: 1.0,
+            out_max: 0.0,
+            clamp: false,
+        };
+        assert_eq!(remap(&params, 3.0), 0.5);
+        assert_eq!(remap(&params, 6.0), -1.0);
+        params.clamp = true;
+        assert_eq!(remap(&params, 6.0), 0.0);
+        assert_eq!(step(0.5, 0.5), 1.0);
+        assert_eq!(step(0.5, 0.25), 0.0);
+    }
+
+    #[test]
+    fn linear_curve_interpolates_and_holds_ends() {
+        let params = curve_params(
+            vec![(0.0, 0.0), (1.0, 1.0), (3.0, 0.0)],
+            CurveInterpolation::Linear,
+        );
+        assert_eq!(curve(&params, -1.0), 0.0);
+        assert_eq!(curve(&params, 0.5), 0.5);
+        assert_eq!(curve(&params, 2.0), 0.5);
+        assert_eq!(curve(&params, 5.0), 0.0);
+    }
+
+    #[test]
+    fn spline_curve_passes_through_points_without_overshoot() {
+        let points = vec![(0.0, 0.0), (1.0, 0.1), (2.0, 1.0), (3.0, 1.0)];
+        let params = curve_params(points.clone(), CurveInterpolation::Spline);
+        for (x, y) in points {
+            assert!((curve(&params, x) - y).abs() < 1e-6);
+        }
+        let mut previous = 0.0;
+        for i in 0..=300 {
+            let value = curve(&params, i as f32 / 100.0);
+            assert!(value >= previous - 1e-6 && value <= 1.0 + 1e-6);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn lut_interpolates_entries() {
+        let params = LutParams {
+            in_min: 0.0,
+            in_max: 1.0,
+            values: vec![0.0, 1.0, 0.0],
+        };
+        assert_eq!(lut(&params, 0.25), 0.5);
+        assert_eq!(lut(&params, 0.5), 1.0);
+        assert_eq!(lut(&params, 2.0), 0.0);
+        let single = LutParams {
+            values: vec![0.75],
+            ..params
+        };
+        assert_eq!(lut(&single, 0.3), 0.75);
+    }
+}
@@ -0,0 +1,153 @@
+//! Field programs shared by several kinds.
+//!
+//! Kinds of one layer are evaluated at the same candidate positions and often read the same
+//! textures, noise, or filtered rasters. [`SharedProgram`] merges their compiled programs into
+//! one, identifying nodes by their content and inputs, so a single
+//! [`crate::fieldgraph::runtime::FieldRuntime`] samples each distinct node once per batch and
+//! bakes each distinct raster once per chunk.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::fieldgraph::cache::hash_node;
+use crate::fieldgraph::{FieldId, FieldProgram, NodeMeta, NodeSpec};
+
+/// Compiled programs merged into one program with structurally identical nodes deduplicated.
+#[derive(Clone, Debug)]
+pub struct SharedProgram {
+    program: Arc<FieldProgram>,
+    fields: Vec<HashMap<FieldId, FieldId>>,
+}
+
+impl SharedProgram {
+    /// Merges `programs`. Fields of the program at index `i` are looked up with
+    /// [`SharedProgram::field`].
+    pub fn new(programs: &[Arc<FieldProgram>]) -> Self {
+        Self::merge(programs, |spec, meta| {
+            let mut hasher = DefaultHasher::new();
+            hash_node(spec, &mut hasher);
+            meta.force_bake.hash(&mut hasher);
+            meta.raster_sampler.key().hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+
+    /// Merges `programs`, looking up candidates for a node by `digest` and sharing it only
+    /// with a candidate that is equal. The digest names the shared node.
+    fn merge(programs: &[Arc<FieldProgram>], digest: impl Fn(&NodeSpec, &NodeMeta) -> u64) -> Self {
+        let mut nodes: HashMap<FieldId, NodeMeta> = HashMap::new();
+        let mut topo: Vec<FieldId> = Vec::new();
+        let mut by_digest: HashMap<u64, Vec<FieldId>> = HashMap::new();
+        let mut fields = Vec::with_capacity(programs.len());
+
+        for program in programs {
+            let mut renamed: HashMap<FieldId, FieldId> = HashMap::new();
+            for id in &program.topo {
+                let meta = &program.nodes[id];
+                let mut spec = meta.spec.clone();
+                for input in spec.inputs_mut() {
+                    // Unknown inputs keep their id and read as zero, like in the source program.
+                    if let Some(shared) = renamed.get(input) {
+                        *input = shared.clone();
+                    }
+                }
+
+                let digest = digest(&spec, meta);
+                let candidates = by_digest.entry(digest).or_default();
+                let existing = candidates.iter().find(|candidate| {
+                    let node = &nodes[*candidate];
+                    node.spec == spec
+                        && node.force_bake == meta.force_bake
+                        && node.raster_sampler == meta.raster_sampler
+                });
+                let shared = match existing {
+                    Some(shared) => shared.clone(),
+                    None => {
+                        // Distinct nodes with the same digest are told apart by a suffix.
+                        let shared = match candidates.len() {
+                            0 => format!("{digest:016x}"),
+                            n => format!("{digest:016x}-{n}"),
+                        };
+                        nodes.insert(
+                            shared.clone(),
+                            NodeMeta {
+                                id: shared.clone(),
+                                spec,
+                                force_bake: meta.force_bake,
+                                raster_sampler: meta.raster_sampler,
+                                semantics: None,
+                            },
+                        );
+                        topo.push(shared.clone());
+                        candidates.push(shared.clone());
+                        shared
+                    }
+                };
+                renamed.insert(id.clone(), shared);
+            }
+            fields.push(renamed);
+        }
+
+        Self {
+            program: Arc::new(FieldProgram::new(nodes, topo)),
+            fields,
+        }
+    }
+
+    /// Returns the merged program.
+    pub fn program(&self) -> &Arc<FieldProgram> {
+        &self.program
+    }
+
+    /// Returns the id in the merged program of `field` of the program at `index`.
+    pub fn field(&self, index: usize, field: &str) -> Option<&FieldId> {
+        self.fields.get(index)?.get(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fieldgraph::compiler::{CompileOptions, FieldGraphCompiler};
+    use crate::prelude::{FieldGraphSpec, FieldSemantics, NodeSpec, TextureChannel};
+
+    fn program(threshold: f32) -> Arc<FieldProgram> {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("height", NodeSpec::texture("height", TextureChannel::R));
+        spec.add("near", NodeSpec::edt_normalize("height".into(), 0.5, 4.0));
+        spec.add_with_semantics(
+            "gate",
+            NodeSpec::step("near".into(), threshold),
+            FieldSemantics::Gate,
+        );
+        let opts = CompileOptions::default().with_optimize(true);
+        Arc::new(FieldGraphCompiler::compile(&spec, &opts).unwrap())
+    }
+
+    #[test]
+    fn identical_nodes_are_shared() {
+        let shared = SharedProgram::new(&[program(0.5), program(0.5), program(0.25)]);
+        // One texture and EDT node, and one gate per distinct threshold.
+        assert_eq!(shared.program().nodes.len(), 4);
+        assert_eq!(shared.field(0, "near"), shared.field(2, "near"));
+        assert_eq!(shared.field(0, "gate"), shared.field(1, "gate"));
+        assert_ne!(shared.field(0, "gate"), shared.field(2, "gate"));
+        assert!(shared.field(3, "gate").is_none());
+    }
+
+    #[test]
+    fn colliding_digests_keep_distinct_nodes_apart() {
+        let shared = SharedProgram::merge(&[program(0.5), program(0.5), program(0.25)], |_, _| 7);
+        assert_eq!(shared.program().nodes.len(), 4);
+        assert_eq!(shared.field(0, "gate"), shared.field(1, "gate"));
+        assert_ne!(shared.field(0, "gate"), shared.field(2, "gate"));
+        assert_ne!(shared.field(0, "near"), shared.field(0, "height"));
+
+        let gate = shared.field(2, "gate").unwrap();
+        assert!(matches!(
+            &shared.program().nodes[gate].spec,
+            NodeSpec::Step { params, .. } if params.threshold == 0.25
+        ));
+    }
+}
@@ -4,10 +4,11 @@ use std::collections::HashMap;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::fieldgraph::{FieldId, NodeSpec};
+use crate::fieldgraph::{FieldId, NodeSpec, TextureSampler};
 
 /// A specification of a field graph, including nodes and their semantics.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, Default)]
 #[non_exhaustive]
 pub struct FieldGraphSpec {
@@ -15,6 +16,12 @@ pub struct FieldGraphSpec {
     pub nodes: HashMap<FieldId, NodeSpec>,
     /// Semantic tags for fields (gate or probability).
     pub semantics: HashMap<FieldId, FieldSemantics>,
+    /// Samplers used to read the rasters of baked fields, such as EDT nodes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub raster_samplers: HashMap<FieldId, TextureSampler>,
+    /// Subgraph definitions instantiated by [`NodeSpec::Subgraph`] nodes, keyed by name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub subgraphs: HashMap<String, SubgraphSpec>,
 }
 
 impl FieldGraphSpec {
@@ -30,6 +37,18 @@ impl FieldGraphSpec {
         self
     }
 
+    /// Set the sampler used to read the baked raster of a field.
+    pub fn set_raster_sampler(&mut self, id: &str, sampler: TextureSampler) -> &mut Self {
+        self.raster_samplers.insert(id.to_string(), sampler);
+        self
+    }
+
+    /// Add a subgraph definition that [`NodeSpec::Subgraph`] nodes can instantiate.
+    pub fn add_subgraph(&mut self, name: &str, subgraph: SubgraphSpec) -> &mut Self {
+        self.subgraphs.insert(name.to_string(), subgraph);
+        self
+    }
+
     /// Add a node with semantics to the field graph specification.
     pub fn add_with_semantics(
         &mut self,
@@ -43,11 +62,59 @@ impl FieldGraphSpec {
     }
 }
 
+/// A named, reusable part of a field graph, such as "not on roads, not in water".
+///
+/// Its nodes may read the subgraph inputs, which each instance binds to fields of the graph it
+/// is used in. The compiler inlines every instance, so the nodes of an instance `id` become
+/// `<id>/<node>` and the output node becomes `id`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct SubgraphSpec {
+    /// Input names, bound to fields in order by each instance.
+    pub inputs: Vec<FieldId>,
+    /// Node whose value an instance produces.
+    pub output: FieldId,
+    /// Node definitions keyed by field id, local to the subgraph.
+    pub nodes: HashMap<FieldId, NodeSpec>,
+}
+
+impl SubgraphSpec {
+    /// Creates an empty subgraph with the given input names and output node.
+    pub fn new<I, S>(inputs: I, output: &str) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<FieldId>,
+    {
+        Self {
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            output: output.to_string(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Add a node to the subgraph.
+    pub fn add(&mut self, id: &str, spec: NodeSpec) -> &mut Self {
+        self.nodes.insert(id.to_string(), spec);
+        self
+    }
+}
+
 /// The semantics of a field node, indicating its role in the field graph.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum FieldSemantics {
     Gate,
     Probability,
+    /// Per-placement scale factor.
+    Scale,
+    /// Per-placement rotation in radians.
+    Rotation,
+    /// Per-placement variant index (floored).
+    Variant,
+    /// Named per-placement attribute.
+    Attribute(String),
 }
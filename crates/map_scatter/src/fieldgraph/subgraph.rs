@@ -0,0 +1,336 @@
+//! Inlining of expression and subgraph nodes.
+//!
+//! [`NodeSpec::Expr`] and [`NodeSpec::Subgraph`] nodes are replaced by regular nodes before a
+//! [`FieldGraphSpec`] is validated. An instance `id` of a [`SubgraphSpec`] becomes the
+//! subgraph's nodes renamed to `<id>/<node>`, with its output node named `id` and its inputs
+//! replaced by the bound fields. Subgraphs expanded once (fragments) are kept in a
+//! [`FragmentCache`], so kinds sharing a subgraph library expand each definition once.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+use crate::error::{Error, Result};
+use crate::fieldgraph::cache::hash_subgraphs;
+use crate::fieldgraph::spec::{FieldGraphSpec, SubgraphSpec};
+use crate::fieldgraph::{expr, FieldId, NodeSpec};
+
+/// Subgraph with its expression and nested subgraph nodes inlined, in local names.
+pub(crate) struct Fragment {
+    inputs: Vec<FieldId>,
+    output: FieldId,
+    nodes: Vec<(FieldId, NodeSpec)>,
+}
+
+/// Fragments keyed by subgraph name and the library defining it.
+#[derive(Default)]
+pub(crate) struct FragmentCache {
+    fragments: RwLock<HashMap<u64, Arc<Fragment>>>,
+}
+
+impl FragmentCache {
+    fn get(&self, key: u64) -> Option<Arc<Fragment>> {
+        let fragments = self.fragments.read().expect("FragmentCache lock poisoned");
+        fragments.get(&key).cloned()
+    }
+
+    fn insert(&self, key: u64, fragment: Arc<Fragment>) {
+        let mut fragments = self.fragments.write().expect("FragmentCache lock poisoned");
+        fragments.insert(key, fragment);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.fragments
+            .read()
+            .expect("FragmentCache lock poisoned")
+            .len()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.fragments
+            .write()
+            .expect("FragmentCache lock poisoned")
+            .clear();
+    }
+}
+
+/// Returns `true` if the graph has expression or subgraph nodes to inline.
+pub(crate) fn needs_inlining(spec: &FieldGraphSpec) -> bool {
+    spec.nodes
+        .values()
+        .any(|node| matches!(node, NodeSpec::Expr { .. } | NodeSpec::Subgraph { .. }))
+}
+
+/// Returns a copy of `spec` with expression and subgraph nodes replaced by the nodes they
+/// expand to, reusing fragments from `cache` if given.
+pub(crate) fn inline(
+    spec: &FieldGraphSpec,
+    cache: Option<&FragmentCache>,
+) -> Result<FieldGraphSpec> {
+    let mut inliner = Inliner {
+        library: &spec.subgraphs,
+        library_key: cache.map(|_| {
+            let mut hasher = DefaultHasher::new();
+            hash_subgraphs(&spec.subgraphs, &mut hasher);
+            hasher.finish()
+        }),
+        cache,
+        stack: Vec::new(),
+    };
+
+    let mut inlined = spec.clone();
+    let mut emitted: HashSet<FieldId> = HashSet::new();
+    let mut ids: Vec<&FieldId> = spec.nodes.keys().collect();
+    ids.sort();
+    for id in ids {
+        let (variant, nodes) = match &spec.nodes[id] {
+            NodeSpec::Expr { params } => ("Expr", expr::expand(id, &params.source)?),
+            NodeSpec::Subgraph { inputs, params } => {
+                ("Subgraph", inliner.instantiate(id, inputs, &params.name)?)
+            }
+            _ => continue,
+        };
+        for (node_id, node) in nodes {
+            let defined = node_id != *id && spec.nodes.contains_key(&node_id);
+            if defined || !emitted.insert(node_id.clone()) {
+                return Err(Error::Compile(format!(
+                    "Node '{id}' ({variant}) expands to '{node_id}', which is already defined"
+                )));
+            }
+            inlined.nodes.insert(node_id, node);
+        }
+    }
+    Ok(inlined)
+}
+
+struct Inliner<'a> {
+    library: &'a HashMap<String, SubgraphSpec>,
+    library_key: Option<u64>,
+    cache: Option<&'a FragmentCache>,
+    /// Subgraphs being expanded, to reject recursive definitions.
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Inliner<'a> {
+    fn instantiate(
+        &mut self,
+        id: &str,
+        bindings: &[FieldId],
+        name: &str,
+    ) -> Result<Vec<(FieldId, NodeSpec)>> {
+        let fragment = self.fragment(id, name)?;
+        if bindings.len() != fragment.inputs.len() {
+            return Err(Error::Compile(format!(
+                "Node '{id}' (Subgraph) binds {} input(s) but subgraph '{name}' declares {}",
+                bindings.len(),
+                fragment.inputs.len()
+            )));
+        }
+
+        let rename = |field: &FieldId| -> FieldId {
+            if let Some(index) = fragment.inputs.iter().position(|input| input == field) {
+                bindings[index].clone()
+            } else if *field == fragment.output {
+                id.to_string()
+            } else {
+                format!("{id}/{field}")
+            }
+        };
+        Ok(fragment
+            .nodes
+            .iter()
+            .map(|(node_id, node)| {
+                let mut node = node.clone();
+                for input in node.inputs_mut() {
+                    *input = rename(input);
+                }
+                (rename(node_id), node)
+            })
+            .collect())
+    }
+
+    fn fragment(&mut self, id: &str, name: &str) -> Result<Arc<Fragment>> {
+        let Some((name, subgraph)) = self.library.get_key_value(name) else {
+            return Err(Error::Compile(format!(
+                "Node '{id}' (Subgraph) instantiates unknown subgraph '{name}'"
+            )));
+        };
+        if self.stack.contains(&name.as_str()) {
+            return Err(Error::Compile(format!(
+                "Subgraph '{name}' instantiates itself"
+            )));
+        }
+
+        let key = self.library_key.map(|library_key| {
+            let mut hasher = DefaultHasher::new();
+            library_key.hash(&mut hasher);
+            name.hash(&mut hasher);
+            hasher.finish()
+        });
+        if let (Some(cache), Some(key)) = (self.cache, key) {
+            if let Some(fragment) = cache.get(key) {
+                return Ok(fragment);
+            }
+        }
+
+        self.stack.push(name);
+        let fragment = self.expand(name, subgraph);
+        self.stack.pop();
+        let fragment = Arc::new(fragment?);
+        if let (Some(cache), Some(key)) = (self.cache, key) {
+            cache.insert(key, fragment.clone());
+        }
+        Ok(fragment)
+    }
+
+    fn expand(&mut self, name: &str, subgraph: &'a SubgraphSpec) -> Result<Fragment> {
+        if !subgraph.nodes.contains_key(&subgraph.output) {
+            return Err(Error::Compile(format!(
+                "Subgraph '{name}' has no output node '{}'",
+                subgraph.output
+            )));
+        }
+
+        let mut ids: Vec<&FieldId> = subgraph.nodes.keys().collect();
+        ids.sort();
+        let mut nodes = Vec::new();
+        for id in ids {
+            match &subgraph.nodes[id] {
+                NodeSpec::Expr { params } => nodes.extend(expr::expand(id, &params.source)?),
+                NodeSpec::Subgraph { inputs, params } => {
+                    nodes.extend(self.instantiate(id, inputs, &params.name)?);
+                }
+                node => nodes.push((id.clone(), node.clone())),
+            }
+        }
+
+        let mut defined: HashSet<&str> = HashSet::new();
+        for (id, _) in &nodes {
+            if subgraph.inputs.contains(id) || !defined.insert(id) {
+                return Err(Error::Compile(format!(
+                    "Subgraph '{name}' defines '{id}' more than once"
+                )));
+            }
+        }
+        for (id, node) in &nodes {
+            for input in node.inputs() {
+                if !defined.contains(input.as_str()) && !subgraph.inputs.contains(input) {
+                    return Err(Error::Compile(format!(
+                        "Subgraph '{name}' node '{id}' references unknown input '{input}'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Fragment {
+            inputs: subgraph.inputs.clone(),
+            output: subgraph.output.clone(),
+            nodes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fieldgraph::TextureChannel;
+
+    fn avoid_roads() -> SubgraphSpec {
+        let mut subgraph = SubgraphSpec::new(["moisture"], "out");
+        subgraph.add("roads", NodeSpec::texture("mask_roads", TextureChannel::R));
+        subgraph.add("out", NodeSpec::expr("(1 - roads) * moisture"));
+        subgraph
+    }
+
+    #[test]
+    fn instances_are_renamed_and_bound() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add_subgraph("avoid_roads", avoid_roads());
+        spec.add("wet", NodeSpec::constant(0.5));
+        spec.add(
+            "prob",
+            NodeSpec::subgraph("avoid_roads", vec!["wet".into()]),
+        );
+
+        let inlined = inline(&spec, None).unwrap();
+        let mut ids: Vec<&str> = inlined.nodes.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        assert_eq!(
+            ids,
+            ["prob", "prob/out/0", "prob/out/1", "prob/roads", "wet"]
+        );
+        assert!(matches!(
+            &inlined.nodes["prob"],
+            NodeSpec::Mul { inputs } if inputs == &["prob/out/1", "wet"]
+        ));
+        assert!(matches!(
+            &inlined.nodes["prob/out/1"],
+            NodeSpec::Sub { inputs } if inputs == &["prob/out/0", "prob/roads"]
+        ));
+    }
+
+    #[test]
+    fn nested_subgraphs_and_cache_share_fragments() {
+        let mut outer = SubgraphSpec::new(["x"], "out");
+        outer.add("out", NodeSpec::subgraph("avoid_roads", vec!["x".into()]));
+        let mut spec = FieldGraphSpec::default();
+        spec.add_subgraph("avoid_roads", avoid_roads());
+        spec.add_subgraph("outer", outer);
+        spec.add("wet", NodeSpec::constant(0.5));
+        spec.add("a", NodeSpec::subgraph("outer", vec!["wet".into()]));
+        spec.add("b", NodeSpec::subgraph("avoid_roads", vec!["a".into()]));
+
+        let cache = FragmentCache::default();
+        let inlined = inline(&spec, Some(&cache)).unwrap();
+        assert!(inlined.nodes.contains_key("a/out/roads"));
+        assert!(inlined.nodes.contains_key("b/roads"));
+        assert_eq!(cache.len(), 2);
+        inline(&spec, Some(&cache)).unwrap();
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn invalid_subgraphs_are_compile_errors() {
+        let message = |spec: &FieldGraphSpec| match inline(spec, None) {
+            Err(Error::Compile(message)) => message,
+            other => panic!("expected compile error, got {:?}", other.map(|_| ())),
+        };
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add("prob", NodeSpec::subgraph("missing", Vec::new()));
+        assert!(message(&spec).contains("unknown subgraph 'missing'"));
+
+        spec.add_subgraph("avoid_roads", avoid_roads());
+        spec.add("prob", NodeSpec::subgraph("avoid_roads", Vec::new()));
+        assert!(message(&spec).contains("binds 0 input(s) but subgraph 'avoid_roads' declares 1"));
+
+        let mut looping = SubgraphSpec::new(Vec::<String>::new(), "out");
+        looping.add("out", NodeSpec::subgraph("looping", Vec::new()));
+        spec.add_subgraph("looping", looping);
+        spec.add("prob", NodeSpec::subgraph("looping", Vec::new()));
+        assert!(message(&spec).contains("Subgraph 'looping' instantiates itself"));
+
+        let mut leaky = SubgraphSpec::new(Vec::<String>::new(), "out");
+        leaky.add("out", NodeSpec::invert("outer".into()));
+        spec.add_subgraph("leaky", leaky);
+        spec.add("prob", NodeSpec::subgraph("leaky", Vec::new()));
+        assert!(message(&spec).contains("node 'out' references unknown input 'outer'"));
+    }
+
+    #[test]
+    fn expansions_cannot_emit_the_same_id() {
+        let mut nested = SubgraphSpec::new(Vec::<String>::new(), "out");
+        nested.add("q/0", NodeSpec::constant(1.0));
+        nested.add("out", NodeSpec::invert("q/0".into()));
+        let mut spec = FieldGraphSpec::default();
+        spec.add_subgraph("nested", nested);
+        spec.add("p", NodeSpec::subgraph("nested", Vec::new()));
+        spec.add("p/q", NodeSpec::expr("1 - mask_roads.r"));
+
+        let Err(Error::Compile(message)) = inline(&spec, None) else {
+            panic!("expected compile error");
+        };
+        assert!(message.contains("expands to 'p/q/0', which is already defined"));
+    }
+}
@@ -4,6 +4,7 @@
 //! - Define custom sources by implementing [`Texture`].
 //! - Manage instances with [`TextureRegistry`].
 //! - Sample channels via [`TextureChannel`].
+//! - Choose filtering and edge addressing with [`TextureSampler`].
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -14,6 +15,7 @@ use tracing::warn;
 
 /// Texture channel to sample from.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum TextureChannel {
     R,
@@ -22,10 +24,167 @@ pub enum TextureChannel {
     A,
 }
 
+/// Filter used to reconstruct values between texels.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextureFilter {
+    /// Value of the texel containing the position.
+    #[default]
+    Nearest,
+    /// Linear interpolation between the four closest texel centers.
+    Bilinear,
+    /// Catmull-Rom interpolation over the 4x4 closest texel centers.
+    Bicubic,
+}
+
+/// Addressing of texels outside a texture or raster.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextureAddress {
+    /// Repeats the edge texels.
+    #[default]
+    Clamp,
+    /// Tiles the texture.
+    Wrap,
+    /// Reads the given value outside the texture.
+    Border(f32),
+}
+
+/// Filter and addressing mode used to sample a texture or raster.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextureSampler {
+    /// Filter between texels.
+    pub filter: TextureFilter,
+    /// Addressing outside the texture.
+    pub address: TextureAddress,
+}
+
+impl TextureSampler {
+    /// Creates a new [`TextureSampler`].
+    pub fn new(filter: TextureFilter, address: TextureAddress) -> Self {
+        Self { filter, address }
+    }
+
+    /// Sets the filter.
+    pub fn with_filter(mut self, filter: TextureFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the addressing mode.
+    pub fn with_address(mut self, address: TextureAddress) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Samples a `width` x `height` grid of texels at texel coordinates `(x, y)`, where texel
+    /// `(i, j)` covers `[i, i + 1) x [j, j + 1)` and `fetch(i, j)` returns its value.
+    pub fn sample_texels(
+        &self,
+        width: usize,
+        height: usize,
+        x: f32,
+        y: f32,
+        fetch: impl Fn(usize, usize) -> f32,
+    ) -> f32 {
+        if width == 0 || height == 0 || !x.is_finite() || !y.is_finite() {
+            return match self.address {
+                TextureAddress::Border(value) => value,
+                _ => 0.0,
+            };
+        }
+
+        let texel = |ix: i64, iy: i64| -> f32 {
+            let (w, h) = (width as i64, height as i64);
+            match self.address {
+                TextureAddress::Clamp => {
+                    fetch(ix.clamp(0, w - 1) as usize, iy.clamp(0, h - 1) as usize)
+                }
+                TextureAddress::Wrap => fetch(ix.rem_euclid(w) as usize, iy.rem_euclid(h) as usize),
+                TextureAddress::Border(value) => {
+                    if ix < 0 || iy < 0 || ix >= w || iy >= h {
+                        value
+                    } else {
+                        fetch(ix as usize, iy as usize)
+                    }
+                }
+            }
+        };
+
+        // Far-away coordinates saturate when converted, so neighbours are found with
+        // saturating arithmetic.
+        match self.filter {
+            TextureFilter::Nearest => texel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (tx, ty) = (x - x0, y - y0);
+                let (ix, iy) = (x0 as i64, y0 as i64);
+                let (ix1, iy1) = (ix.saturating_add(1), iy.saturating_add(1));
+                let top = texel(ix, iy) * (1.0 - tx) + texel(ix1, iy) * tx;
+                let bottom = texel(ix, iy1) * (1.0 - tx) + texel(ix1, iy1) * tx;
+                top * (1.0 - ty) + bottom * ty
+            }
+            TextureFilter::Bicubic => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let wx = catmull_rom_weights(x - x0);
+                let wy = catmull_rom_weights(y - y0);
+                let (ix, iy) = (x0 as i64, y0 as i64);
+                let mut sum = 0.0;
+                for (j, wy) in wy.iter().enumerate() {
+                    let mut row = 0.0;
+                    for (i, wx) in wx.iter().enumerate() {
+                        let (dx, dy) = (i as i64 - 1, j as i64 - 1);
+                        row += texel(ix.saturating_add(dx), iy.saturating_add(dy)) * wx;
+                    }
+                    sum += row * wy;
+                }
+                sum
+            }
+        }
+    }
+
+    /// Stable identity for hashing and merging nodes.
+    pub(crate) fn key(&self) -> (u8, u8, u32) {
+        let (address, border) = match self.address {
+            TextureAddress::Clamp => (0, 0),
+            TextureAddress::Wrap => (1, 0),
+            TextureAddress::Border(value) => (2, value.to_bits()),
+        };
+        (self.filter as u8, address, border)
+    }
+}
+
+/// Catmull-Rom weights of the four texels around a fractional offset `t` in `[0, 1)`.
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
 /// Trait for 2D textures sampled at a position in domain/world coordinates.
 /// Implementors should map the domain position to their own texel space as needed.
 pub trait Texture: Send + Sync {
     fn sample(&self, channel: TextureChannel, p: Vec2) -> f32;
+
+    /// Samples with the given filter and addressing mode. The default ignores the sampler and
+    /// calls [`Texture::sample`]; texel-backed textures can override it, typically with
+    /// [`TextureSampler::sample_texels`].
+    fn sample_with(&self, channel: TextureChannel, p: Vec2, sampler: &TextureSampler) -> f32 {
+        let _ = sampler;
+        self.sample(channel, p)
+    }
 }
 
 /// Registry for storing and managing textures by unique string identifiers.
@@ -108,6 +267,23 @@ impl TextureRegistry {
             0.0
         }
     }
+
+    /// Samples the specified texture like [`TextureRegistry::sample`] with the given sampler.
+    #[inline]
+    pub fn sample_with(
+        &self,
+        texture_id: &str,
+        channel: TextureChannel,
+        p: Vec2,
+        sampler: &TextureSampler,
+    ) -> f32 {
+        if let Some(tex) = self.textures.get(texture_id) {
+            tex.sample_with(channel, p, sampler)
+        } else {
+            warn!("Unknown texture id '{}'.", texture_id);
+            0.0
+        }
+    }
 }
 
 impl Default for TextureRegistry {
@@ -115,3 +291,73 @@ impl Default for TextureRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: [f32; 4] = [0.0, 1.0, 2.0, 3.0];
+
+    fn sample(sampler: TextureSampler, x: f32, y: f32) -> f32 {
+        sampler.sample_texels(2, 2, x, y, |ix, iy| DATA[iy * 2 + ix])
+    }
+
+    #[test]
+    fn nearest_reads_containing_texel() {
+        let sampler = TextureSampler::default();
+        assert_eq!(sample(sampler, 0.2, 0.9), 0.0);
+        assert_eq!(sample(sampler, 1.9, 1.1), 3.0);
+        assert_eq!(sample(sampler, -5.0, 7.0), 2.0);
+    }
+
+    #[test]
+    fn bilinear_interpolates_between_texel_centers() {
+        let sampler = TextureSampler::default().with_filter(TextureFilter::Bilinear);
+        assert_eq!(sample(sampler, 0.5, 0.5), 0.0);
+        assert_eq!(sample(sampler, 1.5, 1.5), 3.0);
+        assert!((sample(sampler, 1.0, 1.0) - 1.5).abs() < 1e-6);
+        assert!((sample(sampler, 1.0, 0.5) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bicubic_passes_through_texel_centers() {
+        let sampler = TextureSampler::default().with_filter(TextureFilter::Bicubic);
+        for (i, expected) in DATA.iter().enumerate() {
+            let (x, y) = ((i % 2) as f32 + 0.5, (i / 2) as f32 + 0.5);
+            assert!((sample(sampler, x, y) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn address_modes_handle_out_of_range_texels() {
+        let wrap = TextureSampler::new(TextureFilter::Nearest, TextureAddress::Wrap);
+        assert_eq!(sample(wrap, 2.5, 0.5), 0.0);
+        assert_eq!(sample(wrap, -0.5, 0.5), 1.0);
+
+        let border = TextureSampler::new(TextureFilter::Bilinear, TextureAddress::Border(-1.0));
+        assert_eq!(sample(border, 5.0, 5.0), -1.0);
+        assert!((sample(border, 0.0, 0.5) - (-0.5)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn far_away_and_non_finite_coordinates_are_addressed() {
+        for filter in [
+            TextureFilter::Nearest,
+            TextureFilter::Bilinear,
+            TextureFilter::Bicubic,
+        ] {
+            let clamp = TextureSampler::new(filter, TextureAddress::Clamp);
+            assert_eq!(sample(clamp, 1e30, 1e30), 3.0);
+            assert_eq!(sample(clamp, -1e30, -1e30), 0.0);
+            assert_eq!(sample(clamp, f32::INFINITY, 0.5), 0.0);
+
+            let wrap = TextureSampler::new(filter, TextureAddress::Wrap);
+            assert!(sample(wrap, 1e30, -1e30).is_finite());
+            assert_eq!(sample(wrap, f32::NAN, 0.5), 0.0);
+
+            let border = TextureSampler::new(filter, TextureAddress::Border(-1.0));
+            assert_eq!(sample(border, 1e30, 0.5), -1.0);
+            assert_eq!(sample(border, 0.5, f32::NEG_INFINITY), -1.0);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Per-placement attributes such as scale, rotation, and variant.
+//!
+//! Attribute values come either from fields tagged with attribute
+//! [`FieldSemantics`] in a kind's field graph, or from seeded random ranges declared on the
+//! [`crate::scatter::Kind`]. Both are deterministic for a given run seed.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::fieldgraph::spec::FieldSemantics;
+
+/// Attribute channel carried by a [`PlacementAttributes`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeChannel {
+    /// Uniform scale factor.
+    Scale,
+    /// Rotation in radians.
+    Rotation,
+    /// Variant index; values are floored and clamped at zero.
+    Variant,
+    /// Arbitrary named value.
+    Named(String),
+}
+
+impl AttributeChannel {
+    /// Returns the attribute channel assigned by the given field semantics, if any.
+    pub fn from_semantics(semantics: &FieldSemantics) -> Option<Self> {
+        match semantics {
+            FieldSemantics::Scale => Some(Self::Scale),
+            FieldSemantics::Rotation => Some(Self::Rotation),
+            FieldSemantics::Variant => Some(Self::Variant),
+            FieldSemantics::Attribute(name) => Some(Self::Named(name.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Seeded random range for an attribute channel, sampled uniformly in `[min, max)`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomAttribute {
+    /// Channel receiving the value.
+    pub channel: AttributeChannel,
+    /// Inclusive lower bound.
+    pub min: f32,
+    /// Exclusive upper bound.
+    pub max: f32,
+}
+
+impl RandomAttribute {
+    /// Maps a unit value in `[0, 1)` into this range.
+    #[inline]
+    pub fn lerp(&self, t: f32) -> f32 {
+        self.min + (self.max - self.min) * t
+    }
+}
+
+/// Attribute values resolved for a single placement.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlacementAttributes {
+    /// Uniform scale factor, if the kind defines one.
+    pub scale: Option<f32>,
+    /// Rotation in radians, if the kind defines one.
+    pub rotation: Option<f32>,
+    /// Variant index, if the kind defines one.
+    pub variant: Option<u32>,
+    /// Named values in insertion order.
+    pub named: Vec<(String, f32)>,
+}
+
+impl PlacementAttributes {
+    /// Returns `true` if no attribute is set.
+    pub fn is_empty(&self) -> bool {
+        self.scale.is_none()
+            && self.rotation.is_none()
+            && self.variant.is_none()
+            && self.named.is_empty()
+    }
+
+    /// Sets the value of a channel, replacing any previous value.
+    pub fn set(&mut self, channel: &AttributeChannel, value: f32) {
+        match channel {
+            AttributeChannel::Scale => self.scale = Some(value),
+            AttributeChannel::Rotation => self.rotation = Some(value),
+            AttributeChannel::Variant => self.variant = Some(value.max(0.0).floor() as u32),
+            AttributeChannel::Named(name) => match self.named.iter_mut().find(|(n, _)| n == name) {
+                Some((_, v)) => *v = value,
+                None => self.named.push((name.clone(), value)),
+            },
+        }
+    }
+
+    /// Returns a named value.
+    pub fn get(&self, name: &str) -> Option<f32> {
+        self.named
+            .iter()
+            .find_map(|(n, v)| (n == name).then_some(*v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_replaces_values_per_channel() {
+        let mut attributes = PlacementAttributes::default();
+        assert!(attributes.is_empty());
+
+        attributes.set(&AttributeChannel::Scale, 1.5);
+        attributes.set(&AttributeChannel::Variant, 2.7);
+        attributes.set(&AttributeChannel::Named("tint".into()), 0.2);
+        attributes.set(&AttributeChannel::Named("tint".into()), 0.4);
+
+        assert_eq!(attributes.scale, Some(1.5));
+        assert_eq!(attributes.variant, Some(2));
+        assert_eq!(attributes.get("tint"), Some(0.4));
+        assert_eq!(attributes.named.len(), 1);
+        assert_eq!(attributes.get("missing"), None);
+    }
+
+    #[test]
+    fn variant_is_clamped_at_zero() {
+        let mut attributes = PlacementAttributes::default();
+        attributes.set(&AttributeChannel::Variant, -3.0);
+        assert_eq!(attributes.variant, Some(0));
+    }
+}
@@ -180,7 +180,7 @@ pub fn seed_for_chunk(base_seed: u64, chunk: ChunkId) -> u64 {
 }
 
 #[inline]
-fn mix_u64(mut x: u64) -> u64 {
+pub(crate) fn mix_u64(mut x: u64) -> u64 {
     x ^= x >> 30;
     x = x.wrapping_mul(0xBF58476D1CE4E5B9);
     x ^= x >> 27;
@@ -0,0 +1,434 @@
+//! Debug rasters of fields and overlays over a whole domain.
+//!
+//! [`sample_field`] evaluates any field of a compiled [`FieldProgram`] at the pixel centers
+//! of a grid spanning the run domain, and [`sample_overlay`] does the same for an
+//! [`OverlayTexture`]. [`sample_kind_fields`] collects the gate and probability fields of a
+//! kind, which shows why a kind is not placed somewhere. The resulting [`DebugRaster`] can be
+//! written as PGM or raw `f32`, as PNG with the `png` feature, and as OpenEXR with the `exr`
+//! feature.
+//!
+//! Row 0 of a raster, and the first row of every written file, lies at the minimum Y of the
+//! domain. This matches how textures and overlays map their rows, so the raster of a field
+//! that reads a texture reproduces the texture image.
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use glam::Vec2;
+
+use crate::error::{Error, Result};
+use crate::fieldgraph::cache::FieldProgramCache;
+use crate::fieldgraph::compiler::CompileOptions;
+use crate::fieldgraph::program::FieldProgram;
+use crate::fieldgraph::runtime::FieldRuntime;
+use crate::fieldgraph::{ChunkId, TextureChannel, TextureRegistry};
+use crate::scatter::chunk::{
+    chunk_id_for_position_in_domain, domain_bounds, make_chunk_grid_in_domain,
+};
+use crate::scatter::overlay::OverlayTexture;
+use crate::scatter::runner::RunConfig;
+use crate::scatter::Kind;
+
+/// Values sampled at the pixel centers of a grid spanning a domain.
+#[derive(Clone, Debug)]
+pub struct DebugRaster {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Size of the sampled domain in world units.
+    pub domain_extent: Vec2,
+    /// World-space center of the sampled domain.
+    pub domain_center: Vec2,
+    /// Values in row-major order, starting at the minimum corner of the domain.
+    pub data: Vec<f32>,
+}
+
+impl DebugRaster {
+    /// Creates a raster by evaluating `f` at the center of every pixel.
+    pub fn from_fn(
+        domain_extent: Vec2,
+        domain_center: Vec2,
+        width: u32,
+        height: u32,
+        mut f: impl FnMut(Vec2) -> f32,
+    ) -> Self {
+        let mut raster = Self {
+            width,
+            height,
+            domain_extent,
+            domain_center,
+            data: Vec::with_capacity(width as usize * height as usize),
+        };
+        for y in 0..height {
+            for x in 0..width {
+                raster.data.push(f(raster.position(x, y)));
+            }
+        }
+        raster
+    }
+
+    /// Returns the world position of the center of a pixel.
+    pub fn position(&self, x: u32, y: u32) -> Vec2 {
+        let (min, max) = domain_bounds(self.domain_extent, self.domain_center);
+        let pixel = (max - min) / Vec2::new(self.width as f32, self.height as f32);
+        min + (Vec2::new(x as f32, y as f32) + 0.5) * pixel
+    }
+
+    /// Returns the value of a pixel.
+    pub fn get(&self, x: u32, y: u32) -> f32 {
+        self.data[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Returns the smallest and largest finite value, or `None` if there is none.
+    pub fn value_range(&self) -> Option<(f32, f32)> {
+        self.data
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold(None, |range, &v| match range {
+                None => Some((v, v)),
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+            })
+    }
+
+    /// Maps values from `[min, max]` to 8-bit gray levels, clamping values outside the range.
+    pub fn to_gray8(&self, min: f32, max: f32) -> Vec<u8> {
+        let scale = if max > min { 1.0 / (max - min) } else { 0.0 };
+        self.data
+            .iter()
+            .map(|v| (((v - min) * scale).clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect()
+    }
+}
+
+/// Evaluates a field of a compiled program over the domain of `config`.
+///
+/// Positions are evaluated chunk by chunk with the chunk grid of `config`, so baked fields
+/// such as distance transforms and blurs see the same rasters as a scatter run.
+pub fn sample_field(
+    program: Arc<FieldProgram>,
+    field: &str,
+    textures: &TextureRegistry,
+    config: &RunConfig,
+    width: u32,
+    height: u32,
+) -> Result<DebugRaster> {
+    if !program.nodes.contains_key(field) {
+        return Err(Error::UnknownField {
+            id: field.to_string(),
+        });
+    }
+    let mut raster = DebugRaster::from_fn(
+        config.domain_extent,
+        config.domain_center,
+        width,
+        height,
+        |_| 0.0,
+    );
+
+    let mut chunks: HashMap<ChunkId, (Vec<usize>, Vec<Vec2>)> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            let p = raster.position(x, y);
+            let chunk = chunk_id_for_position_in_domain(
+                p,
+                config.domain_extent,
+                config.domain_center,
+                config.chunk_extent,
+            );
+            let (indices, positions) = chunks.entry(chunk).or_default();
+            indices.push(y as usize * width as usize + x as usize);
+            positions.push(p);
+        }
+    }
+
+    let grid_halo = config
+        .grid_halo
+        .max(program.filter_halo(config.raster_cell_size));
+    let mut runtime = FieldRuntime::new(program, textures);
+    let mut values = Vec::new();
+    for (chunk, (indices, positions)) in chunks {
+        let grid = make_chunk_grid_in_domain(
+            config.domain_extent,
+            config.domain_center,
+            config.chunk_extent,
+            config.raster_cell_size,
+            grid_halo,
+            chunk,
+        );
+        values.clear();
+        values.resize(positions.len(), 0.0);
+        runtime.sample_batch(field, &positions, chunk, &grid, &mut values);
+        for (&index, &value) in indices.iter().zip(&values) {
+            raster.data[index] = value;
+        }
+    }
+    Ok(raster)
+}
+
+/// Samples a channel of an overlay over its own domain.
+pub fn sample_overlay(
+    overlay: &OverlayTexture,
+    channel: TextureChannel,
+    width: u32,
+    height: u32,
+) -> DebugRaster {
+    DebugRaster::from_fn(
+        overlay.domain_extent,
+        overlay.domain_center,
+        width,
+        height,
+        |p| overlay.sample_domain(channel, p),
+    )
+}
+
+/// Evaluates every gate and probability field of a kind, sorted by field id.
+///
+/// The kind is compiled through `cache` with the options of scatter runs.
+pub fn sample_kind_fields(
+    kind: &Kind,
+    cache: &FieldProgramCache,
+    textures: &TextureRegistry,
+    config: &RunConfig,
+    width: u32,
+    height: u32,
+) -> Result<Vec<(String, DebugRaster)>> {
+    let program = cache.get_or_compile(kind, &CompileOptions::default().with_optimize(true))?;
+    let mut fields: Vec<&String> = program
+        .nodes
+        .iter()
+        .filter(|(_, meta)| meta.is_gate() || meta.is_probability())
+        .map(|(id, _)| id)
+        .collect();
+    fields.sort();
+    fields
+        .into_iter()
+        .map(|field| {
+            let raster = sample_field(program.clone(), field, textures, config, width, height)?;
+            Ok((field.clone(), raster))
+        })
+        .collect()
+}
+
+/// Writes a binary 8-bit PGM (`P5`) image, mapping `[min, max]` to black and white.
+pub fn write_pgm<W: Write>(mut out: W, raster: &DebugRaster, min: f32, max: f32) -> io::Result<()> {
+    write!(out, "P5\n{} {}\n255\n", raster.width, raster.height)?;
+    out.write_all(&raster.to_gray8(min, max))
+}
+
+/// Writes the values as little-endian `f32` in row-major order, without a header.
+pub fn write_raw_f32<W: Write>(mut out: W, raster: &DebugRaster) -> io::Result<()> {
+    for value in &raster.data {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes an 8-bit grayscale PNG, mapping `[min, max]` to black and white.
+#[cfg(feature = "png")]
+pub fn write_png<W: Write>(out: W, raster: &DebugRaster, min: f32, max: f32) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(out, raster.width, raster.height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&raster.to_gray8(min, max))?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes an OpenEXR image with a single 32-bit float `Y` channel holding the raw values.
+#[cfg(feature = "exr")]
+pub fn write_exr<W: Write + io::Seek>(out: W, raster: &DebugRaster) -> io::Result<()> {
+    use exr::prelude::{Image, SpecificChannels, WritableImage};
+
+    let width = raster.width as usize;
+    let channels = SpecificChannels::build()
+        .with_channel("Y")
+        .with_pixel_fn(|p: exr::math::Vec2<usize>| (raster.data[p.y() * width + p.x()],));
+    Image::from_channels((width, raster.height as usize), channels)
+        .write()
+        .to_buffered(out)
+        .map_err(|error| match error {
+            exr::error::Error::Io(error) => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fieldgraph::spec::{FieldGraphSpec, FieldSemantics};
+    use crate::fieldgraph::{NodeSpec, TextureFilter, TextureSampler};
+    use crate::scatter::overlay::{build_overlay_from_placements, OverlayBrush};
+    use crate::scatter::runner::Placement;
+
+    fn config() -> RunConfig {
+        RunConfig::new(Vec2::new(40.0, 20.0))
+            .with_domain_center(Vec2::new(20.0, 0.0))
+            .with_chunk_extent(16.0)
+            .with_grid_halo(0)
+    }
+
+    fn gradient_kind() -> Kind {
+        let mut spec = FieldGraphSpec::default();
+        spec.add_with_semantics(
+            "allowed",
+            NodeSpec::expr("position_y() > 0"),
+            FieldSemantics::Gate,
+        );
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::expr("position_x() / 40"),
+            FieldSemantics::Probability,
+        );
+        Kind::new("grass", spec)
+    }
+
+    #[test]
+    fn fields_are_sampled_at_pixel_centers() {
+        let cache = FieldProgramCache::new();
+        let fields = sample_kind_fields(
+            &gradient_kind(),
+            &cache,
+            &TextureRegistry::new(),
+            &config(),
+            4,
+            2,
+        )
+        .unwrap();
+        let ids: Vec<&str> = fields.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["allowed", "probability"]);
+
+        let (_, gate) = &fields[0];
+        assert_eq!(gate.data, [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]);
+        let (_, probability) = &fields[1];
+        assert_eq!(probability.position(0, 0), Vec2::new(5.0, -5.0));
+        for x in 0..4 {
+            let expected = (5.0 + 10.0 * x as f32) / 40.0;
+            assert!((probability.get(x, 1) - expected).abs() < 1e-6);
+        }
+        assert_eq!(probability.value_range(), Some((0.125, 0.875)));
+    }
+
+    #[test]
+    fn unknown_fields_are_errors() {
+        let cache = FieldProgramCache::new();
+        let program = cache
+            .get_or_compile(&gradient_kind(), &CompileOptions::default())
+            .unwrap();
+        let err =
+            sample_field(program, "missing", &TextureRegistry::new(), &config(), 2, 2).unwrap_err();
+        assert!(matches!(err, Error::UnknownField { id } if id == "missing"));
+    }
+
+    #[test]
+    fn filters_have_no_seams_at_chunk_borders() {
+        let mut kind = Kind::new("grass", FieldGraphSpec::default());
+        kind.spec.add("x", NodeSpec::position_x());
+        kind.spec.add(
+            "blurred",
+            NodeSpec::blur("x".into(), crate::fieldgraph::BlurKernel::Box, 4.0),
+        );
+        kind.spec.add_with_semantics(
+            "probability",
+            NodeSpec::constant(1.0),
+            FieldSemantics::Probability,
+        );
+        let bicubic = TextureSampler::default().with_filter(TextureFilter::Bicubic);
+        for sampler in [TextureSampler::default(), bicubic] {
+            kind.spec.set_raster_sampler("blurred", sampler);
+            let program = FieldProgramCache::new()
+                .get_or_compile(&kind, &CompileOptions::default())
+                .unwrap();
+            let sample = |config: &RunConfig| {
+                sample_field(
+                    program.clone(),
+                    "blurred",
+                    &TextureRegistry::new(),
+                    config,
+                    40,
+                    20,
+                )
+                .unwrap()
+            };
+
+            // The grid halo is raised to the blur radius plus the reach of the raster
+            // sampler, so clamping at chunk borders does not show up within the chunks.
+            let reference = sample(&config().with_grid_halo(16));
+            assert_eq!(sample(&config()).data, reference.data);
+            assert!(reference.data.windows(2).take(39).all(|w| w[1] > w[0]));
+        }
+    }
+
+    #[test]
+    fn overlays_are_sampled_over_their_domain() {
+        let placement = Placement {
+            layer_id: "trees".into(),
+            kind_id: "tree".into(),
+            position: Vec2::new(-5.0, -5.0),
+            attributes: Default::default(),
+        };
+        let overlay = build_overlay_from_placements(
+            Vec2::splat(20.0),
+            Vec2::ZERO,
+            &[placement],
+            2,
+            2,
+            &OverlayBrush::new(0),
+            &[],
+        );
+        let raster = sample_overlay(&overlay, TextureChannel::R, 2, 2);
+        assert_eq!(raster.data, [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn pgm_and_raw_files_hold_all_pixels() {
+        let raster = DebugRaster::from_fn(Vec2::splat(2.0), Vec2::ZERO, 2, 1, |p| p.x);
+        assert_eq!(raster.data, [-0.5, 0.5]);
+
+        let mut pgm = Vec::new();
+        write_pgm(&mut pgm, &raster, -1.0, 1.0).unwrap();
+        assert_eq!(pgm, b"P5\n2 1\n255\n\x40\xbf");
+
+        let mut raw = Vec::new();
+        write_raw_f32(&mut raw, &raster).unwrap();
+        assert_eq!(raw[4..], 0.5f32.to_le_bytes());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_files_decode_to_gray_levels() {
+        let raster = DebugRaster::from_fn(Vec2::splat(2.0), Vec2::ZERO, 2, 1, |p| p.x);
+        let mut bytes = Vec::new();
+        write_png(&mut bytes, &raster, -0.5, 0.5).unwrap();
+        let mut reader = png::Decoder::new(io::Cursor::new(bytes))
+            .read_info()
+            .unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, [0, 255]);
+    }
+
+    #[cfg(feature = "exr")]
+    #[test]
+    fn exr_files_hold_raw_values() {
+        use exr::prelude::{ReadChannels, ReadLayers};
+
+        let raster = DebugRaster::from_fn(Vec2::splat(2.0), Vec2::ZERO, 2, 1, |p| p.x * 10.0);
+        let mut bytes = io::Cursor::new(Vec::new());
+        write_exr(&mut bytes, &raster).unwrap();
+        bytes.set_position(0);
+        let image = exr::prelude::read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .first_valid_layer()
+            .all_attributes()
+            .from_buffered(bytes)
+            .unwrap();
+        let channel = &image.layer_data.channel_data.list[0];
+        assert_eq!(channel.name, *"Y");
+        let values: Vec<f32> = channel.sample_data.values_as_f32().collect();
+        assert_eq!(values, [-5.0, 5.0]);
+    }
+}
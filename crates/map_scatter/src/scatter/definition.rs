@@ -0,0 +1,431 @@
+//! Serializable descriptions of scatter plans.
+//!
+//! [`Plan`] holds boxed [`PositionSampling`] trait objects and cannot be serialized. [`PlanDef`]
+//! describes the same plan as plain data, so plans can be stored in files and shared between
+//! tools. Enable the `serde` feature to serialize these types, and convert a [`PlanDef`] into a
+//! runnable [`Plan`] with [`From`].
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::fieldgraph::spec::{FieldGraphSpec, SubgraphSpec};
+use crate::sampling::{
+    BestCandidateSampling, ClusteredSampling, FibonacciLatticeSampling, HaltonSampling,
+    HexJitterGridSampling, JitterGridSampling, PoissonDiskSampling, PositionSampling,
+    StratifiedMultiJitterSampling, UniformRandomSampling,
+};
+use crate::scatter::attributes::RandomAttribute;
+use crate::scatter::overlay::BrushFalloff;
+use crate::scatter::plan::{Layer, Plan, SelectionStrategy};
+use crate::scatter::spacing::SpacingRules;
+use crate::scatter::{Kind, KindId};
+
+/// Serializable description of a [`Plan`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, Default)]
+pub struct PlanDef {
+    /// Ordered list of layer definitions in the plan.
+    pub layers: Vec<LayerDef>,
+    /// Subgraph definitions available to the field graphs of all kinds, keyed by name.
+    /// Definitions of the same name in a kind's own spec take precedence.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub subgraphs: HashMap<String, SubgraphSpec>,
+}
+
+/// Serializable description of a [`Layer`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug)]
+pub struct LayerDef {
+    /// Unique identifier for this layer.
+    pub id: String,
+    /// Kinds evaluated in this layer.
+    pub kinds: Vec<KindDef>,
+    /// Sampling strategy used for candidate generation.
+    pub sampling: SamplingDef,
+    /// Optional overlay mask size in pixels (width, height).
+    pub overlay_mask_size_px: Option<(u32, u32)>,
+    /// Optional overlay brush radius in pixels.
+    pub overlay_brush_radius_px: Option<i32>,
+    /// Weight profile of the overlay brush.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overlay_brush_falloff: BrushFalloff,
+    /// Overlay brush radius overrides in pixels, keyed by kind id.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overlay_kind_radius_px: HashMap<String, i32>,
+    /// Whether the overlay also exposes one mask per kind as `mask_<layer_id>:<kind_id>`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overlay_kind_masks: bool,
+    /// Strategy for selecting a kind when multiple are valid.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub selection_strategy: SelectionStrategy,
+    /// Minimum-distance constraints between placements of this layer.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spacing: SpacingDef,
+    /// Optional ids of layers this layer depends on; `None` depends on all earlier layers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dependencies: Option<Vec<String>>,
+    /// Minimum distance between candidates of neighbouring chunks in streamed worlds.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seam_distance: f32,
+}
+
+impl LayerDef {
+    /// Create a layer definition with the given kinds and sampling, and default options.
+    pub fn new(id: impl Into<String>, kinds: Vec<KindDef>, sampling: SamplingDef) -> Self {
+        Self {
+            id: id.into(),
+            kinds,
+            sampling,
+            overlay_mask_size_px: None,
+            overlay_brush_radius_px: None,
+            overlay_brush_falloff: BrushFalloff::Hard,
+            overlay_kind_radius_px: HashMap::new(),
+            overlay_kind_masks: false,
+            selection_strategy: SelectionStrategy::WeightedRandom,
+            spacing: SpacingDef::default(),
+            dependencies: None,
+            seam_distance: 0.0,
+        }
+    }
+}
+
+/// Serializable description of a [`Kind`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug)]
+pub struct KindDef {
+    /// Unique identifier for this kind.
+    pub id: String,
+    /// Field graph specification for this kind.
+    pub spec: FieldGraphSpec,
+    /// Seeded random ranges for per-placement attributes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub random_attributes: Vec<RandomAttribute>,
+}
+
+impl KindDef {
+    /// Create a kind definition without random attributes.
+    pub fn new(id: impl Into<String>, spec: FieldGraphSpec) -> Self {
+        Self {
+            id: id.into(),
+            spec,
+            random_attributes: Vec::new(),
+        }
+    }
+}
+
+/// Serializable description of [`SpacingRules`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, Default)]
+pub struct SpacingDef {
+    /// Footprint radius per kind in world units.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub footprint_radii: HashMap<KindId, f32>,
+    /// Explicit minimum distances between two kinds in world units.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_distances: Vec<(KindId, KindId, f32)>,
+}
+
+/// Parent placement of clustered sampling.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug)]
+pub enum ParentDef {
+    Count(
+        /// Number of parent centers to generate.
+        usize,
+    ),
+    Density(
+        /// Parent density per unit area.
+        f32,
+    ),
+}
+
+/// Serializable description of a [`PositionSampling`] implementation.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug)]
+pub enum SamplingDef {
+    UniformRandom {
+        /// Number of candidate points to generate.
+        count: usize,
+    },
+    Halton {
+        /// Number of candidate points to generate.
+        count: usize,
+        /// Bases for the 2D Halton sequence.
+        bases: (u32, u32),
+        /// Starting index in the Halton sequence.
+        start_index: u32,
+        /// Apply Cranley-Patterson rotation.
+        rotate: bool,
+    },
+    FibonacciLattice {
+        /// Number of candidate points to generate.
+        count: usize,
+        /// Apply Cranley-Patterson rotation.
+        rotate: bool,
+    },
+    StratifiedMultiJitter {
+        /// Number of candidate points to generate.
+        count: usize,
+        /// Apply Cranley-Patterson rotation.
+        rotate: bool,
+    },
+    BestCandidate {
+        /// Number of candidate points to generate.
+        count: usize,
+        /// Trials per point for best-candidate selection.
+        k: usize,
+    },
+    PoissonDisk {
+        /// Minimum distance between points in world units.
+        radius: f32,
+    },
+    JitterGrid {
+        /// Jitter amount in [0, 1].
+        jitter: f32,
+        /// Cell size for the base grid in world units.
+        cell_size: f32,
+    },
+    HexJitterGrid {
+        /// Jitter amount in [0, 1].
+        jitter: f32,
+        /// Base spacing along X in world units.
+        cell_size: f32,
+    },
+    ClusteredThomas {
+        /// Parent placement configuration.
+        parents: ParentDef,
+        /// Mean number of children per parent.
+        mean_children: f32,
+        /// Gaussian sigma for child offsets.
+        sigma: f32,
+        /// Clamp children inside the domain bounds.
+        clamp_inside: bool,
+    },
+    ClusteredNeymanScott {
+        /// Parent placement configuration.
+        parents: ParentDef,
+        /// Mean number of children per parent.
+        mean_children: f32,
+        /// Disk radius for child offsets.
+        radius: f32,
+        /// Clamp children inside the domain bounds.
+        clamp_inside: bool,
+    },
+}
+
+impl SamplingDef {
+    /// Create the sampling strategy this definition describes.
+    pub fn to_sampling(&self) -> Box<dyn PositionSampling> {
+        match self {
+            SamplingDef::UniformRandom { count } => {
+                Box::new(UniformRandomSampling { count: *count })
+            }
+            SamplingDef::Halton {
+                count,
+                bases,
+                start_index,
+                rotate,
+            } => Box::new(HaltonSampling {
+                count: *count,
+                bases: *bases,
+                start_index: *start_index,
+                rotate: *rotate,
+            }),
+            SamplingDef::FibonacciLattice { count, rotate } => Box::new(FibonacciLatticeSampling {
+                count: *count,
+                rotate: *rotate,
+            }),
+            SamplingDef::StratifiedMultiJitter { count, rotate } => {
+                Box::new(StratifiedMultiJitterSampling {
+                    count: *count,
+                    rotate: *rotate,
+                })
+            }
+            SamplingDef::BestCandidate { count, k } => Box::new(BestCandidateSampling {
+                count: *count,
+                k: *k,
+            }),
+            SamplingDef::PoissonDisk { radius } => {
+                Box::new(PoissonDiskSampling { radius: *radius })
+            }
+            SamplingDef::JitterGrid { jitter, cell_size } => {
+                Box::new(JitterGridSampling::new(*jitter, *cell_size))
+            }
+            SamplingDef::HexJitterGrid { jitter, cell_size } => {
+                Box::new(HexJitterGridSampling::new(*jitter, *cell_size))
+            }
+            SamplingDef::ClusteredThomas {
+                parents,
+                mean_children,
+                sigma,
+                clamp_inside,
+            } => {
+                let base = match parents {
+                    ParentDef::Count(n) => {
+                        ClusteredSampling::thomas_with_count(*n, *mean_children, *sigma)
+                    }
+                    ParentDef::Density(d) => {
+                        ClusteredSampling::thomas_with_density(*d, *mean_children, *sigma)
+                    }
+                };
+                Box::new(base.with_clamp_inside(*clamp_inside))
+            }
+            SamplingDef::ClusteredNeymanScott {
+                parents,
+                mean_children,
+                radius,
+                clamp_inside,
+            } => {
+                let base = match parents {
+                    ParentDef::Count(n) => {
+                        ClusteredSampling::neyman_scott_with_count(*n, *mean_children, *radius)
+                    }
+                    ParentDef::Density(d) => {
+                        ClusteredSampling::neyman_scott_with_density(*d, *mean_children, *radius)
+                    }
+                };
+                Box::new(base.with_clamp_inside(*clamp_inside))
+            }
+        }
+    }
+}
+
+impl From<&SpacingDef> for SpacingRules {
+    fn from(def: &SpacingDef) -> Self {
+        let mut rules = SpacingRules::new();
+        for (kind, radius) in &def.footprint_radii {
+            rules = rules.with_footprint(kind.clone(), *radius);
+        }
+        for (a, b, distance) in &def.min_distances {
+            rules = rules.with_min_distance(a.clone(), b.clone(), *distance);
+        }
+        rules
+    }
+}
+
+impl From<&KindDef> for Kind {
+    fn from(def: &KindDef) -> Self {
+        let mut kind = Kind::new(def.id.clone(), def.spec.clone());
+        kind.random_attributes = def.random_attributes.clone();
+        kind
+    }
+}
+
+impl From<KindDef> for Kind {
+    fn from(def: KindDef) -> Self {
+        let mut kind = Kind::new(def.id, def.spec);
+        kind.random_attributes = def.random_attributes;
+        kind
+    }
+}
+
+impl From<&LayerDef> for Layer {
+    fn from(def: &LayerDef) -> Self {
+        let kinds: Vec<Kind> = def.kinds.iter().map(Kind::from).collect();
+        let mut layer = Layer::new(def.id.clone(), kinds, def.sampling.to_sampling());
+
+        if let (Some(size), Some(radius)) = (def.overlay_mask_size_px, def.overlay_brush_radius_px)
+        {
+            layer = layer
+                .with_overlay(size, radius)
+                .with_overlay_falloff(def.overlay_brush_falloff)
+                .with_overlay_kind_masks(def.overlay_kind_masks);
+            for (kind_id, radius) in &def.overlay_kind_radius_px {
+                layer = layer.with_overlay_kind_radius(kind_id.clone(), *radius);
+            }
+        }
+
+        if let Some(dependencies) = &def.dependencies {
+            layer = layer.with_dependencies(dependencies.iter().cloned());
+        }
+
+        layer
+            .with_spacing((&def.spacing).into())
+            .with_seam_distance(def.seam_distance)
+            .with_selection_strategy(def.selection_strategy)
+    }
+}
+
+impl From<&PlanDef> for Plan {
+    fn from(def: &PlanDef) -> Self {
+        let mut layers: Vec<Layer> = def.layers.iter().map(Layer::from).collect();
+        for kind in layers.iter_mut().flat_map(|layer| layer.kinds.iter_mut()) {
+            for (name, subgraph) in &def.subgraphs {
+                kind.spec
+                    .subgraphs
+                    .entry(name.clone())
+                    .or_insert_with(|| subgraph.clone());
+            }
+        }
+        Plan::new().with_layers(layers)
+    }
+}
+
+impl From<PlanDef> for Plan {
+    fn from(def: PlanDef) -> Self {
+        (&def).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fieldgraph::NodeSpec;
+
+    #[test]
+    fn plan_def_converts_layer_options() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("prob", NodeSpec::subgraph("shared", Vec::new()));
+        let mut shared = SubgraphSpec::new(Vec::<String>::new(), "out");
+        shared.add("out", NodeSpec::constant(0.5));
+
+        let mut layer = LayerDef::new(
+            "trees",
+            vec![KindDef::new("oak", spec)],
+            SamplingDef::ClusteredThomas {
+                parents: ParentDef::Count(4),
+                mean_children: 3.0,
+                sigma: 2.0,
+                clamp_inside: true,
+            },
+        );
+        layer.overlay_mask_size_px = Some((64, 64));
+        layer.overlay_brush_radius_px = Some(2);
+        layer.overlay_kind_masks = true;
+        layer.selection_strategy = SelectionStrategy::HighestProbability;
+        layer.spacing.footprint_radii.insert("oak".into(), 1.5);
+        layer
+            .spacing
+            .min_distances
+            .push(("oak".into(), "oak".into(), 4.0));
+        layer.dependencies = Some(Vec::new());
+        layer.seam_distance = 2.0;
+
+        let mut def = PlanDef {
+            layers: vec![layer],
+            ..PlanDef::default()
+        };
+        def.subgraphs.insert("shared".into(), shared);
+
+        let plan = Plan::from(&def);
+        let layer = &plan.layers[0];
+        assert_eq!(layer.id, "trees");
+        assert!(layer.produces_overlay());
+        assert!(layer.overlay_kind_masks);
+        assert!(matches!(
+            layer.selection_strategy,
+            SelectionStrategy::HighestProbability
+        ));
+        assert_eq!(layer.spacing.min_distance("oak", "oak"), 4.0);
+        assert_eq!(layer.dependencies.as_deref(), Some(&[][..]));
+        assert_eq!(layer.seam_distance, 2.0);
+        assert!(layer.kinds[0].spec.subgraphs.contains_key("shared"));
+    }
+}
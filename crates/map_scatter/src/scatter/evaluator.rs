@@ -43,7 +43,7 @@ impl Evaluator {
     /// Creates a new evaluator by compiling the field graphs of the given kinds.
     pub fn new(kinds: &[Kind], cache: &FieldProgramCache) -> Result<Self> {
         let mut kind_info = HashMap::new();
-        let opts = CompileOptions::default();
+        let opts = CompileOptions::default().with_optimize(true);
 
         for kind in kinds {
             let program = cache.get_or_compile(kind, &opts)?;
@@ -129,44 +129,50 @@ impl Evaluator {
             }
         }
 
-        let mut all_results = Vec::with_capacity(positions.len());
+        let mut all_results: Vec<Vec<KindEvaluation>> = positions
+            .iter()
+            .map(|_| Vec::with_capacity(kinds.len()))
+            .collect();
+        let mut values = vec![0.0; positions.len()];
+        let mut allowed = vec![true; positions.len()];
 
-        for &pos in positions {
-            let mut results = Vec::with_capacity(kinds.len());
+        for kind in kinds {
+            let (Some(info), Some(rt)) = (self.kind_info.get(&kind.id), runtimes.get_mut(&kind.id))
+            else {
+                continue;
+            };
+
+            allowed.fill(true);
+            for field_id in &info.gate_fields {
+                rt.sample_batch(field_id, positions, chunk, grid, &mut values);
+                for (a, v) in allowed.iter_mut().zip(&values) {
+                    if *v <= 0.0 {
+                        *a = false;
+                    }
+                }
+            }
 
-            for kind in kinds {
-                if let Some(info) = self.kind_info.get(&kind.id) {
-                    if let Some(rt) = runtimes.get_mut(&kind.id) {
-                        let mut allowed = true;
-                        for field_id in &info.gate_fields {
-                            let value = rt.sample(field_id, pos, chunk, grid);
-                            if value <= 0.0 {
-                                allowed = false;
-                                break;
-                            }
-                        }
-
-                        let weight = if allowed {
-                            if let Some(prob_id) = &info.probability_field {
-                                rt.sample(prob_id, pos, chunk, grid).clamp(0.0, 1.0)
-                            } else {
-                                DEFAULT_PROBABILITY_WHEN_MISSING
-                            }
-                        } else {
-                            0.0
-                        };
-
-                        results.push(KindEvaluation {
-                            kind: kind.clone(),
-                            allowed,
-                            weight,
-                        });
+            match &info.probability_field {
+                Some(prob_id) => {
+                    rt.sample_batch(prob_id, positions, chunk, grid, &mut values);
+                    for v in &mut values {
+                        *v = v.clamp(0.0, 1.0);
                     }
                 }
+                None => values.fill(DEFAULT_PROBABILITY_WHEN_MISSING),
+            }
+
+            for ((results, allowed), weight) in all_results.iter_mut().zip(&allowed).zip(&values) {
+                results.push(KindEvaluation {
+                    kind: kind.clone(),
+                    allowed: *allowed,
+                    weight: if *allowed { *weight } else { 0.0 },
+                });
             }
+        }
 
+        for results in &mut all_results {
             results.sort_by(|a, b| b.weight.total_cmp(&a.weight));
-            all_results.push(results);
         }
 
         all_results
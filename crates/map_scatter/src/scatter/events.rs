@@ -22,6 +22,20 @@ pub enum ScatterEventKind {
     Warning,
 }
 
+impl ScatterEventKind {
+    /// All event kinds.
+    pub const ALL: [ScatterEventKind; 8] = [
+        ScatterEventKind::RunStarted,
+        ScatterEventKind::RunFinished,
+        ScatterEventKind::LayerStarted,
+        ScatterEventKind::LayerFinished,
+        ScatterEventKind::PositionEvaluated,
+        ScatterEventKind::PlacementMade,
+        ScatterEventKind::OverlayGenerated,
+        ScatterEventKind::Warning,
+    ];
+}
+
 /// Describes events emitted by scatter operations.
 #[non_exhaustive]
 #[derive(Debug, Clone)]
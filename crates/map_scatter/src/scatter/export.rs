@@ -0,0 +1,502 @@
+//! Placement export to CSV, JSON Lines, GeoJSON, and a compact binary format.
+//!
+//! Every record holds the layer id, kind id, position, and the attributes set on the
+//! placement. The binary format is versioned and can be read back with [`read_binary`].
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use glam::Vec2;
+use serde_json::{Map, Value};
+
+use crate::scatter::attributes::PlacementAttributes;
+use crate::scatter::runner::Placement;
+
+/// Magic bytes at the start of binary placement files.
+pub const BINARY_MAGIC: [u8; 4] = *b"MSPL";
+/// Version of the binary format written by [`write_binary`].
+pub const BINARY_VERSION: u32 = 1;
+
+const FLAG_SCALE: u8 = 1;
+const FLAG_ROTATION: u8 = 1 << 1;
+const FLAG_VARIANT: u8 = 1 << 2;
+
+/// Supported export formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row, see [`write_csv`].
+    Csv,
+    /// One JSON object per line, see [`write_json_lines`].
+    JsonLines,
+    /// GeoJSON feature collection of points, see [`write_geojson`].
+    GeoJson,
+    /// Versioned little-endian binary, see [`write_binary`].
+    Binary,
+}
+
+impl ExportFormat {
+    /// Returns the format conventionally stored under a file extension.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            "geojson" => Some(Self::GeoJson),
+            "bin" | "mspl" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Writes placements in the given format.
+pub fn write_placements<'a, W: Write>(
+    out: W,
+    format: ExportFormat,
+    placements: impl IntoIterator<Item = &'a Placement>,
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(out, placements),
+        ExportFormat::JsonLines => write_json_lines(out, placements),
+        ExportFormat::GeoJson => write_geojson(out, placements),
+        ExportFormat::Binary => write_binary(out, placements),
+    }
+}
+
+/// Writes CSV with the columns `layer,kind,x,y,scale,rotation,variant,attributes`.
+///
+/// Unset attributes are empty. Named attributes are `name=value` pairs separated by `;`.
+/// Fields containing commas, quotes, or line breaks are quoted.
+pub fn write_csv<'a, W: Write>(
+    mut out: W,
+    placements: impl IntoIterator<Item = &'a Placement>,
+) -> io::Result<()> {
+    writeln!(out, "layer,kind,x,y,scale,rotation,variant,attributes")?;
+    let optional = |v: Option<String>| v.unwrap_or_default();
+    for placement in placements {
+        let a = &placement.attributes;
+        let named: Vec<String> = a
+            .named
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            csv_field(&placement.layer_id),
+            csv_field(&placement.kind_id),
+            placement.position.x,
+            placement.position.y,
+            optional(a.scale.map(|v| v.to_string())),
+            optional(a.rotation.map(|v| v.to_string())),
+            optional(a.variant.map(|v| v.to_string())),
+            csv_field(&named.join(";")),
+        )?;
+    }
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes one JSON object per line with `layer`, `kind`, `x`, `y`, and the set `scale`,
+/// `rotation`, `variant`, and `attributes` (an object of named values).
+pub fn write_json_lines<'a, W: Write>(
+    mut out: W,
+    placements: impl IntoIterator<Item = &'a Placement>,
+) -> io::Result<()> {
+    for placement in placements {
+        let mut object = Map::new();
+        object.insert("layer".into(), placement.layer_id.as_str().into());
+        object.insert("kind".into(), placement.kind_id.as_str().into());
+        object.insert("x".into(), number(placement.position.x));
+        object.insert("y".into(), number(placement.position.y));
+        insert_attributes(&mut object, &placement.attributes);
+        serde_json::to_writer(&mut out, &object)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes a GeoJSON `FeatureCollection` with one `Point` feature per placement.
+///
+/// Coordinates are the domain position as `[x, y]`. Properties hold `layer`, `kind`, and
+/// the attributes as in [`write_json_lines`].
+pub fn write_geojson<'a, W: Write>(
+    mut out: W,
+    placements: impl IntoIterator<Item = &'a Placement>,
+) -> io::Result<()> {
+    write!(out, r#"{{"type":"FeatureCollection","features":["#)?;
+    for (i, placement) in placements.into_iter().enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        let mut properties = Map::new();
+        properties.insert("layer".into(), placement.layer_id.as_str().into());
+        properties.insert("kind".into(), placement.kind_id.as_str().into());
+        insert_attributes(&mut properties, &placement.attributes);
+        let feature = serde_json::json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [number(placement.position.x), number(placement.position.y)],
+            },
+            "properties": properties,
+        });
+        writeln!(out)?;
+        serde_json::to_writer(&mut out, &feature)?;
+    }
+    writeln!(out, "\n]}}")?;
+    Ok(())
+}
+
+fn insert_attributes(object: &mut Map<String, Value>, attributes: &PlacementAttributes) {
+    if let Some(scale) = attributes.scale {
+        object.insert("scale".into(), number(scale));
+    }
+    if let Some(rotation) = attributes.rotation {
+        object.insert("rotation".into(), number(rotation));
+    }
+    if let Some(variant) = attributes.variant {
+        object.insert("variant".into(), variant.into());
+    }
+    if !attributes.named.is_empty() {
+        let named = attributes
+            .named
+            .iter()
+            .map(|(name, value)| (name.clone(), number(*value)))
+            .collect();
+        object.insert("attributes".into(), Value::Object(named));
+    }
+}
+
+/// Converts through the shortest decimal form of the `f32`, so values such as `0.1` do
+/// not gain digits when widened. Non-finite values become `null`.
+fn number(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map_or(Value::Null, Value::Number)
+}
+
+/// Writes the versioned binary format.
+///
+/// All numbers are little-endian. The file starts with [`BINARY_MAGIC`] and a `u32`
+/// version, followed by a string table (`u32` count, then per string a `u32` byte length
+/// and UTF-8 bytes) holding every layer id, kind id, and attribute name once. Next comes a
+/// `u64` placement count and one record per placement:
+///
+/// - `u32` layer id and `u32` kind id, as indices into the string table
+/// - `f32` x and `f32` y
+/// - `u8` flags: bit 0 scale, bit 1 rotation, bit 2 variant
+/// - `f32` scale, `f32` rotation, and `u32` variant, each only if flagged
+/// - `u16` count of named attributes, then per attribute a `u32` name index and `f32` value
+pub fn write_binary<'a, W: Write>(
+    mut out: W,
+    placements: impl IntoIterator<Item = &'a Placement>,
+) -> io::Result<()> {
+    let placements: Vec<&Placement> = placements.into_iter().collect();
+    let mut strings: Vec<&str> = Vec::new();
+    let mut indices: HashMap<&str, u32> = HashMap::new();
+    let mut intern = |s: &'a str| {
+        *indices.entry(s).or_insert_with(|| {
+            strings.push(s);
+            strings.len() as u32 - 1
+        })
+    };
+    let records: Vec<(u32, u32, Vec<u32>)> = placements
+        .iter()
+        .map(|placement| {
+            let names = placement
+                .attributes
+                .named
+                .iter()
+                .map(|(name, _)| intern(name))
+                .collect();
+            (
+                intern(&placement.layer_id),
+                intern(&placement.kind_id),
+                names,
+            )
+        })
+        .collect();
+
+    out.write_all(&BINARY_MAGIC)?;
+    out.write_all(&BINARY_VERSION.to_le_bytes())?;
+    out.write_all(&len_u32(strings.len())?.to_le_bytes())?;
+    for s in &strings {
+        out.write_all(&len_u32(s.len())?.to_le_bytes())?;
+        out.write_all(s.as_bytes())?;
+    }
+    out.write_all(&(placements.len() as u64).to_le_bytes())?;
+    for (placement, (layer, kind, names)) in placements.iter().zip(&records) {
+        let a = &placement.attributes;
+        out.write_all(&layer.to_le_bytes())?;
+        out.write_all(&kind.to_le_bytes())?;
+        out.write_all(&placement.position.x.to_le_bytes())?;
+        out.write_all(&placement.position.y.to_le_bytes())?;
+        let mut flags = 0;
+        if a.scale.is_some() {
+            flags |= FLAG_SCALE;
+        }
+        if a.rotation.is_some() {
+            flags |= FLAG_ROTATION;
+        }
+        if a.variant.is_some() {
+            flags |= FLAG_VARIANT;
+        }
+        out.write_all(&[flags])?;
+        if let Some(scale) = a.scale {
+            out.write_all(&scale.to_le_bytes())?;
+        }
+        if let Some(rotation) = a.rotation {
+            out.write_all(&rotation.to_le_bytes())?;
+        }
+        if let Some(variant) = a.variant {
+            out.write_all(&variant.to_le_bytes())?;
+        }
+        let count = u16::try_from(names.len())
+            .map_err(|_| invalid_data("more than 65535 named attributes on a placement"))?;
+        out.write_all(&count.to_le_bytes())?;
+        for (name, (_, value)) in names.iter().zip(&a.named) {
+            out.write_all(&name.to_le_bytes())?;
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn len_u32(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| invalid_data("length does not fit in u32"))
+}
+
+/// Reads placements written by [`write_binary`].
+///
+/// Fails with [`io::ErrorKind::InvalidData`] on a wrong magic, an unsupported version, or
+/// out-of-range string indices, and with [`io::ErrorKind::UnexpectedEof`] on truncated
+/// input.
+pub fn read_binary<R: Read>(mut input: R) -> io::Result<Vec<Placement>> {
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic)?;
+    if magic != BINARY_MAGIC {
+        return Err(invalid_data("not a map_scatter placement file"));
+    }
+    let version = read_u32(&mut input)?;
+    if version != BINARY_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported placement file version {version}"
+        )));
+    }
+
+    let string_count = read_u32(&mut input)?;
+    let mut strings = Vec::new();
+    for _ in 0..string_count {
+        let len = read_u32(&mut input)? as u64;
+        let mut bytes = Vec::new();
+        input.by_ref().take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        strings.push(String::from_utf8(bytes).map_err(invalid_data)?);
+    }
+    let string = |index: u32| {
+        strings
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| invalid_data(format!("string index {index} out of range")))
+    };
+
+    let count = read_u64(&mut input)?;
+    let mut placements = Vec::new();
+    for _ in 0..count {
+        let layer_id = string(read_u32(&mut input)?)?;
+        let kind_id = string(read_u32(&mut input)?)?;
+        let position = Vec2::new(read_f32(&mut input)?, read_f32(&mut input)?);
+        let mut flags = [0];
+        input.read_exact(&mut flags)?;
+        let flags = flags[0];
+        let mut attributes = PlacementAttributes::default();
+        if flags & FLAG_SCALE != 0 {
+            attributes.scale = Some(read_f32(&mut input)?);
+        }
+        if flags & FLAG_ROTATION != 0 {
+            attributes.rotation = Some(read_f32(&mut input)?);
+        }
+        if flags & FLAG_VARIANT != 0 {
+            attributes.variant = Some(read_u32(&mut input)?);
+        }
+        let mut named_count = [0; 2];
+        input.read_exact(&mut named_count)?;
+        for _ in 0..u16::from_le_bytes(named_count) {
+            let name = string(read_u32(&mut input)?)?;
+            attributes.named.push((name, read_f32(&mut input)?));
+        }
+        placements.push(Placement {
+            layer_id,
+            kind_id,
+            position,
+            attributes,
+        });
+    }
+    Ok(placements)
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(input: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::scatter::attributes::AttributeChannel;
+
+    fn placement(layer_id: &str, kind_id: &str, x: f32, y: f32) -> Placement {
+        Placement {
+            layer_id: layer_id.into(),
+            kind_id: kind_id.into(),
+            position: Vec2::new(x, y),
+            attributes: PlacementAttributes::default(),
+        }
+    }
+
+    fn sample() -> Vec<Placement> {
+        let mut oak = placement("trees", "oak, old", 1.0, -2.5);
+        oak.attributes.set(&AttributeChannel::Scale, 1.5);
+        oak.attributes
+            .set(&AttributeChannel::Named("tint".into()), 0.1);
+        let mut rock = placement("rocks", "rock", 3.0, 4.0);
+        rock.attributes.set(&AttributeChannel::Variant, 2.0);
+        rock.attributes.set(&AttributeChannel::Rotation, 0.5);
+        vec![oak, rock]
+    }
+
+    fn written(format: ExportFormat) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_placements(&mut out, format, &sample()).unwrap();
+        out
+    }
+
+    #[test]
+    fn csv_quotes_fields_and_leaves_unset_attributes_empty() {
+        let csv = String::from_utf8(written(ExportFormat::Csv)).unwrap();
+        assert_eq!(
+            csv,
+            "layer,kind,x,y,scale,rotation,variant,attributes\n\
+             trees,\"oak, old\",1,-2.5,1.5,,,tint=0.1\n\
+             rocks,rock,3,4,,0.5,2,\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_hold_one_placement_per_line() {
+        let text = String::from_utf8(written(ExportFormat::JsonLines)).unwrap();
+        let lines: Vec<Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                json!({
+                    "layer": "trees", "kind": "oak, old", "x": 1.0, "y": -2.5,
+                    "scale": 1.5, "attributes": { "tint": 0.1 },
+                }),
+                json!({
+                    "layer": "rocks", "kind": "rock", "x": 3.0, "y": 4.0,
+                    "rotation": 0.5, "variant": 2,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn geojson_is_a_feature_collection_of_points() {
+        let geojson: Value = serde_json::from_slice(&written(ExportFormat::GeoJson)).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[1]["geometry"]["type"], "Point");
+        assert_eq!(features[1]["geometry"]["coordinates"], json!([3.0, 4.0]));
+        assert_eq!(features[1]["properties"]["layer"], "rocks");
+        assert_eq!(features[0]["properties"]["attributes"]["tint"], 0.1);
+
+        let mut empty = Vec::new();
+        write_geojson(&mut empty, []).unwrap();
+        let empty: Value = serde_json::from_slice(&empty).unwrap();
+        assert_eq!(empty["features"], json!([]));
+    }
+
+    #[test]
+    fn binary_round_trips() {
+        let bytes = written(ExportFormat::Binary);
+        assert_eq!(bytes[..4], BINARY_MAGIC);
+        let read = read_binary(bytes.as_slice()).unwrap();
+        let expected = sample();
+        assert_eq!(read.len(), expected.len());
+        for (read, expected) in read.iter().zip(&expected) {
+            assert_eq!(read.layer_id, expected.layer_id);
+            assert_eq!(read.kind_id, expected.kind_id);
+            assert_eq!(read.position, expected.position);
+            assert_eq!(read.attributes, expected.attributes);
+        }
+    }
+
+    #[test]
+    fn binary_reader_rejects_bad_input() {
+        let bytes = written(ExportFormat::Binary);
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 9;
+        let err = read_binary(wrong_version.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("version 9"));
+
+        let err = read_binary(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = read_binary(&b"PNG\0"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn formats_follow_file_extensions() {
+        assert_eq!(ExportFormat::from_extension("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(
+            ExportFormat::from_extension("ndjson"),
+            Some(ExportFormat::JsonLines)
+        );
+        assert_eq!(
+            ExportFormat::from_extension("geojson"),
+            Some(ExportFormat::GeoJson)
+        );
+        assert_eq!(
+            ExportFormat::from_extension("mspl"),
+            Some(ExportFormat::Binary)
+        );
+        assert_eq!(ExportFormat::from_extension("json"), None);
+    }
+}
@@ -1,13 +1,21 @@
 //! Scattering pipeline for evaluating spatial fields and placing kinds across a 2D domain.
 use crate::fieldgraph::spec::FieldGraphSpec;
+use crate::scatter::attributes::{AttributeChannel, RandomAttribute};
 
+pub mod attributes;
 pub mod chunk;
+pub mod debug;
+pub mod definition;
 pub mod evaluator;
 pub mod events;
+#[cfg(feature = "export")]
+pub mod export;
 pub mod overlay;
 pub mod plan;
 pub mod runner;
 pub mod selection;
+pub mod spacing;
+pub mod world;
 
 pub const DEFAULT_PROBABILITY_WHEN_MISSING: f32 = 0.1;
 
@@ -22,6 +30,8 @@ pub struct Kind {
     pub id: KindId,
     /// Field graph specification that drives placement rules.
     pub spec: FieldGraphSpec,
+    /// Seeded random ranges for per-placement attributes.
+    pub random_attributes: Vec<RandomAttribute>,
 }
 
 impl Kind {
@@ -29,6 +39,15 @@ impl Kind {
         Self {
             id: id.into(),
             spec,
+            random_attributes: Vec::new(),
         }
     }
+
+    /// Adds a seeded random range for an attribute channel, sampled uniformly in `[min, max)`.
+    /// Fields with attribute semantics take precedence over random ranges for the same channel.
+    pub fn with_random_attribute(mut self, channel: AttributeChannel, min: f32, max: f32) -> Self {
+        self.random_attributes
+            .push(RandomAttribute { channel, min, max });
+        self
+    }
 }
@@ -1,9 +1,21 @@
 //! Overlay textures and utilities for building mask textures from placements.
+//!
+//! Overlays store two channels: `R` holds coverage (the strongest brush weight covering a
+//! pixel) and `G` holds density (the sum of brush weights of all placements stamped on it).
+//! Overlays built with per-kind masks additionally carry one overlay per kind, registered as
+//! `<name>:<kind_id>` next to the layer overlay.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
 use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use crate::fieldgraph::{Texture, TextureChannel};
+use crate::fieldgraph::{Texture, TextureChannel, TextureRegistry, TextureSampler};
+use crate::scatter::runner::Placement;
+use crate::scatter::KindId;
 
-/// A 2D overlay texture with a single red channel.
+/// A 2D overlay texture with a coverage (red) and a density (green) channel.
 #[derive(Clone)]
 pub struct OverlayTexture {
     /// Size of the overlay domain in world units.
@@ -14,12 +26,16 @@ pub struct OverlayTexture {
     pub width: u32,
     /// Overlay texture height in pixels.
     pub height: u32,
-    /// Red channel data in row-major order.
+    /// Red channel (coverage) data in row-major order.
     pub data_r: Vec<f32>,
+    /// Green channel (density) data in row-major order; empty reads as zero.
+    pub data_g: Vec<f32>,
+    /// Overlays restricted to the placements of a single kind, keyed by kind id.
+    pub kind_masks: BTreeMap<String, Arc<OverlayTexture>>,
 }
 
 impl OverlayTexture {
-    /// Create a new [`OverlayTexture`].
+    /// Create a new [`OverlayTexture`] with an empty density channel and no per-kind masks.
     pub fn new(
         domain_extent: Vec2,
         domain_center: Vec2,
@@ -33,42 +49,73 @@ impl OverlayTexture {
             width,
             height,
             data_r,
+            data_g: Vec::new(),
+            kind_masks: BTreeMap::new(),
+        }
+    }
+
+    /// Set the density channel data in row-major order.
+    pub fn with_density(mut self, data_g: Vec<f32>) -> Self {
+        self.data_g = data_g;
+        self
+    }
+
+    /// Add an overlay restricted to the placements of a single kind.
+    pub fn with_kind_mask(mut self, kind_id: impl Into<String>, mask: OverlayTexture) -> Self {
+        self.kind_masks.insert(kind_id.into(), Arc::new(mask));
+        self
+    }
+
+    /// Register this overlay as `name` and each of its per-kind masks as `name:kind_id`.
+    pub fn register_into(self: &Arc<Self>, name: &str, registry: &mut TextureRegistry) {
+        registry.register_arc(name.to_string(), self.clone());
+        for (kind_id, mask) in &self.kind_masks {
+            registry.register_arc(format!("{name}:{kind_id}"), mask.clone());
         }
     }
 
     /// Sample the texture at a position in domain space.
     pub fn sample_domain(&self, channel: TextureChannel, p: Vec2) -> f32 {
+        self.sample_domain_with(channel, p, &TextureSampler::default())
+    }
+
+    /// Sample the texture at a position in domain space with a filter and addressing mode.
+    pub fn sample_domain_with(
+        &self,
+        channel: TextureChannel,
+        p: Vec2,
+        sampler: &TextureSampler,
+    ) -> f32 {
+        let data = match channel {
+            TextureChannel::A => return 1.0,
+            TextureChannel::B => return 0.0,
+            TextureChannel::R => &self.data_r,
+            TextureChannel::G => &self.data_g,
+        };
         if self.width == 0 || self.height == 0 {
-            return if matches!(channel, TextureChannel::A) {
-                1.0
-            } else {
-                0.0
-            };
+            return 0.0;
         }
 
         let local = p - self.domain_center;
         let u = if self.domain_extent.x != 0.0 {
-            ((local.x / self.domain_extent.x) + 0.5).clamp(0.0, 1.0)
+            (local.x / self.domain_extent.x) + 0.5
         } else {
             0.5
         };
         let v = if self.domain_extent.y != 0.0 {
-            ((local.y / self.domain_extent.y) + 0.5).clamp(0.0, 1.0)
+            (local.y / self.domain_extent.y) + 0.5
         } else {
             0.5
         };
 
-        let w1 = self.width - 1;
-        let h1 = self.height - 1;
-        let x = ((u * self.width as f32) as u32).min(w1);
-        let y = ((v * self.height as f32) as u32).min(h1);
-        let idx = (y as usize) * (self.width as usize) + (x as usize);
-
-        match channel {
-            TextureChannel::R => self.data_r.get(idx).copied().unwrap_or(0.0),
-            TextureChannel::A => 1.0,
-            _ => 0.0,
-        }
+        let width = self.width as usize;
+        sampler.sample_texels(
+            width,
+            self.height as usize,
+            u * self.width as f32,
+            v * self.height as f32,
+            |x, y| data.get(y * width + x).copied().unwrap_or(0.0),
+        )
     }
 }
 
@@ -76,6 +123,161 @@ impl Texture for OverlayTexture {
     fn sample(&self, channel: TextureChannel, p: Vec2) -> f32 {
         self.sample_domain(channel, p)
     }
+
+    fn sample_with(&self, channel: TextureChannel, p: Vec2, sampler: &TextureSampler) -> f32 {
+        self.sample_domain_with(channel, p, sampler)
+    }
+}
+
+/// Weight profile of an overlay brush over its radius.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrushFalloff {
+    /// Weight 1 within the radius.
+    #[default]
+    Hard,
+    /// Weight falling linearly from 1 at the center to 0 one pixel beyond the radius.
+    Linear,
+    /// Gaussian weight with a sigma of half the radius, truncated at the radius.
+    Gaussian,
+}
+
+impl BrushFalloff {
+    /// Weight of a pixel at `distance` pixels from the center of a brush of `radius` pixels.
+    pub fn weight(self, distance: f32, radius: f32) -> f32 {
+        if distance > radius {
+            return 0.0;
+        }
+        match self {
+            BrushFalloff::Hard => 1.0,
+            BrushFalloff::Linear => 1.0 - distance / (radius + 1.0),
+            BrushFalloff::Gaussian if radius > 0.0 => {
+                let sigma = radius * 0.5;
+                (-(distance * distance) / (2.0 * sigma * sigma)).exp()
+            }
+            BrushFalloff::Gaussian => 1.0,
+        }
+    }
+}
+
+/// Brush used to stamp placements into an overlay.
+#[derive(Clone, Debug, Default)]
+pub struct OverlayBrush {
+    /// Brush radius in pixels.
+    pub radius_px: i32,
+    /// Weight profile over the radius.
+    pub falloff: BrushFalloff,
+    /// Radius overrides in pixels, keyed by kind id.
+    pub kind_radius_px: HashMap<String, i32>,
+}
+
+impl OverlayBrush {
+    /// Create a new brush with a radius in pixels and a hard edge.
+    pub fn new(radius_px: i32) -> Self {
+        Self {
+            radius_px,
+            ..Default::default()
+        }
+    }
+
+    /// Set the weight profile over the radius.
+    pub fn with_falloff(mut self, falloff: BrushFalloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Override the radius in pixels for placements of one kind.
+    pub fn with_kind_radius(mut self, kind_id: impl Into<String>, radius_px: i32) -> Self {
+        self.kind_radius_px.insert(kind_id.into(), radius_px);
+        self
+    }
+
+    /// Radius in pixels used for placements of `kind_id`.
+    pub fn radius_for(&self, kind_id: &str) -> i32 {
+        self.kind_radius_px
+            .get(kind_id)
+            .copied()
+            .unwrap_or(self.radius_px)
+    }
+}
+
+/// Coverage and density channels accumulated by stamping brushes.
+struct OverlayCanvas {
+    domain_extent: Vec2,
+    domain_center: Vec2,
+    width: u32,
+    height: u32,
+    coverage: Vec<f32>,
+    density: Vec<f32>,
+}
+
+impl OverlayCanvas {
+    fn new(domain_extent: Vec2, domain_center: Vec2, width: u32, height: u32) -> Self {
+        let len = (width as usize) * (height as usize);
+        Self {
+            domain_extent,
+            domain_center,
+            width,
+            height,
+            coverage: vec![0.0; len],
+            density: vec![0.0; len],
+        }
+    }
+
+    fn stamp(&mut self, position: Vec2, radius_px: i32, falloff: BrushFalloff) {
+        if self.coverage.is_empty() || radius_px < 0 {
+            return;
+        }
+        let w_i = self.width as i32;
+        let h_i = self.height as i32;
+
+        let local = position - self.domain_center;
+        let u = if self.domain_extent.x != 0.0 {
+            ((local.x / self.domain_extent.x) + 0.5).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        let v = if self.domain_extent.y != 0.0 {
+            ((local.y / self.domain_extent.y) + 0.5).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+
+        let px = ((u * self.width as f32).floor() as i32).clamp(0, w_i - 1);
+        let py = ((v * self.height as f32).floor() as i32).clamp(0, h_i - 1);
+
+        let start_x = (px - radius_px).max(0);
+        let end_x = (px + radius_px).min(w_i - 1);
+        let start_y = (py - radius_px).max(0);
+        let end_y = (py + radius_px).min(h_i - 1);
+
+        let radius = radius_px as f32;
+        for sy in start_y..=end_y {
+            let row = (sy as usize) * (self.width as usize);
+            for sx in start_x..=end_x {
+                let dx = (sx - px) as f32;
+                let dy = (sy - py) as f32;
+                let weight = falloff.weight((dx * dx + dy * dy).sqrt(), radius);
+                if weight > 0.0 {
+                    let idx = row + sx as usize;
+                    self.coverage[idx] = self.coverage[idx].max(weight);
+                    self.density[idx] += weight;
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> OverlayTexture {
+        OverlayTexture::new(
+            self.domain_extent,
+            self.domain_center,
+            self.width,
+            self.height,
+            self.coverage,
+        )
+        .with_density(self.density)
+    }
 }
 
 pub fn build_overlay_mask_from_positions(
@@ -121,61 +323,52 @@ pub fn build_overlay_mask_from_positions_with_shape(
     height: u32,
     stamp_radius_px: i32,
 ) -> OverlayTexture {
-    let len = (width as usize) * (height as usize);
-    if len == 0 {
-        return OverlayTexture::new(domain_extent, domain_center, width, height, Vec::new());
-    }
-    let mut data = vec![0.0f32; len];
-    let w_i = width as i32;
-    let h_i = height as i32;
-
+    let mut canvas = OverlayCanvas::new(domain_extent, domain_center, width, height);
     for &position in positions {
-        let local = position - domain_center;
-        let u = if domain_extent.x != 0.0 {
-            ((local.x / domain_extent.x) + 0.5).clamp(0.0, 1.0)
-        } else {
-            0.5
-        };
-        let v = if domain_extent.y != 0.0 {
-            ((local.y / domain_extent.y) + 0.5).clamp(0.0, 1.0)
-        } else {
-            0.5
-        };
-
-        let px = ((u * width as f32).floor() as i32).clamp(0, w_i - 1);
-        let py = ((v * height as f32).floor() as i32).clamp(0, h_i - 1);
-
-        let start_x = (px - stamp_radius_px).max(0);
-        let end_x = (px + stamp_radius_px).min(w_i - 1);
-        let start_y = (py - stamp_radius_px).max(0);
-        let end_y = (py + stamp_radius_px).min(h_i - 1);
-
-        let r2 = stamp_radius_px * stamp_radius_px;
-
-        for sy in start_y..=end_y {
-            let row = (sy as usize) * (width as usize);
-            for sx in start_x..=end_x {
-                let idx = row + sx as usize;
-
-                let stamp = {
-                    let dx = sx - px;
-                    let dy = sy - py;
-                    dx * dx + dy * dy <= r2
-                };
+        canvas.stamp(position, stamp_radius_px, BrushFalloff::Hard);
+    }
+    canvas.finish()
+}
 
-                if stamp {
-                    data[idx] = 1.0;
-                }
-            }
+/// Builds an overlay from placements, stamping each with the brush radius of its kind.
+///
+/// The overlay carries one mask per id in `kind_masks`, empty for kinds without placements.
+pub fn build_overlay_from_placements(
+    domain_extent: Vec2,
+    domain_center: Vec2,
+    placements: &[Placement],
+    width: u32,
+    height: u32,
+    brush: &OverlayBrush,
+    kind_masks: &[KindId],
+) -> OverlayTexture {
+    let mut canvas = OverlayCanvas::new(domain_extent, domain_center, width, height);
+    let mut kinds: BTreeMap<&str, OverlayCanvas> = kind_masks
+        .iter()
+        .map(|kind_id| {
+            let kind = OverlayCanvas::new(domain_extent, domain_center, width, height);
+            (kind_id.as_str(), kind)
+        })
+        .collect();
+    for placement in placements {
+        let radius = brush.radius_for(&placement.kind_id);
+        canvas.stamp(placement.position, radius, brush.falloff);
+        if let Some(kind) = kinds.get_mut(placement.kind_id.as_str()) {
+            kind.stamp(placement.position, radius, brush.falloff);
         }
     }
 
-    OverlayTexture::new(domain_extent, domain_center, width, height, data)
+    kinds
+        .into_iter()
+        .fold(canvas.finish(), |overlay, (kind_id, canvas)| {
+            overlay.with_kind_mask(kind_id, canvas.finish())
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fieldgraph::{TextureAddress, TextureFilter};
 
     #[test]
     fn sample_domain_handles_empty_texture() {
@@ -211,6 +404,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sample_domain_with_filters_and_wraps() {
+        let overlay = OverlayTexture::new(
+            Vec2::new(2.0, 2.0),
+            Vec2::ZERO,
+            2,
+            2,
+            vec![0.0, 1.0, 0.0, 1.0],
+        );
+        let bilinear = TextureSampler::default().with_filter(TextureFilter::Bilinear);
+        assert!(
+            (overlay.sample_domain_with(TextureChannel::R, Vec2::ZERO, &bilinear) - 0.5).abs()
+                < 1e-6
+        );
+
+        let wrap = TextureSampler::new(TextureFilter::Nearest, TextureAddress::Wrap);
+        assert_eq!(
+            overlay.sample_domain_with(TextureChannel::R, Vec2::new(1.5, 0.0), &wrap),
+            0.0
+        );
+        assert_eq!(
+            overlay.sample_domain(TextureChannel::R, Vec2::new(1.5, 0.0)),
+            1.0
+        );
+    }
+
     #[test]
     fn build_overlay_mask_sets_pixels() {
         let texture = build_overlay_mask_from_positions_in_domain(
@@ -223,4 +442,59 @@ mod tests {
         );
         assert_eq!(texture.data_r.iter().filter(|v| **v > 0.0).count(), 1);
     }
+
+    #[test]
+    fn brush_falloff_weights() {
+        assert_eq!(BrushFalloff::Hard.weight(2.0, 2.0), 1.0);
+        assert_eq!(BrushFalloff::Hard.weight(2.5, 2.0), 0.0);
+        assert_eq!(BrushFalloff::Linear.weight(0.0, 3.0), 1.0);
+        assert_eq!(BrushFalloff::Linear.weight(2.0, 3.0), 0.5);
+        assert_eq!(BrushFalloff::Gaussian.weight(0.0, 0.0), 1.0);
+        assert!((BrushFalloff::Gaussian.weight(2.0, 2.0) - (-2.0f32).exp()).abs() < 1e-6);
+    }
+
+    fn placement(kind_id: &str, x: f32) -> Placement {
+        Placement {
+            layer_id: "layer".into(),
+            kind_id: kind_id.into(),
+            position: Vec2::new(x, 0.0),
+            attributes: Default::default(),
+        }
+    }
+
+    #[test]
+    fn placements_fill_density_and_kind_masks() {
+        let placements = [
+            placement("oak", -1.5),
+            placement("oak", -0.5),
+            placement("pine", 1.5),
+        ];
+        let brush = OverlayBrush::new(0)
+            .with_falloff(BrushFalloff::Linear)
+            .with_kind_radius("oak", 1);
+        let overlay = build_overlay_from_placements(
+            Vec2::new(4.0, 1.0),
+            Vec2::ZERO,
+            &placements,
+            4,
+            1,
+            &brush,
+            &["oak".to_string(), "birch".to_string()],
+        );
+        assert_eq!(overlay.data_r, vec![1.0, 1.0, 0.5, 1.0]);
+        assert_eq!(overlay.data_g, vec![1.5, 1.5, 0.5, 1.0]);
+        assert_eq!(overlay.kind_masks["oak"].data_r, vec![1.0, 1.0, 0.5, 0.0]);
+        assert_eq!(overlay.kind_masks["birch"].data_g, vec![0.0; 4]);
+        assert!(!overlay.kind_masks.contains_key("pine"));
+
+        let overlay = Arc::new(overlay);
+        let mut registry = TextureRegistry::new();
+        overlay.register_into("mask_trees", &mut registry);
+        assert!(registry.contains("mask_trees"));
+        assert!(registry.contains("mask_trees:oak"));
+        assert_eq!(
+            registry.sample("mask_trees", TextureChannel::G, Vec2::new(-1.5, 0.0)),
+            1.5
+        );
+    }
 }
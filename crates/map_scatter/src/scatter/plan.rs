@@ -1,10 +1,23 @@
 //! Planning module for defining scatter layers and plans.
+use std::collections::{BTreeSet, HashMap};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::fieldgraph::spec::FieldGraphSpec;
+use crate::fieldgraph::{subgraph, NodeSpec, TextureRegistry};
 use crate::sampling::PositionSampling;
+use crate::scatter::overlay::{BrushFalloff, OverlayBrush};
+use crate::scatter::spacing::SpacingRules;
 use crate::scatter::Kind;
 
 /// Strategy for selecting a kind when multiple are placeable at a candidate position.
-#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum SelectionStrategy {
+    #[default]
     WeightedRandom,
     HighestProbability,
 }
@@ -22,8 +35,22 @@ pub struct Layer {
     pub overlay_mask_size_px: Option<(u32, u32)>,
     /// Optional overlay brush radius in pixels.
     pub overlay_brush_radius_px: Option<i32>,
+    /// Weight profile of the overlay brush.
+    pub overlay_brush_falloff: BrushFalloff,
+    /// Overlay brush radius overrides in pixels, keyed by kind id.
+    pub overlay_kind_radius_px: HashMap<String, i32>,
+    /// Whether the overlay also exposes one mask per kind as `mask_<layer_id>:<kind_id>`.
+    pub overlay_kind_masks: bool,
     /// Strategy for selecting a kind.
     pub selection_strategy: SelectionStrategy,
+    /// Minimum-distance constraints between placements of this layer.
+    pub spacing: SpacingRules,
+    /// Ids of layers whose outputs this layer depends on.
+    /// `None` depends on every earlier layer in the plan.
+    pub dependencies: Option<Vec<String>>,
+    /// Minimum distance between candidates generated by neighbouring world chunks, used by
+    /// [`crate::scatter::world::run_world_chunk`]. Usually the sampler's minimum distance.
+    pub seam_distance: f32,
 }
 
 impl Layer {
@@ -39,7 +66,13 @@ impl Layer {
             sampling,
             overlay_mask_size_px: None,
             overlay_brush_radius_px: None,
+            overlay_brush_falloff: BrushFalloff::Hard,
+            overlay_kind_radius_px: HashMap::new(),
+            overlay_kind_masks: false,
             selection_strategy: SelectionStrategy::WeightedRandom,
+            spacing: SpacingRules::default(),
+            dependencies: None,
+            seam_distance: 0.0,
         }
     }
 
@@ -59,11 +92,68 @@ impl Layer {
         self
     }
 
+    /// Set the weight profile of the overlay brush.
+    pub fn with_overlay_falloff(mut self, falloff: BrushFalloff) -> Self {
+        self.overlay_brush_falloff = falloff;
+        self
+    }
+
+    /// Override the overlay brush radius in pixels for placements of one kind.
+    pub fn with_overlay_kind_radius(mut self, kind_id: impl Into<String>, radius: i32) -> Self {
+        self.overlay_kind_radius_px.insert(kind_id.into(), radius);
+        self
+    }
+
+    /// Expose one overlay mask per kind as `mask_<layer_id>:<kind_id>`.
+    pub fn with_overlay_kind_masks(mut self, enabled: bool) -> Self {
+        self.overlay_kind_masks = enabled;
+        self
+    }
+
     /// Set the selection strategy for this layer.
     pub fn with_selection_strategy(mut self, strategy: SelectionStrategy) -> Self {
         self.selection_strategy = strategy;
         self
     }
+
+    /// Set the spacing constraints between placements of this layer.
+    pub fn with_spacing(mut self, spacing: SpacingRules) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Declare the layers this layer depends on. Only overlays of these layers (and their
+    /// own dependencies) are visible to it, and layers without a dependency path between
+    /// them may run concurrently. An empty list makes the layer independent.
+    pub fn with_dependencies<I, S>(mut self, dependencies: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.dependencies = Some(dependencies.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the minimum distance between candidates of neighbouring world chunks.
+    pub fn with_seam_distance(mut self, seam_distance: f32) -> Self {
+        self.seam_distance = seam_distance;
+        self
+    }
+
+    /// Returns `true` if this layer produces an overlay mask.
+    pub fn produces_overlay(&self) -> bool {
+        self.overlay_mask_size_px.is_some() && self.overlay_brush_radius_px.is_some()
+    }
+
+    /// Returns the brush used to stamp placements into the overlay, if the layer has one.
+    pub fn overlay_brush(&self) -> Option<OverlayBrush> {
+        let radius_px = self.overlay_brush_radius_px?;
+        Some(OverlayBrush {
+            radius_px,
+            falloff: self.overlay_brush_falloff,
+            kind_radius_px: self.overlay_kind_radius_px.clone(),
+        })
+    }
 }
 
 /// A scatter plan composed of one or more [`Layer`]s.
@@ -91,15 +181,210 @@ impl Plan {
         self.layers.extend(layers);
         self
     }
+
+    /// Validates the plan against the given base textures.
+    pub fn validate(&self, base_textures: &TextureRegistry) -> Result<()> {
+        self.schedule(base_textures).map(|_| ())
+    }
+
+    /// Validates the layer dependency graph and resolves the execution order.
+    ///
+    /// Fails with [`Error::Compile`] for duplicate layer ids, unknown dependencies,
+    /// dependency cycles, and overlay textures (`mask_<layer_id>` or
+    /// `mask_<layer_id>:<kind_id>`) that are not provided by a dependency of the reading layer
+    /// or by `base_textures`.
+    pub fn schedule(&self, base_textures: &TextureRegistry) -> Result<PlanSchedule> {
+        let mut index_of: HashMap<&str, usize> = HashMap::with_capacity(self.layers.len());
+        for (index, layer) in self.layers.iter().enumerate() {
+            if index_of.insert(layer.id.as_str(), index).is_some() {
+                return Err(Error::Compile(format!("duplicate layer id '{}'", layer.id)));
+            }
+        }
+
+        let mut dependencies: Vec<Vec<usize>> = Vec::with_capacity(self.layers.len());
+        for (index, layer) in self.layers.iter().enumerate() {
+            let deps = match &layer.dependencies {
+                None => (0..index).collect(),
+                Some(ids) => ids
+                    .iter()
+                    .map(|id| {
+                        index_of.get(id.as_str()).copied().ok_or_else(|| {
+                            Error::Compile(format!(
+                                "layer '{}' depends on unknown layer '{id}'",
+                                layer.id
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            };
+            dependencies.push(deps);
+        }
+
+        // Kahn's algorithm by levels: each wave only depends on earlier waves.
+        let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.layers.len()];
+        for (index, deps) in dependencies.iter().enumerate() {
+            for &dep in deps {
+                dependents[dep].push(index);
+            }
+        }
+        let mut waves: Vec<Vec<usize>> = Vec::new();
+        let mut ready: Vec<usize> = (0..self.layers.len())
+            .filter(|&i| remaining[i] == 0)
+            .collect();
+        let mut scheduled = 0;
+        while !ready.is_empty() {
+            let mut next = Vec::new();
+            for &index in &ready {
+                for &dependent in &dependents[index] {
+                    remaining[dependent] -= 1;
+                    if remaining[dependent] == 0 {
+                        next.push(dependent);
+                    }
+                }
+            }
+            scheduled += ready.len();
+            next.sort_unstable();
+            waves.push(std::mem::replace(&mut ready, next));
+        }
+        if scheduled < self.layers.len() {
+            let cyclic: Vec<&str> = (0..self.layers.len())
+                .filter(|&i| remaining[i] > 0)
+                .map(|i| self.layers[i].id.as_str())
+                .collect();
+            return Err(Error::Compile(format!(
+                "layer dependency cycle involving: {}",
+                cyclic.join(", ")
+            )));
+        }
+
+        let mut ancestors: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); self.layers.len()];
+        for &index in waves.iter().flatten() {
+            let mut set = BTreeSet::new();
+            for &dep in &dependencies[index] {
+                set.insert(dep);
+                set.extend(ancestors[dep].iter().copied());
+            }
+            ancestors[index] = set;
+        }
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            self.validate_overlay_references(layer, &ancestors[index], &index_of, base_textures)?;
+        }
+
+        Ok(PlanSchedule {
+            waves,
+            ancestors: ancestors
+                .into_iter()
+                .map(|set| set.into_iter().collect())
+                .collect(),
+        })
+    }
+
+    fn validate_overlay_references(
+        &self,
+        layer: &Layer,
+        ancestors: &BTreeSet<usize>,
+        index_of: &HashMap<&str, usize>,
+        base_textures: &TextureRegistry,
+    ) -> Result<()> {
+        for kind in &layer.kinds {
+            let inlined;
+            let spec = if subgraph::needs_inlining(&kind.spec) {
+                inlined = subgraph::inline(&kind.spec, None)?;
+                &inlined
+            } else {
+                &kind.spec
+            };
+            let mut field_ids: Vec<&String> = spec.nodes.keys().collect();
+            field_ids.sort_unstable();
+            for field_id in field_ids {
+                let NodeSpec::Texture { params } = &spec.nodes[field_id] else {
+                    continue;
+                };
+                let texture_id = params.texture_id.as_str();
+                let Some(mask_id) = texture_id.strip_prefix("mask_") else {
+                    continue;
+                };
+                if base_textures.contains(texture_id) {
+                    continue;
+                }
+                let (source_id, kind_id) = match mask_id.split_once(':') {
+                    Some((source_id, kind_id)) if !index_of.contains_key(mask_id) => {
+                        (source_id, Some(kind_id))
+                    }
+                    _ => (mask_id, None),
+                };
+                let field_id = origin_field(&kind.spec, field_id);
+                let context = format!("layer '{}' kind '{}' field '{field_id}'", layer.id, kind.id);
+                let Some(&source) = index_of.get(source_id) else {
+                    return Err(Error::Compile(format!(
+                        "{context} reads overlay '{texture_id}' but no layer '{source_id}' exists"
+                    )));
+                };
+                if !ancestors.contains(&source) {
+                    return Err(Error::Compile(format!(
+                        "{context} reads overlay '{texture_id}' without depending on layer '{source_id}'"
+                    )));
+                }
+                if !self.layers[source].produces_overlay() {
+                    return Err(Error::Compile(format!(
+                        "{context} reads overlay '{texture_id}' but layer '{source_id}' produces no overlay"
+                    )));
+                }
+                let Some(kind_id) = kind_id else {
+                    continue;
+                };
+                let source_layer = &self.layers[source];
+                if !source_layer.overlay_kind_masks {
+                    return Err(Error::Compile(format!(
+                        "{context} reads overlay '{texture_id}' but layer '{source_id}' produces no per-kind masks"
+                    )));
+                }
+                if !source_layer.kinds.iter().any(|k| k.id == kind_id) {
+                    return Err(Error::Compile(format!(
+                        "{context} reads overlay '{texture_id}' but layer '{source_id}' has no kind '{kind_id}'"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the top-level field of `spec` that `field_id` is or was expanded from.
+fn origin_field<'a>(spec: &'a FieldGraphSpec, field_id: &'a str) -> &'a str {
+    spec.nodes
+        .keys()
+        .map(String::as_str)
+        .filter(|id| {
+            field_id
+                .strip_prefix(id)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        .max_by_key(|id| id.len())
+        .unwrap_or(field_id)
+}
+
+/// Execution order of a validated [`Plan`], see [`Plan::schedule`].
+#[derive(Clone, Debug)]
+pub struct PlanSchedule {
+    /// Groups of layer indices; layers within a group are independent of each other and
+    /// only depend on layers of earlier groups.
+    pub waves: Vec<Vec<usize>>,
+    /// For every layer, the sorted indices of all layers it transitively depends on.
+    pub ancestors: Vec<Vec<usize>>,
 }
 
 #[cfg(test)]
 mod tests {
+    use glam::Vec2;
     use mint::Vector2;
     use rand::RngCore;
 
     use super::*;
-    use crate::prelude::FieldGraphSpec;
+    use crate::prelude::{FieldGraphSpec, TextureChannel};
+    use crate::scatter::overlay::OverlayTexture;
 
     fn kind(id: &str) -> Kind {
         Kind::new(id, FieldGraphSpec::default())
@@ -130,6 +415,114 @@ mod tests {
         assert_eq!(plan.layers.len(), 1);
     }
 
+    fn layer(id: &str) -> Layer {
+        Layer::new_with(id, vec![kind("a")], JitterSampling)
+    }
+
+    fn reading(id: &str, overlay: &str) -> Layer {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("mask", NodeSpec::texture(overlay, TextureChannel::R));
+        Layer::new_with(id, vec![Kind::new("reader", spec)], JitterSampling)
+    }
+
+    fn compile_error(plan: &Plan) -> String {
+        match plan.schedule(&TextureRegistry::new()) {
+            Err(Error::Compile(message)) => message,
+            other => panic!("expected compile error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn layers_without_dependencies_run_in_plan_order() {
+        let plan = Plan::new().with_layers(vec![layer("a"), layer("b"), layer("c")]);
+        let schedule = plan.schedule(&TextureRegistry::new()).expect("schedule");
+        assert_eq!(schedule.waves, vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(schedule.ancestors[2], vec![0, 1]);
+    }
+
+    #[test]
+    fn independent_layers_share_a_wave() {
+        let plan = Plan::new().with_layers(vec![
+            layer("trees").with_overlay((8, 8), 1),
+            layer("rocks").with_dependencies(Vec::<String>::new()),
+            reading("grass", "mask_trees").with_dependencies(["trees", "rocks"]),
+        ]);
+        let schedule = plan.schedule(&TextureRegistry::new()).expect("schedule");
+        assert_eq!(schedule.waves, vec![vec![0, 1], vec![2]]);
+    }
+
+    #[test]
+    fn invalid_dependencies_are_compile_errors() {
+        let unknown = Plan::new().with_layer(layer("a").with_dependencies(["missing"]));
+        assert!(compile_error(&unknown).contains("unknown layer 'missing'"));
+
+        let cycle = Plan::new().with_layers(vec![
+            layer("a").with_dependencies(["b"]),
+            layer("b").with_dependencies(["a"]),
+        ]);
+        assert!(compile_error(&cycle).contains("cycle"));
+
+        let duplicate = Plan::new().with_layers(vec![layer("a"), layer("a")]);
+        assert!(compile_error(&duplicate).contains("duplicate layer id"));
+    }
+
+    #[test]
+    fn overlay_references_must_be_provided_by_dependencies() {
+        let missing = Plan::new().with_layer(reading("grass", "mask_trees"));
+        assert!(compile_error(&missing).contains("no layer 'trees'"));
+
+        let undeclared = Plan::new().with_layers(vec![
+            layer("trees").with_overlay((8, 8), 1),
+            reading("grass", "mask_trees").with_dependencies(Vec::<String>::new()),
+        ]);
+        assert!(compile_error(&undeclared).contains("without depending on layer 'trees'"));
+
+        let no_overlay =
+            Plan::new().with_layers(vec![layer("trees"), reading("grass", "mask_trees")]);
+        assert!(compile_error(&no_overlay).contains("produces no overlay"));
+
+        let mut textures = TextureRegistry::new();
+        textures.register(
+            "mask_trees",
+            OverlayTexture::new(Vec2::ONE, Vec2::ZERO, 1, 1, vec![1.0]),
+        );
+        assert!(missing.validate(&textures).is_ok());
+    }
+
+    #[test]
+    fn overlay_references_in_expressions_are_validated() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("prob", NodeSpec::expr("1 - mask_trees.r"));
+        let grass = Layer::new_with("grass", vec![Kind::new("reader", spec)], JitterSampling);
+        let plan = Plan::new().with_layer(grass);
+        assert!(compile_error(&plan).contains("field 'prob' reads overlay 'mask_trees'"));
+    }
+
+    #[test]
+    fn kind_mask_references_need_kind_masks_and_kind() {
+        let trees = || {
+            Layer::new_with("trees", vec![kind("oak"), kind("pine")], JitterSampling)
+                .with_overlay((8, 8), 1)
+        };
+        let valid = Plan::new().with_layers(vec![
+            trees().with_overlay_kind_masks(true),
+            reading("grass", "mask_trees:oak"),
+        ]);
+        assert!(valid.validate(&TextureRegistry::new()).is_ok());
+
+        let disabled = Plan::new().with_layers(vec![trees(), reading("grass", "mask_trees:oak")]);
+        assert!(compile_error(&disabled).contains("produces no per-kind masks"));
+
+        let unknown_kind = Plan::new().with_layers(vec![
+            trees().with_overlay_kind_masks(true),
+            reading("grass", "mask_trees:birch"),
+        ]);
+        assert!(compile_error(&unknown_kind).contains("has no kind 'birch'"));
+
+        let unknown_layer = Plan::new().with_layer(reading("grass", "mask_shrubs:oak"));
+        assert!(compile_error(&unknown_layer).contains("no layer 'shrubs'"));
+    }
+
     struct JitterSampling;
 
     impl PositionSampling for JitterSampling {
@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use glam::Vec2;
-use rand::RngCore;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 use tracing::{info, warn};
 
 use crate::error::{Error, Result};
@@ -11,23 +12,38 @@ use crate::fieldgraph::cache::FieldProgramCache;
 use crate::fieldgraph::compiler::CompileOptions;
 use crate::fieldgraph::program::FieldProgram;
 use crate::fieldgraph::runtime::FieldRuntime;
-use crate::fieldgraph::{ChunkId, TextureRegistry};
+use crate::fieldgraph::shared::SharedProgram;
+use crate::fieldgraph::{ChunkGrid, ChunkId, TextureRegistry};
+use crate::scatter::attributes::{AttributeChannel, PlacementAttributes};
 use crate::scatter::evaluator::KindEvaluation;
-use crate::scatter::events::{EventSink, OverlaySummary, ScatterEvent, ScatterEventKind};
-use crate::scatter::overlay::{build_overlay_mask_from_positions_in_domain, OverlayTexture};
+use crate::scatter::events::{
+    EventSink, KindEvaluationLite, OverlaySummary, ScatterEvent, ScatterEventKind,
+};
+use crate::scatter::overlay::{build_overlay_from_placements, OverlayTexture};
 use crate::scatter::plan::{Layer, Plan, SelectionStrategy};
 use crate::scatter::selection::{pick_highest_probability, pick_weighted_random};
+use crate::scatter::spacing::SpacingIndex;
 use crate::scatter::{chunk, Kind, KindId, DEFAULT_PROBABILITY_WHEN_MISSING};
 
-type KindInfo = (Kind, Arc<FieldProgram>, Vec<String>, Option<String>);
+/// Compiled kind with the ids of the fields it is evaluated through in its layer's program.
+pub(crate) struct KindInfo {
+    pub(crate) kind: Kind,
+    pub(crate) gates: Vec<String>,
+    pub(crate) probability: Option<String>,
+    pub(crate) attributes: Vec<(AttributeChannel, String)>,
+}
 
 /// Represents a placed instance of a kind at a specific position.
 #[derive(Debug, Clone)]
 pub struct Placement {
+    /// Id of the layer that produced this placement.
+    pub layer_id: String,
     /// Kind identifier for this placement.
     pub kind_id: KindId,
     /// World/domain position of the placement.
     pub position: Vec2,
+    /// Attribute values resolved for this placement.
+    pub attributes: PlacementAttributes,
 }
 
 /// Configuration for running a scatter plan.
@@ -42,7 +58,9 @@ pub struct RunConfig {
     pub chunk_extent: f32,
     /// Raster cell size used for field sampling in world units.
     pub raster_cell_size: f32,
-    /// Extra halo cells around each chunk for filters and EDT.
+    /// Extra halo cells around each chunk for filters and EDT. Runs raise it to cover the
+    /// kernels of blur, morphology, convolution, and derivative nodes, see
+    /// [`FieldProgram::filter_halo`].
     pub grid_halo: usize,
 }
 
@@ -321,8 +339,6 @@ fn run_layer_with_events_internal<R: RngCore>(
     let domain_extent = ctx.config.domain_extent;
     let domain_center = ctx.config.domain_center;
 
-    let opts = CompileOptions::default();
-    let mut kind_info: Vec<KindInfo> = Vec::new();
     // Emit layer start
     if sink.wants(ScatterEventKind::LayerStarted) {
         sink.send(ScatterEvent::LayerStarted {
@@ -333,58 +349,8 @@ fn run_layer_with_events_internal<R: RngCore>(
             overlay_brush_radius_px: layer.overlay_brush_radius_px,
         });
     }
-    for k in &layer.kinds {
-        match cache.get_or_compile(k, &opts) {
-            Ok(program) => {
-                let gates: Vec<String> = program
-                    .nodes
-                    .iter()
-                    .filter_map(|(id, meta)| {
-                        if meta.is_gate() {
-                            Some(id.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                let prob_ids: Vec<String> = program
-                    .nodes
-                    .iter()
-                    .filter(|(_, m)| m.is_probability())
-                    .map(|(id, _)| id.clone())
-                    .collect();
-                if prob_ids.len() > 1 {
-                    warn!(
-                        "Kind '{}' has multiple Probability fields; using the first: {:?}.",
-                        k.id, prob_ids
-                    );
-                    if sink.wants(ScatterEventKind::Warning) {
-                        sink.send(ScatterEvent::Warning {
-                            context: format!("layer:{} kind:{}", layer.id, k.id),
-                            message: format!(
-                                "Multiple Probability fields found; using first: {prob_ids:?}"
-                            ),
-                        });
-                    }
-                }
-                let prob: Option<String> = prob_ids.into_iter().next();
-                kind_info.push((k.clone(), program.clone(), gates, prob));
-            }
-            Err(e) => {
-                warn!(
-                    "Failed to compile kind '{}' in layer '{}': {}.",
-                    k.id, layer.id, e
-                );
-                if sink.wants(ScatterEventKind::Warning) {
-                    sink.send(ScatterEvent::Warning {
-                        context: format!("layer:{} kind:{}", layer.id, k.id),
-                        message: format!("Failed to compile kind: {e}"),
-                    });
-                }
-            }
-        }
-    }
-    if kind_info.is_empty() {
+    let layer_kinds = compile_kinds(layer, cache, sink);
+    if layer_kinds.kinds.is_empty() {
         return (
             RunResult {
                 placements: Vec::new(),
@@ -406,117 +372,124 @@ fn run_layer_with_events_internal<R: RngCore>(
         TextureRegistry::with_capacity(ctx.base_textures.len() + ctx.overlays.len());
     layer_textures.extend_from(ctx.base_textures);
     for (name, ov) in ctx.overlays.iter() {
-        layer_textures.register_arc(name.clone(), ov.clone());
+        ov.register_into(name, &mut layer_textures);
     }
 
-    let mut runtime_cache: std::collections::HashMap<(KindId, ChunkId), FieldRuntime> =
-        std::collections::HashMap::new();
-
-    let mut placed: Vec<Placement> = Vec::new();
-    for position in positions.iter().copied() {
-        let (chunk, grid) = chunk::chunk_id_and_grid_for_position_in_domain(
-            position,
+    // Candidates are partitioned by chunk and every chunk draws from its own RNG stream,
+    // so placements do not depend on the order (or thread) in which chunks are evaluated.
+    let layer_seed = rng.next_u64();
+    let mut chunk_positions: HashMap<ChunkId, Vec<usize>> = HashMap::new();
+    for (index, position) in positions.iter().enumerate() {
+        let chunk = chunk::chunk_id_for_position_in_domain(
+            *position,
             domain_extent,
             domain_center,
             ctx.config.chunk_extent,
-            ctx.config.raster_cell_size,
-            ctx.config.grid_halo,
         );
+        chunk_positions.entry(chunk).or_default().push(index);
+    }
+    let mut chunk_jobs: Vec<(ChunkId, Vec<usize>)> = chunk_positions.into_iter().collect();
+    chunk_jobs.sort_unstable_by_key(|(chunk, _)| (chunk.0, chunk.1));
+
+    // Spacing couples neighbouring candidates, so fields are still evaluated per chunk but
+    // kinds are then selected sequentially in candidate order against a spatial hash.
+    let kind_ids: Vec<KindId> = layer_kinds
+        .kinds
+        .iter()
+        .map(|info| info.kind.id.clone())
+        .collect();
+    let mut spacing = SpacingIndex::new(&layer.spacing, &kind_ids);
+
+    let chunk_ctx = ChunkEvalContext {
+        layer,
+        layer_index,
+        config: ctx.config,
+        textures: &layer_textures,
+        kind_info: &layer_kinds.kinds,
+        program: &layer_kinds.program,
+        grid_halo: ctx
+            .config
+            .grid_halo
+            .max(layer_kinds.program.filter_halo(ctx.config.raster_cell_size)),
+        positions: &positions,
+        layer_seed,
+        emit_evaluations: sink.wants(ScatterEventKind::PositionEvaluated),
+        defer_selection: spacing.is_some(),
+    };
 
-        let mut results: Vec<KindEvaluation> = Vec::with_capacity(kind_info.len());
-        for (kind, program, gate_fields, probability_field) in &kind_info {
-            let key = (kind.id.clone(), chunk);
-            if !runtime_cache.contains_key(&key) {
-                runtime_cache.insert(
-                    key.clone(),
-                    FieldRuntime::new(program.clone(), &layer_textures),
-                );
-            }
-            let rt = runtime_cache
-                .get_mut(&key)
-                .expect("runtime exists after insertion");
-
-            let mut allowed = true;
-            for field_id in gate_fields {
-                let value = rt.sample(field_id, position, chunk, &grid);
-                if value <= 0.0 {
-                    allowed = false;
-                    break;
-                }
-            }
-
-            let weight = if allowed {
-                if let Some(prob_id) = probability_field {
-                    rt.sample(prob_id, position, chunk, &grid).clamp(0.0, 1.0)
-                } else {
-                    DEFAULT_PROBABILITY_WHEN_MISSING
-                }
-            } else {
-                0.0
-            };
-
-            results.push(KindEvaluation {
-                kind: kind.clone(),
-                allowed,
-                weight,
-            });
+    let outcomes: Vec<ChunkOutcome> = map_chunks(&chunk_jobs, |(chunk, indices)| {
+        evaluate_chunk(&chunk_ctx, *chunk, indices)
+    });
+
+    let mut evaluations: Vec<(usize, ScatterEvent)> = Vec::new();
+    let mut indexed_placements: Vec<(usize, Placement)> = Vec::new();
+    let mut deferred: Vec<(usize, Vec<(bool, f32)>)> = Vec::new();
+    for outcome in outcomes {
+        evaluations.extend(outcome.evaluations);
+        indexed_placements.extend(outcome.placements);
+        deferred.extend(outcome.deferred);
+    }
+    if let Some(spacing) = spacing.as_mut() {
+        let selections = select_with_spacing(&chunk_ctx, deferred, spacing);
+        indexed_placements.extend(resolve_placements(&chunk_ctx, selections));
+    }
+    evaluations.sort_unstable_by_key(|(index, _)| *index);
+    indexed_placements.sort_unstable_by_key(|(index, _)| *index);
+
+    let mut evaluations = evaluations.into_iter().peekable();
+    let mut placed: Vec<Placement> = Vec::with_capacity(indexed_placements.len());
+    for (index, placement) in indexed_placements {
+        while let Some((_, event)) = evaluations.next_if(|(i, _)| *i <= index) {
+            sink.send(event);
         }
-
-        let max_weight = results
-            .iter()
-            .filter(|r| r.allowed)
-            .map(|r| r.weight)
-            .fold(0.0f32, f32::max);
-
-        if sink.wants(ScatterEventKind::PositionEvaluated) {
-            sink.send(ScatterEvent::PositionEvaluated {
+        if sink.wants(ScatterEventKind::PlacementMade) {
+            sink.send(ScatterEvent::PlacementMade {
                 layer_index,
                 layer_id: layer.id.clone(),
-                position,
-                evaluations: results
-                    .iter()
-                    .map(|r| {
-                        crate::scatter::events::KindEvaluationLite::new(
-                            r.kind.id.clone(),
-                            r.allowed,
-                            r.weight,
-                        )
-                    })
-                    .collect(),
-                max_weight,
+                placement: placement.clone(),
             });
         }
-
-        let rand01 = crate::sampling::rand01(rng);
-        if max_weight > 0.0 && rand01 < max_weight {
-            let selected = match layer.selection_strategy {
-                SelectionStrategy::WeightedRandom => pick_weighted_random(&results, rng),
-                SelectionStrategy::HighestProbability => pick_highest_probability(&results),
-            };
-            if let Some(selected_kind) = selected {
-                let placement = Placement {
-                    kind_id: selected_kind.id.clone(),
-                    position,
-                };
-                if sink.wants(ScatterEventKind::PlacementMade) {
-                    sink.send(ScatterEvent::PlacementMade {
-                        layer_index,
-                        layer_id: layer.id.clone(),
-                        placement: placement.clone(),
-                    });
-                }
-                placed.push(placement);
-            }
-        }
+        placed.push(placement);
+    }
+    for (_, event) in evaluations {
+        sink.send(event);
     }
 
     let eval_count = positions.len();
     let placed_count = placed.len();
     let rejected = eval_count.saturating_sub(placed_count);
 
-    let overlay_opt = if let (Some((mask_w, mask_h)), Some(brush_radius)) =
-        (layer.overlay_mask_size_px, layer.overlay_brush_radius_px)
-    {
+    let overlay_opt = build_layer_overlay(
+        layer,
+        layer_index,
+        domain_extent,
+        domain_center,
+        layer.overlay_mask_size_px,
+        &placed,
+        sink,
+    );
+
+    (
+        RunResult {
+            placements: placed,
+            positions_evaluated: eval_count,
+            positions_rejected: rejected,
+        },
+        overlay_opt,
+    )
+}
+
+/// Builds the overlay mask of a layer from its placements, if the layer has one.
+pub(crate) fn build_layer_overlay(
+    layer: &Layer,
+    layer_index: usize,
+    domain_extent: Vec2,
+    domain_center: Vec2,
+    mask_size_px: Option<(u32, u32)>,
+    placements: &[Placement],
+    sink: &mut dyn EventSink,
+) -> Option<(String, Arc<OverlayTexture>)> {
+    if let (Some((mask_w, mask_h)), Some(brush)) = (mask_size_px, layer.overlay_brush()) {
         if mask_w == 0 || mask_h == 0 {
             warn!(
                 "Layer '{}' overlay size is zero; skipping overlay.",
@@ -529,7 +502,7 @@ fn run_layer_with_events_internal<R: RngCore>(
                 });
             }
             None
-        } else if brush_radius < 0 {
+        } else if brush.radius_px < 0 {
             warn!(
                 "Layer '{}' overlay brush radius < 0; skipping overlay.",
                 layer.id
@@ -542,13 +515,19 @@ fn run_layer_with_events_internal<R: RngCore>(
             }
             None
         } else {
-            let mask = build_overlay_mask_from_positions_in_domain(
+            let kind_masks: Vec<KindId> = if layer.overlay_kind_masks {
+                layer.kinds.iter().map(|kind| kind.id.clone()).collect()
+            } else {
+                Vec::new()
+            };
+            let mask = build_overlay_from_placements(
                 domain_extent,
                 domain_center,
-                &placed.iter().map(|p| p.position).collect::<Vec<_>>(),
+                placements,
                 mask_w,
                 mask_h,
-                brush_radius,
+                &brush,
+                &kind_masks,
             );
             let mask_name = format!("mask_{}", layer.id);
             let summary = OverlaySummary {
@@ -566,16 +545,451 @@ fn run_layer_with_events_internal<R: RngCore>(
         }
     } else {
         None
+    }
+}
+
+/// Compiled kinds of a layer and the program they share.
+pub(crate) struct LayerKinds {
+    pub(crate) kinds: Vec<KindInfo>,
+    /// Programs of all kinds merged, so fields they have in common are evaluated once.
+    pub(crate) program: Arc<FieldProgram>,
+}
+
+/// Compiles the kinds of a layer into a shared program, reporting kinds that fail to compile
+/// as warnings.
+pub(crate) fn compile_kinds(
+    layer: &Layer,
+    cache: &FieldProgramCache,
+    sink: &mut dyn EventSink,
+) -> LayerKinds {
+    let opts = CompileOptions::default().with_optimize(true);
+    let mut kind_info: Vec<KindInfo> = Vec::new();
+    let mut programs: Vec<Arc<FieldProgram>> = Vec::new();
+    for k in &layer.kinds {
+        match cache.get_or_compile(k, &opts) {
+            Ok(program) => {
+                let gates: Vec<String> = program
+                    .nodes
+                    .iter()
+                    .filter_map(|(id, meta)| {
+                        if meta.is_gate() {
+                            Some(id.clone())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                let prob_ids: Vec<String> = program
+                    .nodes
+                    .iter()
+                    .filter(|(_, m)| m.is_probability())
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                if prob_ids.len() > 1 {
+                    warn!(
+                        "Kind '{}' has multiple Probability fields; using the first: {:?}.",
+                        k.id, prob_ids
+                    );
+                    if sink.wants(ScatterEventKind::Warning) {
+                        sink.send(ScatterEvent::Warning {
+                            context: format!("layer:{} kind:{}", layer.id, k.id),
+                            message: format!(
+                                "Multiple Probability fields found; using first: {prob_ids:?}"
+                            ),
+                        });
+                    }
+                }
+                let prob: Option<String> = prob_ids.into_iter().next();
+                let mut attributes: Vec<(AttributeChannel, String)> = program
+                    .nodes
+                    .iter()
+                    .filter_map(|(id, m)| {
+                        let channel = AttributeChannel::from_semantics(m.semantics.as_ref()?)?;
+                        Some((channel, id.clone()))
+                    })
+                    .collect();
+                attributes.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+                programs.push(program.clone());
+                kind_info.push(KindInfo {
+                    kind: k.clone(),
+                    gates,
+                    probability: prob,
+                    attributes,
+                });
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to compile kind '{}' in layer '{}': {}.",
+                    k.id, layer.id, e
+                );
+                if sink.wants(ScatterEventKind::Warning) {
+                    sink.send(ScatterEvent::Warning {
+                        context: format!("layer:{} kind:{}", layer.id, k.id),
+                        message: format!("Failed to compile kind: {e}"),
+                    });
+                }
+            }
+        }
+    }
+
+    let shared = SharedProgram::new(&programs);
+    for (index, info) in kind_info.iter_mut().enumerate() {
+        let ids = info
+            .gates
+            .iter_mut()
+            .chain(&mut info.probability)
+            .chain(info.attributes.iter_mut().map(|(_, id)| id));
+        for id in ids {
+            *id = shared
+                .field(index, id)
+                .expect("kind field is shared")
+                .clone();
+        }
+    }
+    LayerKinds {
+        kinds: kind_info,
+        program: shared.program().clone(),
+    }
+}
+
+struct ChunkEvalContext<'a> {
+    layer: &'a Layer,
+    layer_index: usize,
+    config: &'a RunConfig,
+    textures: &'a TextureRegistry,
+    kind_info: &'a [KindInfo],
+    program: &'a Arc<FieldProgram>,
+    /// Grid halo covering the filter kernels of `program`.
+    grid_halo: usize,
+    positions: &'a [Vec2],
+    layer_seed: u64,
+    emit_evaluations: bool,
+    defer_selection: bool,
+}
+
+impl ChunkEvalContext<'_> {
+    fn chunk_of(&self, position: Vec2) -> ChunkId {
+        chunk::chunk_id_for_position_in_domain(
+            position,
+            self.config.domain_extent,
+            self.config.domain_center,
+            self.config.chunk_extent,
+        )
+    }
+
+    fn chunk_grid(&self, chunk: ChunkId) -> ChunkGrid {
+        chunk::make_chunk_grid_in_domain(
+            self.config.domain_extent,
+            self.config.domain_center,
+            self.config.chunk_extent,
+            self.config.raster_cell_size,
+            self.grid_halo,
+            chunk,
+        )
+    }
+
+    /// Resolves the attributes of a placement of kind `slot` at candidate `index`.
+    ///
+    /// Random ranges draw from a stream seeded by the layer seed and candidate index, so they
+    /// never shift the selection RNG.
+    fn placement_attributes(
+        &self,
+        slot: usize,
+        index: usize,
+        position: Vec2,
+        rt: &mut FieldRuntime,
+        chunk: ChunkId,
+        grid: &ChunkGrid,
+    ) -> PlacementAttributes {
+        let seed = self.layer_seed ^ (index as u64).wrapping_mul(0xD1B5_4A32_D192_ED03);
+        resolve_attributes(&self.kind_info[slot], seed, position, rt, chunk, grid)
+    }
+}
+
+/// Resolves placement attributes: random ranges are drawn from `seed`, then attribute fields
+/// override random values.
+pub(crate) fn resolve_attributes(
+    info: &KindInfo,
+    seed: u64,
+    position: Vec2,
+    rt: &mut FieldRuntime,
+    chunk: ChunkId,
+    grid: &ChunkGrid,
+) -> PlacementAttributes {
+    let mut attributes = PlacementAttributes::default();
+
+    if !info.kind.random_attributes.is_empty() {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for random in &info.kind.random_attributes {
+            let t = crate::sampling::rand01(&mut rng);
+            attributes.set(&random.channel, random.lerp(t));
+        }
+    }
+
+    for (channel, field_id) in &info.attributes {
+        let value = rt.sample(field_id, position, chunk, grid);
+        attributes.set(channel, value);
+    }
+
+    attributes
+}
+
+struct ChunkOutcome {
+    placements: Vec<(usize, Placement)>,
+    evaluations: Vec<(usize, ScatterEvent)>,
+    /// Per-kind `(allowed, weight)` of candidates whose selection depends on spacing.
+    deferred: Vec<(usize, Vec<(bool, f32)>)>,
+}
+
+fn evaluate_chunk(ctx: &ChunkEvalContext<'_>, chunk: ChunkId, indices: &[usize]) -> ChunkOutcome {
+    let grid = ctx.chunk_grid(chunk);
+    let mut rng = StdRng::seed_from_u64(chunk::seed_for_chunk(ctx.layer_seed, chunk));
+    let mut rt = FieldRuntime::new(ctx.program.clone(), ctx.textures);
+
+    let mut outcome = ChunkOutcome {
+        placements: Vec::new(),
+        evaluations: Vec::new(),
+        deferred: Vec::new(),
     };
 
-    (
-        RunResult {
-            placements: placed,
-            positions_evaluated: eval_count,
-            positions_rejected: rejected,
-        },
-        overlay_opt,
-    )
+    let positions: Vec<Vec2> = indices.iter().map(|&i| ctx.positions[i]).collect();
+    let evaluations = evaluate_kinds(ctx.kind_info, &mut rt, &positions, chunk, &grid);
+
+    for ((&index, &position), results) in indices.iter().zip(&positions).zip(evaluations) {
+        let max_weight = results
+            .iter()
+            .filter(|r| r.allowed)
+            .map(|r| r.weight)
+            .fold(0.0f32, f32::max);
+
+        if ctx.emit_evaluations {
+            outcome.evaluations.push((
+                index,
+                ScatterEvent::PositionEvaluated {
+                    layer_index: ctx.layer_index,
+                    layer_id: ctx.layer.id.clone(),
+                    position,
+                    evaluations: results
+                        .iter()
+                        .map(|r| KindEvaluationLite::new(r.kind.id.clone(), r.allowed, r.weight))
+                        .collect(),
+                    max_weight,
+                },
+            ));
+        }
+
+        if ctx.defer_selection {
+            outcome.deferred.push((
+                index,
+                results.iter().map(|r| (r.allowed, r.weight)).collect(),
+            ));
+            continue;
+        }
+
+        if let Some(selected) = select_kind(ctx.layer.selection_strategy, &results, &mut rng) {
+            let attributes =
+                ctx.placement_attributes(selected, index, position, &mut rt, chunk, &grid);
+            outcome.placements.push((
+                index,
+                Placement {
+                    layer_id: ctx.layer.id.clone(),
+                    kind_id: results[selected].kind.id.clone(),
+                    position,
+                    attributes,
+                },
+            ));
+        }
+    }
+
+    outcome
+}
+
+/// Evaluates the gate and probability fields of every kind at each of `positions`.
+///
+/// All fields are sampled in one batch through the layer's shared program, so a field used by
+/// several kinds is computed once.
+pub(crate) fn evaluate_kinds(
+    kind_info: &[KindInfo],
+    rt: &mut FieldRuntime,
+    positions: &[Vec2],
+    chunk: ChunkId,
+    grid: &ChunkGrid,
+) -> Vec<Vec<KindEvaluation>> {
+    let n = positions.len();
+    let mut fields: Vec<&str> = Vec::new();
+    let mut row_of: HashMap<&str, usize> = HashMap::new();
+    for info in kind_info {
+        for field_id in info.gates.iter().chain(&info.probability) {
+            row_of.entry(field_id).or_insert_with(|| {
+                fields.push(field_id);
+                fields.len() - 1
+            });
+        }
+    }
+    let mut sampled = vec![0.0; fields.len() * n];
+    rt.sample_batch_many(&fields, positions, chunk, grid, &mut sampled);
+    let row = |field_id: &str| &sampled[row_of[field_id] * n..][..n];
+
+    let mut results: Vec<Vec<KindEvaluation>> = positions
+        .iter()
+        .map(|_| Vec::with_capacity(kind_info.len()))
+        .collect();
+    let mut values = vec![0.0; n];
+    let mut allowed = vec![true; n];
+
+    for info in kind_info {
+        allowed.fill(true);
+        for field_id in &info.gates {
+            for (a, v) in allowed.iter_mut().zip(row(field_id)) {
+                if *v <= 0.0 {
+                    *a = false;
+                }
+            }
+        }
+
+        match &info.probability {
+            Some(prob_id) => {
+                for (v, p) in values.iter_mut().zip(row(prob_id)) {
+                    *v = p.clamp(0.0, 1.0);
+                }
+            }
+            None => values.fill(DEFAULT_PROBABILITY_WHEN_MISSING),
+        }
+
+        for ((result, allowed), weight) in results.iter_mut().zip(&allowed).zip(&values) {
+            result.push(KindEvaluation {
+                kind: info.kind.clone(),
+                allowed: *allowed,
+                weight: if *allowed { *weight } else { 0.0 },
+            });
+        }
+    }
+    results
+}
+
+/// Draws the acceptance roll for a candidate and picks a kind; returns its index in `results`.
+pub(crate) fn select_kind(
+    strategy: SelectionStrategy,
+    results: &[KindEvaluation],
+    rng: &mut StdRng,
+) -> Option<usize> {
+    let max_weight = results
+        .iter()
+        .filter(|r| r.allowed)
+        .map(|r| r.weight)
+        .fold(0.0f32, f32::max);
+
+    let rand01 = crate::sampling::rand01(rng);
+    if max_weight <= 0.0 || rand01 >= max_weight {
+        return None;
+    }
+
+    let selected = match strategy {
+        SelectionStrategy::WeightedRandom => pick_weighted_random(results, rng),
+        SelectionStrategy::HighestProbability => pick_highest_probability(results),
+    }?;
+    results.iter().position(|r| r.kind.id == selected.id)
+}
+
+/// Selects kinds for deferred candidates in candidate order, rejecting kinds that would
+/// violate the layer's spacing rules. Each chunk keeps its own RNG stream.
+fn select_with_spacing(
+    ctx: &ChunkEvalContext<'_>,
+    mut deferred: Vec<(usize, Vec<(bool, f32)>)>,
+    spacing: &mut SpacingIndex,
+) -> Vec<(usize, usize)> {
+    deferred.sort_unstable_by_key(|(index, _)| *index);
+
+    let mut rngs: HashMap<ChunkId, StdRng> = HashMap::new();
+    let mut selections = Vec::new();
+    for (index, weights) in deferred {
+        let position = ctx.positions[index];
+        let chunk = ctx.chunk_of(position);
+        let rng = rngs
+            .entry(chunk)
+            .or_insert_with(|| StdRng::seed_from_u64(chunk::seed_for_chunk(ctx.layer_seed, chunk)));
+
+        let results: Vec<KindEvaluation> = ctx
+            .kind_info
+            .iter()
+            .zip(weights)
+            .enumerate()
+            .map(|(slot, (info, (allowed, weight)))| {
+                let allowed = allowed && spacing.fits(slot, position);
+                KindEvaluation {
+                    kind: info.kind.clone(),
+                    allowed,
+                    weight: if allowed { weight } else { 0.0 },
+                }
+            })
+            .collect();
+
+        if let Some(selected) = select_kind(ctx.layer.selection_strategy, &results, rng) {
+            spacing.insert(selected, position);
+            selections.push((index, selected));
+        }
+    }
+    selections
+}
+
+/// Builds placements for `(candidate index, kind slot)` selections made outside of chunk
+/// evaluation, sampling attribute fields per chunk.
+fn resolve_placements(
+    ctx: &ChunkEvalContext<'_>,
+    selections: Vec<(usize, usize)>,
+) -> Vec<(usize, Placement)> {
+    let mut chunk_selections: HashMap<ChunkId, Vec<(usize, usize)>> = HashMap::new();
+    for (index, slot) in selections {
+        chunk_selections
+            .entry(ctx.chunk_of(ctx.positions[index]))
+            .or_default()
+            .push((index, slot));
+    }
+    let jobs: Vec<(ChunkId, Vec<(usize, usize)>)> = chunk_selections.into_iter().collect();
+
+    map_chunks(&jobs, |(chunk, selections)| {
+        let grid = ctx.chunk_grid(*chunk);
+        let mut rt = FieldRuntime::new(ctx.program.clone(), ctx.textures);
+        selections
+            .iter()
+            .map(|&(index, slot)| {
+                let position = ctx.positions[index];
+                let info = &ctx.kind_info[slot];
+                let attributes =
+                    ctx.placement_attributes(slot, index, position, &mut rt, *chunk, &grid);
+                (
+                    index,
+                    Placement {
+                        layer_id: ctx.layer.id.clone(),
+                        kind_id: info.kind.id.clone(),
+                        position,
+                        attributes,
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Maps chunk jobs, concurrently when the `parallel` feature is enabled.
+pub(crate) fn map_chunks<J, T, F>(jobs: &[J], f: F) -> Vec<T>
+where
+    J: Sync,
+    T: Send,
+    F: Fn(&J) -> T + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        jobs.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        jobs.iter().map(f).collect()
+    }
 }
 
 pub fn run_plan<R: RngCore>(
@@ -618,49 +1032,91 @@ pub fn run_plan_with_events<R: RngCore>(
         }
     }
 
-    let mut overlays: HashMap<String, Arc<OverlayTexture>> = HashMap::new();
-
-    let mut all_placed: Vec<Placement> = Vec::new();
-    let mut total_eval = 0;
-    let mut total_reject = 0;
-
-    for (layer_idx, layer) in plan.layers.iter().enumerate() {
-        info!(
-            "Layer {}: '{}' | kinds: {}.",
-            layer_idx,
-            layer.id,
-            layer.kinds.len(),
-        );
+    let schedule = match plan.schedule(base_textures) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            warn!("Invalid placement plan: {}.", e);
+            if sink.wants(ScatterEventKind::Warning) {
+                sink.send(ScatterEvent::Warning {
+                    context: "plan".into(),
+                    message: format!("Invalid plan: {e}"),
+                });
+            }
+            let result = RunResult::new();
+            if sink.wants(ScatterEventKind::RunFinished) {
+                sink.send(ScatterEvent::RunFinished {
+                    result: result.clone(),
+                });
+            }
+            return result;
+        }
+    };
 
-        let ctx = LayerExecContext {
-            config,
-            base_textures,
-            overlays: &overlays,
+    // Every layer draws from its own RNG stream, so results do not depend on which
+    // independent layers share a wave or run concurrently.
+    let layer_seeds: Vec<u64> = plan.layers.iter().map(|_| rng.next_u64()).collect();
+    let mut outputs: Vec<Option<LayerOutput>> = vec![None; plan.layers.len()];
+
+    for wave in &schedule.waves {
+        let job = |index: usize, sink: &mut dyn EventSink| {
+            let overlays: HashMap<String, Arc<OverlayTexture>> = schedule.ancestors[index]
+                .iter()
+                .filter_map(|&ancestor| outputs[ancestor].as_ref()?.overlay.clone())
+                .collect();
+            run_plan_layer(
+                plan,
+                index,
+                config,
+                base_textures,
+                &overlays,
+                cache,
+                layer_seeds[index],
+                sink,
+            )
         };
-        let (layer_result, overlay_opt) =
-            run_layer_with_events_internal(layer, &ctx, cache, rng, sink, layer_idx);
-
-        total_eval += layer_result.positions_evaluated;
-        total_reject += layer_result.positions_rejected;
-        all_placed.extend(layer_result.placements.iter().cloned());
 
-        let overlay_summary = overlay_opt.as_ref().map(|(name, texture)| OverlaySummary {
-            name: name.clone(),
-            size_px: (texture.width, texture.height),
-        });
+        #[cfg(feature = "parallel")]
+        let finished: Vec<(usize, LayerOutput)> = if wave.len() > 1 {
+            use rayon::prelude::*;
+            let template = BufferedSink::for_sink(sink);
+            let buffered: Vec<(usize, LayerOutput, BufferedSink)> = wave
+                .par_iter()
+                .map(|&index| {
+                    let mut buffer = template.clone();
+                    let output = job(index, &mut buffer);
+                    (index, output, buffer)
+                })
+                .collect();
+            buffered
+                .into_iter()
+                .map(|(index, output, buffer)| {
+                    buffer.replay(sink);
+                    (index, output)
+                })
+                .collect()
+        } else {
+            wave.iter()
+                .map(|&index| (index, job(index, sink)))
+                .collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let finished: Vec<(usize, LayerOutput)> = wave
+            .iter()
+            .map(|&index| (index, job(index, sink)))
+            .collect();
 
-        if sink.wants(ScatterEventKind::LayerFinished) {
-            sink.send(ScatterEvent::LayerFinished {
-                index: layer_idx,
-                id: layer.id.clone(),
-                result: layer_result.clone(),
-                overlay: overlay_summary.clone(),
-            });
+        for (index, output) in finished {
+            outputs[index] = Some(output);
         }
+    }
 
-        if let Some((name, ov)) = overlay_opt {
-            overlays.insert(name, ov);
-        }
+    let mut all_placed: Vec<Placement> = Vec::new();
+    let mut total_eval = 0;
+    let mut total_reject = 0;
+    for output in outputs.into_iter().flatten() {
+        total_eval += output.result.positions_evaluated;
+        total_reject += output.result.positions_rejected;
+        all_placed.extend(output.result.placements);
     }
 
     let result = RunResult {
@@ -678,6 +1134,93 @@ pub fn run_plan_with_events<R: RngCore>(
     result
 }
 
+#[derive(Clone)]
+struct LayerOutput {
+    result: RunResult,
+    overlay: Option<(String, Arc<OverlayTexture>)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_plan_layer(
+    plan: &Plan,
+    layer_idx: usize,
+    config: &RunConfig,
+    base_textures: &TextureRegistry,
+    overlays: &HashMap<String, Arc<OverlayTexture>>,
+    cache: &FieldProgramCache,
+    seed: u64,
+    sink: &mut dyn EventSink,
+) -> LayerOutput {
+    let layer = &plan.layers[layer_idx];
+    info!(
+        "Layer {}: '{}' | kinds: {}.",
+        layer_idx,
+        layer.id,
+        layer.kinds.len(),
+    );
+
+    let ctx = LayerExecContext {
+        config,
+        base_textures,
+        overlays,
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (result, overlay) =
+        run_layer_with_events_internal(layer, &ctx, cache, &mut rng, sink, layer_idx);
+
+    if sink.wants(ScatterEventKind::LayerFinished) {
+        sink.send(ScatterEvent::LayerFinished {
+            index: layer_idx,
+            id: layer.id.clone(),
+            result: result.clone(),
+            overlay: overlay.as_ref().map(|(name, texture)| OverlaySummary {
+                name: name.clone(),
+                size_px: (texture.width, texture.height),
+            }),
+        });
+    }
+
+    LayerOutput { result, overlay }
+}
+
+/// Collects events of a layer running on another thread for in-order replay.
+#[cfg(feature = "parallel")]
+#[derive(Clone)]
+struct BufferedSink {
+    wanted: Vec<ScatterEventKind>,
+    events: Vec<ScatterEvent>,
+}
+
+#[cfg(feature = "parallel")]
+impl BufferedSink {
+    fn for_sink(sink: &dyn EventSink) -> Self {
+        Self {
+            wanted: ScatterEventKind::ALL
+                .into_iter()
+                .filter(|kind| sink.wants(*kind))
+                .collect(),
+            events: Vec::new(),
+        }
+    }
+
+    fn replay(self, sink: &mut dyn EventSink) {
+        for event in self.events {
+            sink.send(event);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl EventSink for BufferedSink {
+    fn send(&mut self, event: ScatterEvent) {
+        self.events.push(event);
+    }
+
+    fn wants(&self, kind: ScatterEventKind) -> bool {
+        self.wanted.contains(&kind)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -690,6 +1233,7 @@ mod tests {
     use crate::fieldgraph::NodeSpec;
     use crate::sampling::JitterGridSampling;
     use crate::scatter::events::{ScatterEvent, VecSink};
+    use crate::scatter::spacing::SpacingRules;
 
     fn make_kind(id: &str) -> Kind {
         let mut spec = FieldGraphSpec::default();
@@ -797,4 +1341,401 @@ mod tests {
 
         assert_eq!(overlay_size, (8, 8));
     }
+
+    #[test]
+    fn later_layers_read_per_kind_overlay_masks() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let mut never = FieldGraphSpec::default();
+        never.add_with_semantics(
+            "probability",
+            NodeSpec::constant(0.0),
+            FieldSemantics::Probability,
+        );
+        let trees = Layer::new_with(
+            "trees",
+            vec![make_kind("oak"), Kind::new("pine", never)],
+            JitterGridSampling::new(0.0, 5.0),
+        )
+        .with_overlay((8, 8), 8)
+        .with_overlay_kind_masks(true);
+        let reader = |id: &str, mask: &str| {
+            let mut spec = FieldGraphSpec::default();
+            spec.add_with_semantics(
+                "probability",
+                NodeSpec::texture(mask, crate::fieldgraph::TextureChannel::R),
+                FieldSemantics::Probability,
+            );
+            Layer::new_with(
+                id,
+                vec![Kind::new(id, spec)],
+                JitterGridSampling::new(0.0, 5.0),
+            )
+            .with_dependencies(["trees"])
+        };
+        let plan = Plan::new().with_layers(vec![
+            trees,
+            reader("near_oak", "mask_trees:oak"),
+            reader("near_pine", "mask_trees:pine"),
+        ]);
+
+        let result = run_plan(&plan, &base_config(), &textures, &cache, &mut rng, None);
+        let count = |kind: &str| {
+            result
+                .placements
+                .iter()
+                .filter(|p| p.kind_id == kind)
+                .count()
+        };
+        assert!(count("oak") > 0);
+        assert_eq!(count("near_oak"), count("oak"));
+        assert_eq!(count("near_pine"), 0);
+    }
+
+    #[test]
+    fn invalid_plan_reports_warning_and_places_nothing() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add_with_semantics(
+            "gate",
+            NodeSpec::texture("mask_missing", crate::fieldgraph::TextureChannel::R),
+            FieldSemantics::Gate,
+        );
+        let plan = Plan::new().with_layer(Layer::new_with(
+            "reader",
+            vec![Kind::new("kind", spec)],
+            JitterGridSampling::new(0.0, 5.0),
+        ));
+
+        let mut sink = VecSink::new();
+        let result = run_plan_with_events(
+            &plan,
+            &base_config(),
+            &textures,
+            &cache,
+            &mut rng,
+            &mut sink,
+        );
+
+        assert!(result.placements.is_empty());
+        assert!(sink.into_inner().iter().any(|event| matches!(
+            event,
+            ScatterEvent::Warning { context, message } if context == "plan" && message.contains("mask_missing")
+        )));
+    }
+
+    #[test]
+    fn independent_layers_match_their_sequential_results() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let layers = || {
+            vec![
+                Layer::new_with(
+                    "a",
+                    vec![make_kind("kind_a")],
+                    JitterGridSampling::new(1.0, 2.0),
+                )
+                .with_dependencies(Vec::<String>::new()),
+                Layer::new_with(
+                    "b",
+                    vec![make_kind("kind_b")],
+                    JitterGridSampling::new(1.0, 2.0),
+                )
+                .with_dependencies(Vec::<String>::new()),
+            ]
+        };
+        let independent = Plan::new().with_layers(layers());
+        let sequential = Plan::new().with_layers(
+            layers()
+                .into_iter()
+                .map(|mut layer| {
+                    layer.dependencies = None;
+                    layer
+                })
+                .collect(),
+        );
+
+        let run = |plan: &Plan| {
+            let mut rng = StdRng::seed_from_u64(21);
+            let mut sink = VecSink::new();
+            let result =
+                run_plan_with_events(plan, &base_config(), &textures, &cache, &mut rng, &mut sink);
+            let started: Vec<usize> = sink
+                .into_inner()
+                .iter()
+                .filter_map(|event| match event {
+                    ScatterEvent::LayerStarted { index, .. } => Some(*index),
+                    _ => None,
+                })
+                .collect();
+            (placement_keys(&result), started)
+        };
+
+        let (independent_keys, independent_started) = run(&independent);
+        let (sequential_keys, sequential_started) = run(&sequential);
+        assert!(!independent_keys.is_empty());
+        assert_eq!(independent_keys, sequential_keys);
+        assert_eq!(independent_started, vec![0, 1]);
+        assert_eq!(sequential_started, vec![0, 1]);
+    }
+
+    #[test]
+    fn placements_are_attributed_to_their_layers() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let plan = Plan::new().with_layers(vec![
+            Layer::new_with(
+                "trees",
+                vec![make_kind("oak")],
+                JitterGridSampling::new(1.0, 2.0),
+            ),
+            Layer::new_with("empty", Vec::new(), JitterGridSampling::new(1.0, 2.0)),
+            Layer::new_with(
+                "rocks",
+                vec![make_kind("rock")],
+                JitterGridSampling::new(1.0, 5.0),
+            ),
+        ]);
+        let mut rng = StdRng::seed_from_u64(3);
+        let result = run_plan(&plan, &base_config(), &textures, &cache, &mut rng, None);
+
+        assert!(result.placements.iter().any(|p| p.layer_id == "trees"));
+        assert!(result.placements.iter().any(|p| p.layer_id == "rocks"));
+        for placement in &result.placements {
+            let expected = if placement.kind_id == "oak" {
+                "trees"
+            } else {
+                "rocks"
+            };
+            assert_eq!(placement.layer_id, expected);
+        }
+    }
+
+    fn run_multi_chunk_layer(seed: u64) -> RunResult {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut half = FieldGraphSpec::default();
+        half.add_with_semantics(
+            "probability",
+            NodeSpec::constant(0.5),
+            FieldSemantics::Probability,
+        );
+        let layer = Layer::new_with(
+            "multi_chunk",
+            vec![make_kind("kind_a"), Kind::new("kind_b", half)],
+            JitterGridSampling::new(1.0, 1.0),
+        );
+        let config = RunConfig::new(Vec2::new(20.0, 20.0))
+            .with_chunk_extent(5.0)
+            .with_raster_cell_size(1.0)
+            .with_grid_halo(0);
+
+        let (result, _) = run_layer(
+            &layer,
+            &config,
+            &textures,
+            &HashMap::new(),
+            &cache,
+            &mut rng,
+            None,
+        );
+        result
+    }
+
+    fn placement_keys(result: &RunResult) -> Vec<(String, u32, u32)> {
+        result
+            .placements
+            .iter()
+            .map(|p| {
+                (
+                    p.kind_id.clone(),
+                    p.position.x.to_bits(),
+                    p.position.y.to_bits(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunked_layer_is_deterministic_per_seed() {
+        let a = run_multi_chunk_layer(11);
+        let b = run_multi_chunk_layer(11);
+        assert_eq!(a.positions_evaluated, 400);
+        assert!(!a.placements.is_empty());
+        assert_eq!(placement_keys(&a), placement_keys(&b));
+    }
+
+    #[test]
+    fn spacing_rules_hold_across_chunk_borders() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let spacing = SpacingRules::new()
+            .with_min_distance("tree", "boulder", 3.0)
+            .with_footprint("tree", 1.0);
+        let layer = Layer::new_with(
+            "spaced",
+            vec![make_kind("tree"), make_kind("boulder")],
+            JitterGridSampling::new(1.0, 0.5),
+        )
+        .with_spacing(spacing.clone());
+        let config = RunConfig::new(Vec2::new(20.0, 20.0))
+            .with_chunk_extent(5.0)
+            .with_raster_cell_size(1.0)
+            .with_grid_halo(0);
+
+        let run = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            run_layer(
+                &layer,
+                &config,
+                &textures,
+                &HashMap::new(),
+                &cache,
+                &mut rng,
+                None,
+            )
+            .0
+        };
+        let result = run(5);
+        assert_eq!(placement_keys(&result), placement_keys(&run(5)));
+        assert!(!result.placements.is_empty());
+        assert!(result.positions_rejected > 0);
+
+        let placements = &result.placements;
+        for (i, a) in placements.iter().enumerate() {
+            for b in &placements[i + 1..] {
+                let min = spacing.min_distance(&a.kind_id, &b.kind_id);
+                assert!(
+                    a.position.distance(b.position) >= min,
+                    "{} at {} too close to {} at {}",
+                    a.kind_id,
+                    a.position,
+                    b.kind_id,
+                    b.position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn placements_carry_field_and_random_attributes() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+
+        let mut spec = FieldGraphSpec::default();
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::constant(1.0),
+            FieldSemantics::Probability,
+        );
+        spec.add_with_semantics("scale", NodeSpec::position_x(), FieldSemantics::Scale);
+        spec.add_with_semantics(
+            "tint",
+            NodeSpec::constant(0.25),
+            FieldSemantics::Attribute("tint".into()),
+        );
+        let kind = Kind::new("tree", spec)
+            .with_random_attribute(AttributeChannel::Rotation, 0.0, std::f32::consts::TAU)
+            .with_random_attribute(AttributeChannel::Variant, 0.0, 3.0);
+        let layer = Layer::new_with("attributed", vec![kind], JitterGridSampling::new(1.0, 1.0));
+        let config = RunConfig::new(Vec2::new(20.0, 20.0))
+            .with_chunk_extent(5.0)
+            .with_raster_cell_size(1.0)
+            .with_grid_halo(0);
+
+        let run = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            run_layer(
+                &layer,
+                &config,
+                &textures,
+                &HashMap::new(),
+                &cache,
+                &mut rng,
+                None,
+            )
+            .0
+        };
+        let a = run(9);
+        let b = run(9);
+        assert!(!a.placements.is_empty());
+
+        let mut rotations = HashSet::new();
+        for (pa, pb) in a.placements.iter().zip(&b.placements) {
+            assert_eq!(pa.attributes, pb.attributes);
+            let attributes = &pa.attributes;
+            assert_eq!(attributes.scale, Some(pa.position.x));
+            assert_eq!(attributes.get("tint"), Some(0.25));
+            assert!(attributes.variant.is_some_and(|v| v < 3));
+            let rotation = attributes.rotation.expect("rotation");
+            assert!((0.0..std::f32::consts::TAU).contains(&rotation));
+            rotations.insert(rotation.to_bits());
+        }
+        assert!(rotations.len() > 1);
+    }
+
+    #[test]
+    fn position_events_precede_their_placements() {
+        let cache = FieldProgramCache::new();
+        let textures = TextureRegistry::new();
+        let mut rng = StdRng::seed_from_u64(3);
+        let layer = Layer::new_with(
+            "ordered",
+            vec![make_kind("kind_a")],
+            JitterGridSampling::new(0.0, 2.5),
+        );
+        let config = base_config()
+            .with_chunk_extent(5.0)
+            .with_raster_cell_size(1.0);
+
+        let mut sink = VecSink::new();
+        let (result, _) = run_layer_with_events(
+            &layer,
+            &config,
+            &textures,
+            &HashMap::new(),
+            &cache,
+            &mut rng,
+            &mut sink,
+        );
+
+        let mut last_evaluated = None;
+        let mut evaluated = 0;
+        for event in sink.into_inner() {
+            match event {
+                ScatterEvent::PositionEvaluated { position, .. } => {
+                    last_evaluated = Some(position);
+                    evaluated += 1;
+                }
+                ScatterEvent::PlacementMade { placement, .. } => {
+                    assert_eq!(Some(placement.position), last_evaluated);
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(evaluated, result.positions_evaluated);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_layer_is_independent_of_thread_count() {
+        let run_with_threads = |threads: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("thread pool")
+                .install(|| run_multi_chunk_layer(99))
+        };
+
+        let single = run_with_threads(1);
+        let many = run_with_threads(4);
+        assert_eq!(placement_keys(&single), placement_keys(&many));
+    }
 }
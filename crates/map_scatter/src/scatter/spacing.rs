@@ -0,0 +1,204 @@
+//! Minimum-distance constraints between placements within a layer.
+//!
+//! [`SpacingRules`] describes per-kind footprint radii and explicit kind-pair minimum
+//! distances. During layer execution the rules are resolved into a [`SpacingIndex`],
+//! a spatial hash of accepted placements that rejects kinds violating their spacing.
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::scatter::KindId;
+
+/// Spacing constraints between kinds placed by the same layer.
+///
+/// The minimum distance between two kinds is the explicit pair distance if one was set,
+/// otherwise the sum of both kinds' footprint radii.
+#[derive(Clone, Debug, Default)]
+pub struct SpacingRules {
+    /// Footprint radius per kind in world units.
+    pub footprint_radii: HashMap<KindId, f32>,
+    /// Explicit minimum distances per unordered kind pair in world units.
+    pub min_distances: HashMap<(KindId, KindId), f32>,
+}
+
+impl SpacingRules {
+    /// Creates empty rules that do not constrain placements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no footprints or pair distances are configured.
+    pub fn is_empty(&self) -> bool {
+        self.footprint_radii.is_empty() && self.min_distances.is_empty()
+    }
+
+    /// Sets the footprint radius of a kind.
+    pub fn with_footprint(mut self, kind: impl Into<KindId>, radius: f32) -> Self {
+        self.footprint_radii.insert(kind.into(), radius);
+        self
+    }
+
+    /// Sets the minimum distance between two kinds (order does not matter).
+    /// Use the same kind twice to space out instances of a single kind.
+    pub fn with_min_distance(
+        mut self,
+        a: impl Into<KindId>,
+        b: impl Into<KindId>,
+        distance: f32,
+    ) -> Self {
+        self.min_distances
+            .insert(pair_key(a.into(), b.into()), distance);
+        self
+    }
+
+    /// Returns the minimum distance required between placements of kinds `a` and `b`.
+    pub fn min_distance(&self, a: &str, b: &str) -> f32 {
+        let key = pair_key(a.to_owned(), b.to_owned());
+        if let Some(distance) = self.min_distances.get(&key) {
+            return distance.max(0.0);
+        }
+        let radius = |kind: &str| self.footprint_radii.get(kind).copied().unwrap_or(0.0);
+        (radius(a) + radius(b)).max(0.0)
+    }
+}
+
+fn pair_key(a: KindId, b: KindId) -> (KindId, KindId) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Spatial hash of accepted placements used to enforce [`SpacingRules`].
+///
+/// Kinds are addressed by their index in the slice passed to [`SpacingIndex::new`].
+pub struct SpacingIndex {
+    kind_count: usize,
+    distances: Vec<f32>,
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(usize, Vec2)>>,
+}
+
+impl SpacingIndex {
+    /// Resolves the rules for the given kinds. Returns `None` if no pair of kinds is constrained.
+    pub fn new(rules: &SpacingRules, kinds: &[KindId]) -> Option<Self> {
+        if rules.is_empty() {
+            return None;
+        }
+
+        let kind_count = kinds.len();
+        let mut distances = vec![0.0; kind_count * kind_count];
+        for (i, a) in kinds.iter().enumerate() {
+            for (j, b) in kinds.iter().enumerate() {
+                distances[i * kind_count + j] = rules.min_distance(a, b);
+            }
+        }
+
+        let cell_size = distances.iter().copied().fold(0.0f32, f32::max);
+        if !cell_size.is_finite() || cell_size <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            kind_count,
+            distances,
+            cell_size,
+            cells: HashMap::new(),
+        })
+    }
+
+    /// Returns `true` if a placement of `kind` at `p` keeps its distance to all accepted placements.
+    pub fn fits(&self, kind: usize, p: Vec2) -> bool {
+        let row = &self.distances[kind * self.kind_count..(kind + 1) * self.kind_count];
+        if row.iter().all(|d| *d <= 0.0) {
+            return true;
+        }
+
+        let (cx, cy) = self.cell(p);
+        for y in cy - 1..=cy + 1 {
+            for x in cx - 1..=cx + 1 {
+                let Some(entries) = self.cells.get(&(x, y)) else {
+                    continue;
+                };
+                for (other, q) in entries {
+                    let min = row[*other];
+                    if min > 0.0 && p.distance_squared(*q) < min * min {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Records an accepted placement of `kind` at `p`.
+    pub fn insert(&mut self, kind: usize, p: Vec2) {
+        let cell = self.cell(p);
+        self.cells.entry(cell).or_default().push((kind, p));
+    }
+
+    fn cell(&self, p: Vec2) -> (i32, i32) {
+        (
+            (p.x / self.cell_size).floor() as i32,
+            (p.y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(ids: &[&str]) -> Vec<KindId> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn min_distance_prefers_explicit_pairs_over_footprints() {
+        let rules = SpacingRules::new()
+            .with_footprint("tree", 1.0)
+            .with_footprint("boulder", 0.5)
+            .with_min_distance("tree", "boulder", 3.0);
+
+        assert_eq!(rules.min_distance("boulder", "tree"), 3.0);
+        assert_eq!(rules.min_distance("tree", "tree"), 2.0);
+        assert_eq!(rules.min_distance("boulder", "bush"), 0.5);
+        assert_eq!(rules.min_distance("bush", "bush"), 0.0);
+    }
+
+    #[test]
+    fn empty_rules_produce_no_index() {
+        assert!(SpacingIndex::new(&SpacingRules::new(), &kinds(&["a"])).is_none());
+    }
+
+    #[test]
+    fn index_rejects_only_constrained_pairs() {
+        let rules = SpacingRules::new()
+            .with_min_distance("tree", "boulder", 3.0)
+            .with_min_distance("bush", "bush", 0.5);
+        let ids = kinds(&["tree", "boulder", "bush"]);
+        let mut index = SpacingIndex::new(&rules, &ids).expect("index");
+
+        index.insert(0, Vec2::ZERO);
+        assert!(!index.fits(1, Vec2::new(2.9, 0.0)));
+        assert!(index.fits(1, Vec2::new(3.0, 0.0)));
+        assert!(index.fits(2, Vec2::new(0.1, 0.0)));
+
+        index.insert(2, Vec2::new(10.0, 10.0));
+        assert!(!index.fits(2, Vec2::new(10.2, 10.2)));
+        assert!(index.fits(0, Vec2::new(10.2, 10.2)));
+    }
+
+    #[test]
+    fn index_finds_neighbours_across_cells() {
+        let rules = SpacingRules::new().with_footprint("a", 1.0);
+        let ids = kinds(&["a"]);
+        let mut index = SpacingIndex::new(&rules, &ids).expect("index");
+
+        index.insert(0, Vec2::new(1.9, 1.9));
+        assert!(!index.fits(0, Vec2::new(2.1, 2.1)));
+        assert!(!index.fits(0, Vec2::new(-0.05, 1.9)));
+        assert!(index.fits(0, Vec2::new(4.0, 1.9)));
+    }
+}
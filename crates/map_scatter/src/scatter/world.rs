@@ -0,0 +1,821 @@
+//! Deterministic scattering of single chunks of an unbounded world.
+//!
+//! [`run_world_chunk`] scatters one [`ChunkId`] of a world tiled into chunks of
+//! [`WorldChunkConfig::chunk_size`]. Chunk `(x, y)` is centered at `(x, y) * chunk_size`.
+//! Results only depend on the plan, the seed, and the chunk id, so neighbouring chunks can be
+//! generated independently and in any order without seams:
+//!
+//! - Every world chunk generates its own candidates from a seed derived from its id. Candidates
+//!   of neighbouring chunks closer than [`Layer::seam_distance`] are thinned by a fixed
+//!   per-candidate priority.
+//! - Kind selection and attributes draw from per-candidate seeds, and spacing rules reject a
+//!   candidate if a higher-priority candidate nearby would violate them.
+//! - Each layer is evaluated over the chunk expanded by a halo, so spacing, seam thinning,
+//!   and overlays read by later layers see the neighbouring chunks' candidates.
+//!
+//! The halo must cover the largest of the seam distances, spacing distances, and overlay
+//! brush radii (in world units) of the plan; layers read by other layers are evaluated
+//! with additional halos.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use glam::Vec2;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::fieldgraph::cache::FieldProgramCache;
+use crate::fieldgraph::runtime::FieldRuntime;
+use crate::fieldgraph::{ChunkId, TextureRegistry};
+use crate::scatter::chunk::{self, mix_u64, seed_for_chunk};
+use crate::scatter::events::{
+    EventSink, KindEvaluationLite, OverlaySummary, ScatterEvent, ScatterEventKind,
+};
+use crate::scatter::overlay::OverlayTexture;
+use crate::scatter::plan::{Layer, Plan};
+use crate::scatter::runner::{
+    build_layer_overlay, compile_kinds, evaluate_kinds, map_chunks, resolve_attributes,
+    select_kind, LayerKinds, Placement, RunConfig, RunResult,
+};
+use crate::scatter::spacing::SpacingIndex;
+
+const ATTRIBUTE_SALT: u64 = 0xA076_1D64_78BD_642F;
+const PRIORITY_SALT: u64 = 0xE703_7ED1_A0B4_28DB;
+
+/// Configuration for scattering chunks of an unbounded world.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct WorldChunkConfig {
+    /// Size of a world chunk in world units.
+    pub chunk_size: Vec2,
+    /// Distance in world units by which neighbouring chunks are read around a chunk.
+    pub halo: f32,
+    /// Size of the world-aligned evaluation tiles for field rasters in world units.
+    pub chunk_extent: f32,
+    /// Raster cell size used for field sampling in world units.
+    pub raster_cell_size: f32,
+    /// Extra halo cells around each evaluation tile for filters and EDT. Raised to cover the
+    /// kernels of filter nodes, as for [`RunConfig::grid_halo`].
+    pub grid_halo: usize,
+}
+
+impl WorldChunkConfig {
+    /// Creates a configuration for chunks of `chunk_size` without halo.
+    pub fn new(chunk_size: Vec2) -> Self {
+        Self {
+            chunk_size,
+            halo: 0.0,
+            chunk_extent: chunk_size.max_element(),
+            raster_cell_size: 1.0,
+            grid_halo: 2,
+        }
+    }
+
+    /// Sets the halo distance in world units.
+    pub fn with_halo(mut self, halo: f32) -> Self {
+        self.halo = halo;
+        self
+    }
+
+    /// Sets the evaluation tile size in world units.
+    pub fn with_chunk_extent(mut self, chunk_extent: f32) -> Self {
+        self.chunk_extent = chunk_extent;
+        self
+    }
+
+    /// Sets the raster cell size in world units.
+    pub fn with_raster_cell_size(mut self, raster_cell_size: f32) -> Self {
+        self.raster_cell_size = raster_cell_size;
+        self
+    }
+
+    /// Sets the halo cell count of evaluation tiles.
+    pub fn with_grid_halo(mut self, grid_halo: usize) -> Self {
+        self.grid_halo = grid_halo;
+        self
+    }
+
+    /// Validates the configuration, returning an error if invalid.
+    pub fn validate(&self) -> Result<()> {
+        if !(self.chunk_size.x > 0.0 && self.chunk_size.y > 0.0) {
+            return Err(Error::InvalidConfig(
+                "chunk_size must be > 0 in both components".into(),
+            ));
+        }
+        if !(self.halo.is_finite() && self.halo >= 0.0) {
+            return Err(Error::InvalidConfig("halo must be finite and >= 0".into()));
+        }
+        if self.chunk_extent <= 0.0 {
+            return Err(Error::InvalidConfig("chunk_extent must be > 0".into()));
+        }
+        if self.raster_cell_size <= 0.0 {
+            return Err(Error::InvalidConfig("raster_cell_size must be > 0".into()));
+        }
+        Ok(())
+    }
+
+    /// Returns the world-space center of a chunk.
+    pub fn chunk_center(&self, chunk: ChunkId) -> Vec2 {
+        Vec2::new(chunk.0 as f32, chunk.1 as f32) * self.chunk_size
+    }
+
+    /// Returns the chunk containing `position`.
+    pub fn chunk_at(&self, position: Vec2) -> ChunkId {
+        let id = (position / self.chunk_size + 0.5).floor();
+        ChunkId(id.x as i32, id.y as i32)
+    }
+
+    /// Returns the run configuration describing a single chunk.
+    pub fn run_config(&self, chunk: ChunkId) -> RunConfig {
+        RunConfig::new(self.chunk_size)
+            .with_domain_center(self.chunk_center(chunk))
+            .with_chunk_extent(self.chunk_extent)
+            .with_raster_cell_size(self.raster_cell_size)
+            .with_grid_halo(self.grid_halo)
+    }
+
+    fn chunk_bounds(&self, chunk: ChunkId) -> Bounds {
+        let center = self.chunk_center(chunk);
+        Bounds {
+            min: center - self.chunk_size * 0.5,
+            max: center + self.chunk_size * 0.5,
+        }
+    }
+}
+
+/// Axis-aligned, half-open rectangle `[min, max)`.
+#[derive(Clone, Copy, Debug)]
+struct Bounds {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Bounds {
+    fn expand(self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vec2::splat(margin),
+            max: self.max + Vec2::splat(margin),
+        }
+    }
+
+    fn contains(&self, p: Vec2) -> bool {
+        p.x >= self.min.x && p.y >= self.min.y && p.x < self.max.x && p.y < self.max.y
+    }
+}
+
+struct Candidate {
+    position: Vec2,
+    source: ChunkId,
+    local: usize,
+    key: u64,
+}
+
+impl Candidate {
+    fn priority(&self) -> (u64, i32, i32, usize) {
+        (self.key, self.source.0, self.source.1, self.local)
+    }
+}
+
+/// Tentative placement of a candidate before spacing rules are applied.
+struct Tentative {
+    candidate: usize,
+    slot: usize,
+    placement: Placement,
+}
+
+/// Scatters a single chunk of an unbounded world; see the [module docs](self).
+pub fn run_world_chunk(
+    plan: &Plan,
+    chunk: ChunkId,
+    config: &WorldChunkConfig,
+    base_textures: &TextureRegistry,
+    cache: &FieldProgramCache,
+    seed: u64,
+    sink: Option<&mut dyn EventSink>,
+) -> RunResult {
+    if let Some(s) = sink {
+        run_world_chunk_with_events(plan, chunk, config, base_textures, cache, seed, s)
+    } else {
+        run_world_chunk_with_events(plan, chunk, config, base_textures, cache, seed, &mut ())
+    }
+}
+
+/// Scatters a single chunk of an unbounded world, emitting events to `sink`.
+///
+/// Placements and events only cover candidates inside `chunk`.
+pub fn run_world_chunk_with_events(
+    plan: &Plan,
+    chunk: ChunkId,
+    config: &WorldChunkConfig,
+    base_textures: &TextureRegistry,
+    cache: &FieldProgramCache,
+    seed: u64,
+    sink: &mut dyn EventSink,
+) -> RunResult {
+    let schedule = match config.validate().and_then(|_| plan.schedule(base_textures)) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            warn!("Cannot scatter world chunk {:?}: {}.", chunk, e);
+            if sink.wants(ScatterEventKind::Warning) {
+                sink.send(ScatterEvent::Warning {
+                    context: format!("chunk:{},{}", chunk.0, chunk.1),
+                    message: format!("Cannot scatter world chunk: {e}"),
+                });
+            }
+            return RunResult::new();
+        }
+    };
+
+    if sink.wants(ScatterEventKind::RunStarted) {
+        sink.send(ScatterEvent::RunStarted {
+            config: config.run_config(chunk),
+            layer_count: plan.layers.len(),
+        });
+    }
+
+    // A layer read by others must be exact over the region its dependents evaluate, which
+    // extends one halo for candidates and one for overlay brushes beyond their own region.
+    let order: Vec<usize> = schedule.waves.iter().flatten().copied().collect();
+    let mut margins = vec![0.0f32; plan.layers.len()];
+    for &index in order.iter().rev() {
+        for (dependent, ancestors) in schedule.ancestors.iter().enumerate() {
+            if ancestors.contains(&index) {
+                margins[index] = margins[index].max(margins[dependent] + 2.0 * config.halo);
+            }
+        }
+    }
+
+    let chunk_bounds = config.chunk_bounds(chunk);
+    let mut overlays_by_layer: Vec<Option<(String, Arc<OverlayTexture>)>> =
+        vec![None; plan.layers.len()];
+    let mut result = RunResult::new();
+
+    for &index in &order {
+        let layer = &plan.layers[index];
+        let overlays: HashMap<String, Arc<OverlayTexture>> = schedule.ancestors[index]
+            .iter()
+            .filter_map(|&ancestor| overlays_by_layer[ancestor].clone())
+            .collect();
+        let mut textures = TextureRegistry::with_capacity(base_textures.len() + overlays.len());
+        textures.extend_from(base_textures);
+        for (name, overlay) in overlays {
+            overlay.register_into(&name, &mut textures);
+        }
+
+        if sink.wants(ScatterEventKind::LayerStarted) {
+            sink.send(ScatterEvent::LayerStarted {
+                index,
+                id: layer.id.clone(),
+                kinds: layer.kinds.iter().map(|k| k.id.clone()).collect(),
+                overlay_mask_size_px: layer.overlay_mask_size_px,
+                overlay_brush_radius_px: layer.overlay_brush_radius_px,
+            });
+        }
+
+        let layer_ctx = WorldLayerContext {
+            layer,
+            layer_index: index,
+            config,
+            textures: &textures,
+            layer_kinds: compile_kinds(layer, cache, sink),
+            layer_seed: mix_u64(seed ^ (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+            region: chunk_bounds.expand(margins[index]),
+            chunk_bounds,
+        };
+        let (overlay, layer_result) = run_world_layer(&layer_ctx, sink);
+
+        if sink.wants(ScatterEventKind::LayerFinished) {
+            sink.send(ScatterEvent::LayerFinished {
+                index,
+                id: layer.id.clone(),
+                result: layer_result.clone(),
+                overlay: overlay.as_ref().map(|(name, texture)| OverlaySummary {
+                    name: name.clone(),
+                    size_px: (texture.width, texture.height),
+                }),
+            });
+        }
+
+        result.positions_evaluated += layer_result.positions_evaluated;
+        result.positions_rejected += layer_result.positions_rejected;
+        result.placements.extend(layer_result.placements);
+        overlays_by_layer[index] = overlay;
+    }
+
+    if sink.wants(ScatterEventKind::RunFinished) {
+        sink.send(ScatterEvent::RunFinished {
+            result: result.clone(),
+        });
+    }
+
+    result
+}
+
+/// Tentative placements and `PositionEvaluated` events (keyed by candidate) of a tile.
+type TileOutcome = (Vec<Tentative>, Vec<(usize, ScatterEvent)>);
+
+struct WorldLayerContext<'a> {
+    layer: &'a Layer,
+    layer_index: usize,
+    config: &'a WorldChunkConfig,
+    textures: &'a TextureRegistry,
+    layer_kinds: LayerKinds,
+    layer_seed: u64,
+    /// Region over which placements of this layer must be exact.
+    region: Bounds,
+    chunk_bounds: Bounds,
+}
+
+fn run_world_layer(
+    ctx: &WorldLayerContext<'_>,
+    sink: &mut dyn EventSink,
+) -> (Option<(String, Arc<OverlayTexture>)>, RunResult) {
+    if ctx.layer_kinds.kinds.is_empty() {
+        return (None, RunResult::new());
+    }
+
+    let halo = ctx.config.halo;
+    let eval_region = ctx.region.expand(halo);
+    let candidates = generate_candidates(ctx, eval_region.expand(halo));
+    let candidates = thin_seams(candidates, ctx.layer.seam_distance, eval_region);
+
+    // Evaluate candidates per world-aligned tile so baked rasters do not depend on the chunk
+    // being scattered.
+    let mut tiles: HashMap<ChunkId, Vec<usize>> = HashMap::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        let tile =
+            chunk::chunk_id_for_position(candidate.position, Vec2::ZERO, ctx.config.chunk_extent);
+        tiles.entry(tile).or_default().push(i);
+    }
+    let mut tile_jobs: Vec<(ChunkId, Vec<usize>)> = tiles.into_iter().collect();
+    tile_jobs.sort_unstable_by_key(|(tile, _)| (tile.0, tile.1));
+
+    let emit_evaluations = sink.wants(ScatterEventKind::PositionEvaluated);
+    let evaluated: Vec<TileOutcome> = map_chunks(&tile_jobs, |(tile, indices)| {
+        evaluate_tile(ctx, &candidates, *tile, indices, emit_evaluations)
+    });
+
+    let mut tentatives: Vec<Tentative> = Vec::new();
+    let mut evaluations: Vec<(usize, ScatterEvent)> = Vec::new();
+    for (tile_tentatives, tile_evaluations) in evaluated {
+        tentatives.extend(tile_tentatives);
+        evaluations.extend(tile_evaluations);
+    }
+
+    // A tentative placement is rejected if any higher-priority tentative placement nearby
+    // violates its spacing, regardless of whether that one is kept itself. This only depends
+    // on candidates within the spacing distance, so it agrees across chunks.
+    let kind_ids: Vec<_> = ctx
+        .layer_kinds
+        .kinds
+        .iter()
+        .map(|info| info.kind.id.clone())
+        .collect();
+    if let Some(mut spacing) = SpacingIndex::new(&ctx.layer.spacing, &kind_ids) {
+        tentatives.sort_unstable_by(|a, b| {
+            candidates[b.candidate]
+                .priority()
+                .cmp(&candidates[a.candidate].priority())
+        });
+        tentatives.retain(|t| {
+            let fits = spacing.fits(t.slot, t.placement.position);
+            spacing.insert(t.slot, t.placement.position);
+            fits
+        });
+    }
+    tentatives.retain(|t| ctx.region.contains(t.placement.position));
+    tentatives.sort_unstable_by_key(|t| t.candidate);
+    evaluations.sort_unstable_by_key(|(i, _)| *i);
+
+    let (placement_candidates, placements): (Vec<usize>, Vec<Placement>) = tentatives
+        .into_iter()
+        .map(|t| (t.candidate, t.placement))
+        .unzip();
+    let overlay = world_overlay(ctx, &placements, sink);
+
+    // Evaluation events are keyed by candidate index, so they are sent before the placement
+    // made from the same candidate, as in the flat runner.
+    let mut evaluations = evaluations.into_iter().peekable();
+    let mut chunk_placements = Vec::new();
+    for (&candidate, placement) in placement_candidates.iter().zip(&placements) {
+        if !ctx.chunk_bounds.contains(placement.position) {
+            continue;
+        }
+        while let Some((_, event)) = evaluations.next_if(|(c, _)| *c <= candidate) {
+            sink.send(event);
+        }
+        if sink.wants(ScatterEventKind::PlacementMade) {
+            sink.send(ScatterEvent::PlacementMade {
+                layer_index: ctx.layer_index,
+                layer_id: ctx.layer.id.clone(),
+                placement: placement.clone(),
+            });
+        }
+        chunk_placements.push(placement.clone());
+    }
+    for (_, event) in evaluations {
+        sink.send(event);
+    }
+
+    let evaluated_count = candidates
+        .iter()
+        .filter(|c| ctx.chunk_bounds.contains(c.position))
+        .count();
+    let result = RunResult {
+        positions_evaluated: evaluated_count,
+        positions_rejected: evaluated_count.saturating_sub(chunk_placements.len()),
+        placements: chunk_placements,
+    };
+    (overlay, result)
+}
+
+/// Generates the candidates of all world chunks overlapping `bounds`, in chunk row-major order.
+fn generate_candidates(ctx: &WorldLayerContext<'_>, bounds: Bounds) -> Vec<Candidate> {
+    let first = ctx.config.chunk_at(bounds.min);
+    let last = ctx.config.chunk_at(bounds.max);
+    let mut candidates = Vec::new();
+    for y in first.1..=last.1 {
+        for x in first.0..=last.0 {
+            let source = ChunkId(x, y);
+            let source_seed = seed_for_chunk(ctx.layer_seed, source);
+            let source_bounds = ctx.config.chunk_bounds(source);
+            let center = ctx.config.chunk_center(source);
+            let mut rng = StdRng::seed_from_u64(source_seed);
+            let positions = ctx
+                .layer
+                .sampling
+                .generate(ctx.config.chunk_size.into(), &mut rng);
+            for (local, p) in positions.into_iter().enumerate() {
+                let position = Vec2::from(p) + center;
+                if source_bounds.contains(position) && bounds.contains(position) {
+                    candidates.push(Candidate {
+                        position,
+                        source,
+                        local,
+                        key: mix_u64(source_seed ^ (local as u64 + 1).wrapping_mul(PRIORITY_SALT)),
+                    });
+                }
+            }
+        }
+    }
+    candidates
+}
+
+/// Drops candidates in `keep` that have a higher-priority candidate of another world chunk
+/// closer than `distance`, and candidates outside of `keep`.
+fn thin_seams(candidates: Vec<Candidate>, distance: f32, keep: Bounds) -> Vec<Candidate> {
+    if !(distance > 0.0 && distance.is_finite()) {
+        return candidates
+            .into_iter()
+            .filter(|c| keep.contains(c.position))
+            .collect();
+    }
+
+    let cell = |p: Vec2| {
+        (
+            (p.x / distance).floor() as i32,
+            (p.y / distance).floor() as i32,
+        )
+    };
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        grid.entry(cell(candidate.position)).or_default().push(i);
+    }
+
+    let dropped: Vec<bool> = candidates
+        .iter()
+        .map(|candidate| {
+            if !keep.contains(candidate.position) {
+                return true;
+            }
+            let (cx, cy) = cell(candidate.position);
+            (cy - 1..=cy + 1)
+                .flat_map(|y| (cx - 1..=cx + 1).map(move |x| (x, y)))
+                .filter_map(|key| grid.get(&key))
+                .flatten()
+                .map(|&j| &candidates[j])
+                .any(|other| {
+                    other.source != candidate.source
+                        && other.position.distance_squared(candidate.position) < distance * distance
+                        && other.priority() > candidate.priority()
+                })
+        })
+        .collect();
+
+    candidates
+        .into_iter()
+        .zip(dropped)
+        .filter_map(|(candidate, dropped)| (!dropped).then_some(candidate))
+        .collect()
+}
+
+fn evaluate_tile(
+    ctx: &WorldLayerContext<'_>,
+    candidates: &[Candidate],
+    tile: ChunkId,
+    indices: &[usize],
+    emit_evaluations: bool,
+) -> TileOutcome {
+    let grid = chunk::make_chunk_grid(
+        Vec2::ZERO,
+        ctx.config.chunk_extent,
+        ctx.config.raster_cell_size,
+        ctx.config.grid_halo.max(
+            ctx.layer_kinds
+                .program
+                .filter_halo(ctx.config.raster_cell_size),
+        ),
+        tile,
+    );
+    let kind_info = &ctx.layer_kinds.kinds;
+    let mut rt = FieldRuntime::new(ctx.layer_kinds.program.clone(), ctx.textures);
+
+    let mut tentatives = Vec::new();
+    let mut evaluations = Vec::new();
+    let positions: Vec<Vec2> = indices.iter().map(|&i| candidates[i].position).collect();
+    let batch = evaluate_kinds(kind_info, &mut rt, &positions, tile, &grid);
+
+    for (&i, results) in indices.iter().zip(batch) {
+        let candidate = &candidates[i];
+        let position = candidate.position;
+
+        if emit_evaluations && ctx.chunk_bounds.contains(position) {
+            evaluations.push((
+                i,
+                ScatterEvent::PositionEvaluated {
+                    layer_index: ctx.layer_index,
+                    layer_id: ctx.layer.id.clone(),
+                    position,
+                    evaluations: results
+                        .iter()
+                        .map(|r| KindEvaluationLite::new(r.kind.id.clone(), r.allowed, r.weight))
+                        .collect(),
+                    max_weight: results
+                        .iter()
+                        .filter(|r| r.allowed)
+                        .map(|r| r.weight)
+                        .fold(0.0f32, f32::max),
+                },
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(candidate.key);
+        if let Some(slot) = select_kind(ctx.layer.selection_strategy, &results, &mut rng) {
+            let info = &kind_info[slot];
+            let attributes = resolve_attributes(
+                info,
+                candidate.key ^ ATTRIBUTE_SALT,
+                position,
+                &mut rt,
+                tile,
+                &grid,
+            );
+            tentatives.push(Tentative {
+                candidate: i,
+                slot,
+                placement: Placement {
+                    layer_id: ctx.layer.id.clone(),
+                    kind_id: info.kind.id.clone(),
+                    position,
+                    attributes,
+                },
+            });
+        }
+    }
+    (tentatives, evaluations)
+}
+
+/// Builds the overlay of a layer over its exact region, snapped to the world-aligned pixel
+/// grid implied by the layer's per-chunk mask size.
+fn world_overlay(
+    ctx: &WorldLayerContext<'_>,
+    placements: &[Placement],
+    sink: &mut dyn EventSink,
+) -> Option<(String, Arc<OverlayTexture>)> {
+    let (mask_w, mask_h) = ctx.layer.overlay_mask_size_px?;
+    ctx.layer.overlay_brush_radius_px?;
+
+    let mask_size = if mask_w == 0 || mask_h == 0 {
+        (mask_w, mask_h)
+    } else {
+        let pixel = ctx.config.chunk_size / Vec2::new(mask_w as f32, mask_h as f32);
+        let min = (ctx.region.min / pixel).floor();
+        let max = (ctx.region.max / pixel).ceil();
+        let size = (max - min).max(Vec2::ONE);
+        let extent = size * pixel;
+        let center = min * pixel + extent * 0.5;
+        return build_layer_overlay(
+            ctx.layer,
+            ctx.layer_index,
+            extent,
+            center,
+            Some((size.x as u32, size.y as u32)),
+            placements,
+            sink,
+        );
+    };
+    // Let the shared overlay builder report the invalid size.
+    build_layer_overlay(
+        ctx.layer,
+        ctx.layer_index,
+        ctx.config.chunk_size,
+        Vec2::ZERO,
+        Some(mask_size),
+        &[],
+        sink,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::fieldgraph::spec::{FieldGraphSpec, FieldSemantics};
+    use crate::fieldgraph::NodeSpec;
+    use crate::sampling::PoissonDiskSampling;
+    use crate::scatter::events::VecSink;
+    use crate::scatter::spacing::SpacingRules;
+    use crate::scatter::Kind;
+
+    fn kind(id: &str, probability: f32) -> Kind {
+        let mut spec = FieldGraphSpec::default();
+        spec.add_with_semantics(
+            "probability",
+            NodeSpec::constant(probability),
+            FieldSemantics::Probability,
+        );
+        Kind::new(id, spec)
+    }
+
+    fn config() -> WorldChunkConfig {
+        WorldChunkConfig::new(Vec2::splat(20.0))
+            .with_halo(4.0)
+            .with_chunk_extent(10.0)
+            .with_grid_halo(0)
+    }
+
+    fn run(plan: &Plan, chunk: ChunkId) -> RunResult {
+        let cache = FieldProgramCache::new();
+        run_world_chunk(
+            plan,
+            chunk,
+            &config(),
+            &TextureRegistry::new(),
+            &cache,
+            99,
+            None,
+        )
+    }
+
+    fn keys(placements: &[Placement]) -> Vec<(String, u32, u32)> {
+        let mut keys: Vec<_> = placements
+            .iter()
+            .map(|p| {
+                (
+                    p.kind_id.clone(),
+                    p.position.x.to_bits(),
+                    p.position.y.to_bits(),
+                )
+            })
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    fn spaced_plan() -> Plan {
+        let spacing = SpacingRules::new()
+            .with_min_distance("tree", "tree", 3.0)
+            .with_min_distance("tree", "rock", 2.0);
+        let forest = Layer::new_with(
+            "forest",
+            vec![kind("tree", 0.9), kind("rock", 0.5)],
+            PoissonDiskSampling::new(1.5),
+        )
+        .with_spacing(spacing)
+        .with_seam_distance(1.5)
+        .with_overlay((20, 20), 1);
+
+        let mut grass = kind("grass", 1.0);
+        grass.spec.add_with_semantics(
+            "free",
+            NodeSpec::invert("mask".into()),
+            FieldSemantics::Gate,
+        );
+        grass.spec.add(
+            "mask",
+            NodeSpec::texture("mask_forest", crate::fieldgraph::TextureChannel::R),
+        );
+        let meadow = Layer::new_with("meadow", vec![grass], PoissonDiskSampling::new(2.0))
+            .with_seam_distance(2.0);
+
+        Plan::new().with_layers(vec![forest, meadow])
+    }
+
+    #[test]
+    fn chunks_are_deterministic_and_stay_inside_their_bounds() {
+        let plan = spaced_plan();
+        let chunk = ChunkId(1, -2);
+        let a = run(&plan, chunk);
+        let b = run(&plan, chunk);
+
+        assert!(!a.placements.is_empty());
+        assert_eq!(keys(&a.placements), keys(&b.placements));
+        let bounds = config().chunk_bounds(chunk);
+        assert!(a.placements.iter().all(|p| bounds.contains(p.position)));
+    }
+
+    #[test]
+    fn constraints_hold_across_chunk_borders() {
+        let plan = spaced_plan();
+        let spacing = &plan.layers[0].spacing;
+        let mut placements = Vec::new();
+        for y in -1..=0 {
+            for x in -1..=0 {
+                placements.extend(run(&plan, ChunkId(x, y)).placements);
+            }
+        }
+
+        for (i, a) in placements.iter().enumerate() {
+            for b in &placements[i + 1..] {
+                let d = a.position.distance(b.position);
+                let min = match (a.kind_id.as_str(), b.kind_id.as_str()) {
+                    ("grass", "grass") => 2.0,
+                    ("grass", _) | (_, "grass") => 0.0,
+                    (ka, kb) => spacing.min_distance(ka, kb).max(1.5),
+                };
+                assert!(
+                    d >= min - 1e-4,
+                    "{} at {} and {} at {} are {d} apart",
+                    a.kind_id,
+                    a.position,
+                    b.kind_id,
+                    b.position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn placements_follow_their_evaluation() {
+        let cache = FieldProgramCache::new();
+        let mut sink = VecSink::new();
+        let result = run_world_chunk(
+            &spaced_plan(),
+            ChunkId(0, 0),
+            &config(),
+            &TextureRegistry::new(),
+            &cache,
+            7,
+            Some(&mut sink),
+        );
+        assert!(!result.placements.is_empty());
+
+        let mut evaluated = HashSet::new();
+        let mut placed = 0;
+        for event in sink.into_inner() {
+            match event {
+                ScatterEvent::PositionEvaluated {
+                    layer_id, position, ..
+                } => {
+                    evaluated.insert((layer_id, position.x.to_bits(), position.y.to_bits()));
+                }
+                ScatterEvent::PlacementMade {
+                    layer_id,
+                    placement,
+                    ..
+                } => {
+                    let key = (
+                        layer_id,
+                        placement.position.x.to_bits(),
+                        placement.position.y.to_bits(),
+                    );
+                    assert!(
+                        evaluated.contains(&key),
+                        "placement at {} was reported before its evaluation",
+                        placement.position
+                    );
+                    placed += 1;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(placed, result.placements.len());
+    }
+
+    #[test]
+    fn invalid_config_places_nothing() {
+        let cache = FieldProgramCache::new();
+        let result = run_world_chunk(
+            &spaced_plan(),
+            ChunkId(0, 0),
+            &config().with_halo(-1.0),
+            &TextureRegistry::new(),
+            &cache,
+            1,
+            None,
+        );
+        assert!(result.placements.is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! JSON Schemas for plan and field graph files.
+//!
+//! With the `schema` feature, [`plan_schema`] and [`field_graph_schema`] describe the JSON
+//! form of [`PlanDef`] and [`FieldGraphSpec`] (including [`crate::fieldgraph::NodeSpec`] and
+//! [`crate::fieldgraph::spec::FieldSemantics`]), so editors can autocomplete and check plan
+//! files. [`validate_plan`] and [`validate_field_graph`] check a parsed JSON document against
+//! these schemas and report every violation with its location.
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::fieldgraph::spec::FieldGraphSpec;
+use crate::scatter::definition::PlanDef;
+
+/// Schema violation in a plan or field graph document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value, such as `/layers/0/sampling`. Empty for the root.
+    pub path: String,
+    /// Description of the violation.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "/: {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Returns the JSON Schema of [`PlanDef`] documents.
+pub fn plan_schema() -> Value {
+    schemars::schema_for!(PlanDef).to_value()
+}
+
+/// Returns the JSON Schema of [`FieldGraphSpec`] documents.
+pub fn field_graph_schema() -> Value {
+    schemars::schema_for!(FieldGraphSpec).to_value()
+}
+
+/// Validates a plan document against [`plan_schema`], returning all violations.
+pub fn validate_plan(document: &Value) -> Result<(), Vec<SchemaError>> {
+    validate(&plan_schema(), document)
+}
+
+/// Validates a field graph document against [`field_graph_schema`], returning all violations.
+pub fn validate_field_graph(document: &Value) -> Result<(), Vec<SchemaError>> {
+    validate(&field_graph_schema(), document)
+}
+
+fn validate(schema: &Value, document: &Value) -> Result<(), Vec<SchemaError>> {
+    let validator = jsonschema::validator_for(schema).expect("generated schema is valid");
+    let errors: Vec<SchemaError> = validator
+        .iter_errors(document)
+        .map(|error| SchemaError {
+            path: error.instance_path().to_string(),
+            message: error.to_string(),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fieldgraph::{BlurKernel, NodeSpec, TextureChannel};
+    use crate::prelude::{FieldSemantics, KindDef, LayerDef, ParentDef, SamplingDef};
+
+    #[test]
+    fn valid_documents_pass() {
+        let graph = json!({
+            "nodes": {
+                "height": { "Texture": { "params": { "texture_id": "height", "channel": "R" } } },
+                "prob": { "Expr": { "params": { "source": "1 - height" } } },
+            },
+            "semantics": { "prob": "Probability" },
+        });
+        assert_eq!(validate_field_graph(&graph), Ok(()));
+
+        let plan = json!({
+            "layers": [{
+                "id": "trees",
+                "kinds": [{ "id": "oak", "spec": graph }],
+                "sampling": { "PoissonDisk": { "radius": 2.0 } },
+                "spacing": { "min_distances": [["oak", "oak", 4.0]] },
+            }],
+        });
+        assert_eq!(validate_plan(&plan), Ok(()));
+    }
+
+    #[test]
+    fn serialized_plans_match_the_schema() {
+        let mut spec = FieldGraphSpec::default();
+        spec.add("height", NodeSpec::texture("height", TextureChannel::R));
+        spec.add("near", NodeSpec::edt_normalize("height".into(), 0.5, 4.0));
+        spec.add(
+            "soft",
+            NodeSpec::blur("near".into(), BlurKernel::Gaussian, 2.0),
+        );
+        spec.add_with_semantics("gate", NodeSpec::expr("soft > 0.2"), FieldSemantics::Gate);
+        let mut layer = LayerDef::new(
+            "trees",
+            vec![KindDef::new("oak", spec)],
+            SamplingDef::ClusteredThomas {
+                parents: ParentDef::Density(0.01),
+                mean_children: 4.0,
+                sigma: 2.0,
+                clamp_inside: true,
+            },
+        );
+        layer.overlay_mask_size_px = Some((32, 32));
+        layer.overlay_brush_radius_px = Some(2);
+        let plan = PlanDef {
+            layers: vec![layer],
+            ..PlanDef::default()
+        };
+
+        let document = serde_json::to_value(&plan).unwrap();
+        assert_eq!(validate_plan(&document), Ok(()));
+    }
+
+    #[test]
+    fn all_errors_are_reported_with_paths() {
+        let plan = json!({
+            "layers": [
+                {
+                    "kinds": [],
+                    "sampling": { "PoissonDisk": { "radius": "far" } },
+                },
+                {
+                    "id": "rocks",
+                    "kinds": [{ "id": "rock", "spec": { "semantics": { "x": "Maybe" } } }],
+                    "sampling": { "JitterGrid": { "jitter": 0.5, "cell_size": 1.0 } },
+                    "selection_strategy": "Random",
+                },
+            ],
+        });
+        let errors = validate_plan(&plan).unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        for path in [
+            "/layers/0",
+            "/layers/0/sampling",
+            "/layers/1/kinds/0/spec/semantics/x",
+            "/layers/1/selection_strategy",
+        ] {
+            assert!(paths.contains(&path), "missing {path} in {errors:?}");
+        }
+        assert!(errors.iter().any(|e| e.message.contains("\"id\"")));
+    }
+}
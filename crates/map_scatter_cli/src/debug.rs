@@ -0,0 +1,161 @@
+//! Debug images of the gate and probability fields and the overlays of a plan.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use glam::Vec2;
+use map_scatter::prelude::*;
+use map_scatter::scatter::debug::{self, DebugRaster};
+use map_scatter::scatter::overlay::build_overlay_from_placements;
+
+/// File format of debug images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DebugFormat {
+    /// 8-bit grayscale PNG of values in `[0, 1]`.
+    Png,
+    /// 8-bit binary PGM of values in `[0, 1]`.
+    Pgm,
+    /// Raw little-endian f32 values.
+    F32,
+    /// OpenEXR with a single f32 channel.
+    Exr,
+}
+
+impl DebugFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Pgm => "pgm",
+            Self::F32 => "f32",
+            Self::Exr => "exr",
+        }
+    }
+}
+
+/// Writes one image per gate and probability field of every kind as
+/// `<dir>/<layer>.<kind>.<field>.<ext>`, and the coverage of every layer overlay as
+/// `<dir>/<layer>.overlay.<ext>`, returning the number of images written.
+///
+/// Layers are visited in plan order and the overlays of earlier layers are rebuilt from the
+/// placements of `result`, so fields reading `mask_<layer>` show what the run saw.
+#[allow(clippy::too_many_arguments)]
+pub fn write_field_images(
+    dir: &Path,
+    format: DebugFormat,
+    plan: &Plan,
+    config: &RunConfig,
+    registry: &mut TextureRegistry,
+    cache: &FieldProgramCache,
+    result: &RunResult,
+    size: u32,
+) -> Result<usize> {
+    let (width, height) = image_size(config.domain_extent, size);
+    let mut written = 0;
+    for layer in &plan.layers {
+        for kind in &layer.kinds {
+            let fields = debug::sample_kind_fields(kind, cache, registry, config, width, height)
+                .with_context(|| format!("evaluating kind '{}'", kind.id))?;
+            for (field, raster) in fields {
+                let name = format!(
+                    "{}.{}.{}",
+                    file_stem(&layer.id),
+                    file_stem(&kind.id),
+                    file_stem(&field)
+                );
+                write_raster(dir, &name, format, &raster)?;
+                written += 1;
+            }
+        }
+
+        if let (Some((mask_w, mask_h)), Some(brush)) =
+            (layer.overlay_mask_size_px, layer.overlay_brush())
+        {
+            if mask_w == 0 || mask_h == 0 || brush.radius_px < 0 {
+                continue;
+            }
+            let layer_placements: Vec<Placement> = result
+                .placements
+                .iter()
+                .filter(|placement| placement.layer_id == layer.id)
+                .cloned()
+                .collect();
+            let kind_masks: Vec<KindId> = if layer.overlay_kind_masks {
+                layer.kinds.iter().map(|kind| kind.id.clone()).collect()
+            } else {
+                Vec::new()
+            };
+            let overlay = Arc::new(build_overlay_from_placements(
+                config.domain_extent,
+                config.domain_center,
+                &layer_placements,
+                mask_w,
+                mask_h,
+                &brush,
+                &kind_masks,
+            ));
+            let raster = debug::sample_overlay(&overlay, TextureChannel::R, width, height);
+            let name = format!("{}.overlay", file_stem(&layer.id));
+            write_raster(dir, &name, format, &raster)?;
+            written += 1;
+            overlay.register_into(&format!("mask_{}", layer.id), registry);
+        }
+    }
+    Ok(written)
+}
+
+fn write_raster(dir: &Path, name: &str, format: DebugFormat, raster: &DebugRaster) -> Result<()> {
+    let path = dir.join(format!("{name}.{}", format.extension()));
+    let file = File::create(&path).with_context(|| format!("creating {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    match format {
+        DebugFormat::Png => debug::write_png(&mut out, raster, 0.0, 1.0),
+        DebugFormat::Pgm => debug::write_pgm(&mut out, raster, 0.0, 1.0),
+        DebugFormat::F32 => debug::write_raw_f32(&mut out, raster),
+        DebugFormat::Exr => debug::write_exr(&mut out, raster),
+    }
+    .and_then(|()| out.flush())
+    .with_context(|| format!("writing {}", path.display()))
+}
+
+/// Image size whose longer side is `size`, keeping the aspect ratio of the domain.
+fn image_size(extent: Vec2, size: u32) -> (u32, u32) {
+    let size = size.max(1);
+    if extent.x >= extent.y {
+        let h = (size as f32 * extent.y / extent.x.max(f32::EPSILON)).round() as u32;
+        (size, h.max(1))
+    } else {
+        let w = (size as f32 * extent.x / extent.y).round() as u32;
+        (w.max(1), size)
+    }
+}
+
+/// Replaces characters that are not safe in file names.
+fn file_stem(id: &str) -> String {
+    id.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_keep_the_domain_aspect_ratio() {
+        assert_eq!(image_size(Vec2::new(200.0, 100.0), 512), (512, 256));
+        assert_eq!(image_size(Vec2::new(50.0, 100.0), 64), (32, 64));
+        assert_eq!(image_size(Vec2::new(1000.0, 1.0), 16), (16, 1));
+    }
+
+    #[test]
+    fn ids_become_safe_file_names() {
+        assert_eq!(file_stem("mask_trees:oak"), "mask_trees_oak");
+        assert_eq!(file_stem("a/b"), "a_b");
+    }
+}
@@ -0,0 +1,222 @@
+//! `map_scatter` command-line tool: runs a plan file headlessly and writes its placements.
+#![forbid(unsafe_code)]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use glam::Vec2;
+use map_scatter::prelude::*;
+use map_scatter::scatter::export;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+mod debug;
+mod output;
+mod textures;
+
+use debug::DebugFormat;
+use output::OutputFormat;
+use textures::TextureArg;
+
+/// Run a map_scatter plan and write its placements.
+#[derive(Debug, Parser)]
+#[command(name = "map_scatter", version)]
+struct Cli {
+    /// Plan file: RON (`.ron`, `.scatter`) or JSON (`.json`).
+    plan: PathBuf,
+
+    /// Texture as `ID=PATH`. PNG and EXR images are stretched over the domain. Raw files
+    /// (`.raw`, `.r32`, `.f32`) hold little-endian f32 values and take their size as
+    /// `ID=PATH@WIDTHxHEIGHT`, or are assumed square.
+    #[arg(short, long = "texture", value_name = "ID=PATH")]
+    textures: Vec<TextureArg>,
+
+    /// Domain extent in world units, as `WIDTHxHEIGHT` or a single size.
+    #[arg(long, value_name = "SIZE", value_parser = parse_vec2)]
+    extent: Vec2,
+
+    /// Domain center in world units, as `X,Y`.
+    #[arg(long, value_name = "X,Y", value_parser = parse_vec2, default_value = "0,0")]
+    center: Vec2,
+
+    /// Raster cell size in world units.
+    #[arg(long, default_value_t = 1.0)]
+    cell_size: f32,
+
+    /// Chunk extent in world units.
+    #[arg(long, default_value_t = 100.0)]
+    chunk_extent: f32,
+
+    /// Halo cells around each chunk for raster filters.
+    #[arg(long, default_value_t = 2)]
+    halo: usize,
+
+    /// Seed of the run.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Output file; `-` writes to stdout.
+    #[arg(short, long, default_value = "-")]
+    output: PathBuf,
+
+    /// Output format. Inferred from the output extension (`.csv`, `.jsonl`, `.geojson`,
+    /// `.bin`) if omitted, otherwise CSV.
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Directory to write an image of every gate and probability field of every kind, and
+    /// of every layer overlay, into.
+    #[arg(long, value_name = "DIR")]
+    debug_fields: Option<PathBuf>,
+
+    /// File format of debug images.
+    #[arg(long, value_enum, default_value_t = DebugFormat::Png)]
+    debug_format: DebugFormat,
+
+    /// Size in pixels of the longer side of debug images.
+    #[arg(long, default_value_t = 512)]
+    debug_size: u32,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let plan_def = load_plan(&cli.plan)?;
+    let plan = Plan::from(&plan_def);
+    let config = RunConfig::new(cli.extent)
+        .with_domain_center(cli.center)
+        .with_raster_cell_size(cli.cell_size)
+        .with_chunk_extent(cli.chunk_extent)
+        .with_grid_halo(cli.halo);
+    config.validate()?;
+
+    let mut registry = TextureRegistry::new();
+    for texture in &cli.textures {
+        let loaded = textures::load(texture, cli.extent, cli.center)
+            .with_context(|| format!("loading texture '{}'", texture.id))?;
+        registry.register(texture.id.clone(), loaded);
+    }
+
+    let cache = FieldProgramCache::new();
+    let mut rng = StdRng::seed_from_u64(cli.seed);
+    let mut sink = WarningSink;
+    let result = run_plan(&plan, &config, &registry, &cache, &mut rng, Some(&mut sink));
+    eprintln!(
+        "placed {} of {} candidates",
+        result.placements.len(),
+        result.positions_evaluated
+    );
+
+    let format = cli
+        .format
+        .or_else(|| OutputFormat::from_path(&cli.output))
+        .unwrap_or(OutputFormat::Csv)
+        .into();
+    if cli.output == Path::new("-") {
+        let mut out = io::stdout().lock();
+        export::write_placements(&mut out, format, &result.placements)?;
+        out.flush()?;
+    } else {
+        let file = File::create(&cli.output)
+            .with_context(|| format!("creating {}", cli.output.display()))?;
+        let mut out = BufWriter::new(file);
+        export::write_placements(&mut out, format, &result.placements)?;
+        out.flush()?;
+    }
+
+    if let Some(dir) = &cli.debug_fields {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let written = debug::write_field_images(
+            dir,
+            cli.debug_format,
+            &plan,
+            &config,
+            &mut registry,
+            &cache,
+            &result,
+            cli.debug_size,
+        )?;
+        eprintln!("wrote {written} debug images to {}", dir.display());
+    }
+    Ok(())
+}
+
+/// Reads a plan definition, choosing RON or JSON by file extension.
+fn load_plan(path: &Path) -> Result<PlanDef> {
+    let source =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let plan = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&source)?,
+        Some("ron" | "scatter") => ron::from_str(&source)?,
+        _ => bail!(
+            "unknown plan format of {}; use .ron, .scatter, or .json",
+            path.display()
+        ),
+    };
+    Ok(plan)
+}
+
+/// Parses `WIDTHxHEIGHT`, `X,Y`, or a single value used for both axes.
+fn parse_vec2(s: &str) -> Result<Vec2, String> {
+    let parts: Vec<&str> = s.split(['x', ',']).collect();
+    let value = |part: &str| {
+        part.trim()
+            .parse::<f32>()
+            .map_err(|e| format!("invalid number '{part}': {e}"))
+    };
+    match parts.as_slice() {
+        [v] => Ok(Vec2::splat(value(v)?)),
+        [x, y] => Ok(Vec2::new(value(x)?, value(y)?)),
+        _ => Err(format!("expected two values, got '{s}'")),
+    }
+}
+
+/// Reports warnings on stderr.
+struct WarningSink;
+
+impl EventSink for WarningSink {
+    fn send(&mut self, event: ScatterEvent) {
+        if let ScatterEvent::Warning { context, message } = event {
+            eprintln!("warning: {context}: {message}");
+        }
+    }
+
+    fn wants(&self, kind: ScatterEventKind) -> bool {
+        kind == ScatterEventKind::Warning
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vec2_arguments_accept_sizes_and_pairs() {
+        assert_eq!(parse_vec2("256").unwrap(), Vec2::splat(256.0));
+        assert_eq!(parse_vec2("512x128").unwrap(), Vec2::new(512.0, 128.0));
+        assert_eq!(parse_vec2("-4, 2.5").unwrap(), Vec2::new(-4.0, 2.5));
+        assert!(parse_vec2("1x2x3").is_err());
+        assert!(parse_vec2("wide").is_err());
+    }
+
+    #[test]
+    fn cli_arguments_parse() {
+        let cli = Cli::try_parse_from([
+            "map_scatter",
+            "plan.ron",
+            "--extent",
+            "100x50",
+            "-t",
+            "height=height.png",
+            "--format",
+            "geojson",
+        ])
+        .unwrap();
+        assert_eq!(cli.extent, Vec2::new(100.0, 50.0));
+        assert_eq!(cli.textures[0].id, "height");
+        assert_eq!(cli.format, Some(OutputFormat::Geojson));
+    }
+}
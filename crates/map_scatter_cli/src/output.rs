@@ -0,0 +1,57 @@
+//! Output format selection.
+use std::path::Path;
+
+use clap::ValueEnum;
+use map_scatter::scatter::export::ExportFormat;
+
+/// Format of the placement output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One row per placement with a header.
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+    /// GeoJSON feature collection of points.
+    Geojson,
+    /// Versioned little-endian binary.
+    Binary,
+}
+
+impl OutputFormat {
+    /// Infers the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let format = ExportFormat::from_extension(path.extension()?.to_str()?)?;
+        Some(match format {
+            ExportFormat::Csv => Self::Csv,
+            ExportFormat::JsonLines => Self::Jsonl,
+            ExportFormat::GeoJson => Self::Geojson,
+            ExportFormat::Binary => Self::Binary,
+        })
+    }
+}
+
+impl From<OutputFormat> for ExportFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Csv => Self::Csv,
+            OutputFormat::Jsonl => Self::JsonLines,
+            OutputFormat::Geojson => Self::GeoJson,
+            OutputFormat::Binary => Self::Binary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_follow_output_extensions() {
+        let format = |path: &str| OutputFormat::from_path(Path::new(path));
+        assert_eq!(format("out/placements.csv"), Some(OutputFormat::Csv));
+        assert_eq!(format("placements.ndjson"), Some(OutputFormat::Jsonl));
+        assert_eq!(format("placements.geojson"), Some(OutputFormat::Geojson));
+        assert_eq!(format("placements.bin"), Some(OutputFormat::Binary));
+        assert_eq!(format("-"), None);
+    }
+}
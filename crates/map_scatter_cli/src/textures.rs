@@ -0,0 +1,152 @@
+//! Texture files given on the command line.
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use glam::Vec2;
+use map_scatter::prelude::DynamicImageTexture;
+
+/// Texture argument of the form `ID=PATH[@WIDTHxHEIGHT]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureArg {
+    pub id: String,
+    pub path: PathBuf,
+    /// Size of raw files in texels.
+    pub size: Option<(u32, u32)>,
+}
+
+impl FromStr for TextureArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, rest) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected ID=PATH, got '{s}'"))?;
+        if id.is_empty() {
+            return Err(format!("missing texture id in '{s}'"));
+        }
+        // Only a trailing `@WIDTHxHEIGHT` is a size, so paths may contain `@`.
+        let digits = |v: &str| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit());
+        let sized = rest.rsplit_once('@').and_then(|(path, size)| {
+            let (w, h) = size.split_once('x')?;
+            (digits(w) && digits(h)).then_some((path, size, w, h))
+        });
+        let (path, size) = match sized {
+            Some((path, size, w, h)) => {
+                let parse = |v: &str| {
+                    v.parse::<u32>()
+                        .map_err(|e| format!("invalid texture size '{size}': {e}"))
+                };
+                (path, Some((parse(w)?, parse(h)?)))
+            }
+            None => (rest, None),
+        };
+        Ok(Self {
+            id: id.to_string(),
+            path: PathBuf::from(path),
+            size,
+        })
+    }
+}
+
+/// Loads a texture file mapped over the domain of the given extent and center.
+pub fn load(
+    arg: &TextureArg,
+    domain_extent: Vec2,
+    domain_center: Vec2,
+) -> Result<DynamicImageTexture> {
+    if is_raw(&arg.path) {
+        let (width, height, values) = load_raw(&arg.path, arg.size)?;
+        Ok(DynamicImageTexture::from_gray_f32(
+            width,
+            height,
+            values,
+            domain_extent,
+            domain_center,
+        )?)
+    } else {
+        DynamicImageTexture::open(&arg.path, domain_extent, domain_center)
+            .with_context(|| format!("reading {}", arg.path.display()))
+    }
+}
+
+fn is_raw(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("raw" | "r32" | "f32")
+    )
+}
+
+/// Reads a single-channel grid of little-endian f32 values.
+fn load_raw(path: &Path, size: Option<(u32, u32)>) -> Result<(u32, u32, Vec<f32>)> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    if bytes.len() % 4 != 0 {
+        bail!("{} is not a whole number of f32 values", path.display());
+    }
+    let count = bytes.len() / 4;
+    let (width, height) = match size {
+        Some(size) => size,
+        None => {
+            let side = (count as f64).sqrt().round() as u32;
+            (side, side)
+        }
+    };
+    if (width as usize) * (height as usize) != count {
+        bail!(
+            "{} holds {count} values, which is not {width}x{height}",
+            path.display()
+        );
+    }
+    let texels = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok((width, height, texels))
+}
+
+#[cfg(test)]
+mod tests {
+    use map_scatter::prelude::{Texture, TextureChannel};
+
+    use super::*;
+
+    #[test]
+    fn texture_arguments_parse() {
+        let arg: TextureArg = "height=maps/height.png".parse().unwrap();
+        assert_eq!(arg.id, "height");
+        assert_eq!(arg.path, PathBuf::from("maps/height.png"));
+        assert_eq!(arg.size, None);
+
+        let arg: TextureArg = "mask=mask.raw@64x32".parse().unwrap();
+        assert_eq!(arg.size, Some((64, 32)));
+
+        assert!("height.png".parse::<TextureArg>().is_err());
+        assert!("=height.png".parse::<TextureArg>().is_err());
+        assert!("mask=mask.raw@99999999999x1".parse::<TextureArg>().is_err());
+
+        let arg: TextureArg = "height=maps/v@2/h.png".parse().unwrap();
+        assert_eq!(arg.path, PathBuf::from("maps/v@2/h.png"));
+        assert_eq!(arg.size, None);
+    }
+
+    #[test]
+    fn raw_textures_span_the_domain() {
+        let path = std::env::temp_dir().join(format!("map_scatter_cli_{}.f32", std::process::id()));
+        let bytes: Vec<u8> = [0.25f32, 0.75]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        std::fs::write(&path, bytes).unwrap();
+        let arg: TextureArg = format!("height={}@2x1", path.display()).parse().unwrap();
+        let texture = load(&arg, Vec2::new(4.0, 2.0), Vec2::new(10.0, 0.0));
+        std::fs::remove_file(&path).unwrap();
+
+        let texture = texture.unwrap();
+        assert_eq!(texture.sample(TextureChannel::R, Vec2::new(9.0, 0.0)), 0.25);
+        assert_eq!(
+            texture.sample(TextureChannel::G, Vec2::new(11.0, 0.5)),
+            0.75
+        );
+        assert_eq!(texture.sample(TextureChannel::A, Vec2::new(11.0, 0.5)), 1.0);
+    }
+}
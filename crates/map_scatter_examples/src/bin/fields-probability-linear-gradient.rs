@@ -7,13 +7,11 @@ use rand::SeedableRng;
 fn main() -> anyhow::Result<()> {
     let domain_extent = Vec2::new(100.0, 100.0);
 
-    // Register a simple procedural texture that returns the normalized x-coordinate (u in [0,1])
-    // across the domain. This directly acts as our probability field.
-    let mut textures = TextureRegistry::new();
-    textures.register("u_gradient", LinearUTexture { domain_extent });
+    // The gradient is authored directly in the field graph, so no textures are needed.
+    let textures = TextureRegistry::new();
 
     // Kind constructed via helper for consistency
-    let dots = gradient_kind();
+    let dots = gradient_kind(domain_extent);
 
     // Use a simple sampler; the field should bias placements towards the right (u close to 1).
     let plan = Plan::new().with_layer(Layer::new(
@@ -49,27 +47,14 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn gradient_kind() -> Kind {
-    // probability = clamp(texture("u_gradient"), 0, 1)
+fn gradient_kind(domain_extent: Vec2) -> Kind {
+    // probability = normalized x-coordinate (u): 0 at the left edge, 1 at the right edge
+    let half = domain_extent.x * 0.5;
     let mut spec = FieldGraphSpec::default();
-    spec.add("u_raw", NodeSpec::texture("u_gradient", TextureChannel::R));
     spec.add_with_semantics(
         "probability",
-        NodeSpec::clamp("u_raw".into(), 0.0, 1.0),
+        NodeSpec::linear_gradient(Vec2::new(-half, 0.0), Vec2::new(half, 0.0)),
         FieldSemantics::Probability,
     );
     Kind::new("dots", spec)
 }
-
-// A procedural texture that returns the normalized x-coordinate (u) within the domain.
-// u = 0 at the left edge, u = 1 at the right edge.
-struct LinearUTexture {
-    domain_extent: Vec2,
-}
-
-impl Texture for LinearUTexture {
-    fn sample(&self, _channel: TextureChannel, p: Vec2) -> f32 {
-        // Convert world position p.x in [-extent.x/2, extent.x/2] to u in [0,1]
-        ((p.x / self.domain_extent.x) + 0.5).clamp(0.0, 1.0)
-    }
-}
@@ -4,7 +4,7 @@ use map_scatter_examples::{init_tracing, render_run_result_to_png, KindStyle, Re
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 
-/// Coarse single-channel grid data (values in \[0,1\]) with helpers for sampling.
+/// Coarse single-channel grid data (values in \[0,1\]) mapped onto the domain.
 #[derive(Clone)]
 struct GridData {
     origin: Vec2,
@@ -47,101 +47,31 @@ impl GridData {
             data,
         }
     }
+}
 
-    #[inline]
-    fn sample_nearest(&self, p: Vec2) -> f32 {
-        if self.width == 0 || self.height == 0 {
-            return 0.0;
-        }
-
-        // Map world pos to [0,1]^2 in the grid
-        let u = if self.extent.x != 0.0 {
-            ((p.x - self.origin.x) / self.extent.x).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-        let v = if self.extent.y != 0.0 {
-            ((p.y - self.origin.y) / self.extent.y).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-
-        // Nearest texel by partitioning into width/height bins
-        let xi = ((u * self.width as f32) as u32).min(self.width - 1);
-        let yi = ((v * self.height as f32) as u32).min(self.height - 1);
-        self.data[(yi as usize) * (self.width as usize) + (xi as usize)]
+/// A Texture that maps the grid onto the domain and samples it with the node's sampler.
+impl Texture for GridData {
+    fn sample(&self, channel: TextureChannel, p: Vec2) -> f32 {
+        self.sample_with(channel, p, &TextureSampler::default())
     }
 
-    #[inline]
-    fn sample_bilinear(&self, p: Vec2) -> f32 {
-        if self.width == 0 || self.height == 0 {
+    fn sample_with(&self, _channel: TextureChannel, p: Vec2, sampler: &TextureSampler) -> f32 {
+        if self.extent.x == 0.0 || self.extent.y == 0.0 {
             return 0.0;
         }
 
         // Map world pos to continuous texel coordinates
-        let u = if self.extent.x != 0.0 {
-            ((p.x - self.origin.x) / self.extent.x).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-        let v = if self.extent.y != 0.0 {
-            ((p.y - self.origin.y) / self.extent.y).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-
-        // Continuous coordinates over texel indices [0..width-1], [0..height-1]
-        let x = u * (self.width.saturating_sub(1) as f32);
-        let y = v * (self.height.saturating_sub(1) as f32);
-
-        let x0 = x.floor().clamp(0.0, (self.width - 1) as f32) as u32;
-        let y0 = y.floor().clamp(0.0, (self.height - 1) as f32) as u32;
-        let x1 = (x0 + 1).min(self.width - 1);
-        let y1 = (y0 + 1).min(self.height - 1);
-
-        let fx = (x - x0 as f32).clamp(0.0, 1.0);
-        let fy = (y - y0 as f32).clamp(0.0, 1.0);
-
-        let idx =
-            |ix: u32, iy: u32| -> usize { (iy as usize) * (self.width as usize) + (ix as usize) };
-        let v00 = self.data[idx(x0, y0)];
-        let v10 = self.data[idx(x1, y0)];
-        let v01 = self.data[idx(x0, y1)];
-        let v11 = self.data[idx(x1, y1)];
-
-        let vx0 = v00 * (1.0 - fx) + v10 * fx;
-        let vx1 = v01 * (1.0 - fx) + v11 * fx;
-        vx0 * (1.0 - fy) + vx1 * fy
-    }
-}
-
-/// A Texture wrapper that samples the grid using nearest neighbor.
-#[derive(Clone)]
-struct NearestGridTexture {
-    grid: GridData,
-}
-
-impl Texture for NearestGridTexture {
-    fn sample(&self, _channel: TextureChannel, p: Vec2) -> f32 {
-        self.grid.sample_nearest(p)
-    }
-}
-
-/// A Texture wrapper that samples the grid using bilinear interpolation.
-#[derive(Clone)]
-struct BilinearGridTexture {
-    grid: GridData,
-}
-
-impl Texture for BilinearGridTexture {
-    fn sample(&self, _channel: TextureChannel, p: Vec2) -> f32 {
-        self.grid.sample_bilinear(p)
+        let x = (p.x - self.origin.x) / self.extent.x * self.width as f32;
+        let y = (p.y - self.origin.y) / self.extent.y * self.height as f32;
+        sampler.sample_texels(self.width as usize, self.height as usize, x, y, |ix, iy| {
+            self.data[iy * self.width as usize + ix]
+        })
     }
 }
 
 fn make_nearest_kind() -> Kind {
     let mut spec = FieldGraphSpec::default();
-    spec.add("g", NodeSpec::texture("grid_nearest", TextureChannel::R));
+    spec.add("g", NodeSpec::texture("grid", TextureChannel::R));
     spec.add_with_semantics(
         "probability",
         NodeSpec::clamp("g".into(), 0.0, 1.0),
@@ -152,7 +82,14 @@ fn make_nearest_kind() -> Kind {
 
 fn make_bilinear_kind() -> Kind {
     let mut spec = FieldGraphSpec::default();
-    spec.add("g", NodeSpec::texture("grid_bilinear", TextureChannel::R));
+    spec.add(
+        "g",
+        NodeSpec::texture_sampled(
+            "grid",
+            TextureChannel::R,
+            TextureSampler::default().with_filter(TextureFilter::Bilinear),
+        ),
+    );
     spec.add_with_semantics(
         "probability",
         NodeSpec::clamp("g".into(), 0.0, 1.0),
@@ -167,10 +104,9 @@ fn main() -> anyhow::Result<()> {
     let domain_extent = Vec2::new(100.0, 100.0);
     let grid = GridData::coarse_gradient_with_waves(domain_extent, 32, 32);
 
-    // Register the same grid twice with different sampling strategies
+    // Register the grid once; each kind picks its own filter on the texture node
     let mut textures = TextureRegistry::new();
-    textures.register("grid_nearest", NearestGridTexture { grid: grid.clone() });
-    textures.register("grid_bilinear", BilinearGridTexture { grid });
+    textures.register("grid", grid);
 
     // Build two simple specs via helpers:
     let nearest_kind = make_nearest_kind();
@@ -4,140 +4,78 @@ use map_scatter_examples::{init_tracing, render_run_result_to_png, KindStyle, Re
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 
-/// A simple single-channel grid that we use as a Texture source.
-/// We'll generate two grids: elevation and moisture (both in \[0,1\]).
-#[derive(Clone)]
-struct SingleChannelGrid {
-    origin: Vec2,
-    extent: Vec2,
-    width: u32,
-    height: u32,
-    data: Vec<f32>,
-}
+/// Size of the scattered domain in world units, centered on the origin.
+const DOMAIN_EXTENT: Vec2 = Vec2::new(100.0, 100.0);
+
+/// Adds pseudo elevation (`elev_raw`) and moisture (`moist_raw`) fields in \[0,1\] to a spec,
+/// built from position, trigonometric, and blend nodes instead of baked textures.
+fn add_climate_fields(spec: &mut FieldGraphSpec) {
+    let half = DOMAIN_EXTENT * 0.5;
+    let tau = std::f32::consts::TAU;
+
+    spec.add("x", NodeSpec::position_x());
+    spec.add("y", NodeSpec::position_y());
+    // Low-frequency waves in [0,1]: 0.5 + 0.5 * sin(2pi * fu * u) * cos(2pi * fv * v)
+    let mut waves = |name: &str, fu: f32, fv: f32| {
+        spec.add(
+            &format!("{name}_u"),
+            NodeSpec::remap("x".into(), (-half.x, half.x), (0.0, tau * fu)),
+        );
+        spec.add(
+            &format!("{name}_v"),
+            NodeSpec::remap("y".into(), (-half.y, half.y), (0.0, tau * fv)),
+        );
+        spec.add(&format!("{name}_sin"), NodeSpec::sin(format!("{name}_u")));
+        spec.add(&format!("{name}_cos"), NodeSpec::cos(format!("{name}_v")));
+        spec.add(
+            &format!("{name}_wave"),
+            NodeSpec::mul(vec![format!("{name}_sin"), format!("{name}_cos")]),
+        );
+        spec.add(
+            name,
+            NodeSpec::remap(format!("{name}_wave"), (-1.0, 1.0), (0.0, 1.0)),
+        );
+    };
+    waves("elev_waves", 1.4, 0.9);
+    waves("moist_waves", 0.8, 1.1);
+    spec.add("wave_weight", NodeSpec::constant(0.4));
 
-impl SingleChannelGrid {
-    /// Generate a pseudo elevation map:
-    /// - A radial slope (higher towards the center)
-    /// - Low-frequency sinusoidal variation
-    ///   final = clamp(0.6 * radial + 0.4 * sinus, 0, 1)
-    fn elevation(domain_extent: Vec2, width: u32, height: u32) -> Self {
-        let origin = Vec2::new(-domain_extent.x * 0.5, -domain_extent.y * 0.5);
-        let extent = domain_extent;
-
-        let mut data = vec![0.0; (width as usize) * (height as usize)];
-        let r_max = 0.5 * domain_extent.length();
-
-        for y in 0..height {
-            for x in 0..width {
-                let u = (x as f32 + 0.5) / width as f32;
-                let v = (y as f32 + 0.5) / height as f32;
-                let wx = origin.x + u * extent.x;
-                let wy = origin.y + v * extent.y;
-
-                let r = (wx * wx + wy * wy).sqrt();
-                let radial = (1.0 - r / r_max).clamp(0.0, 1.0);
-
-                let sinus = 0.5
-                    + 0.5
-                        * ((2.0 * std::f32::consts::PI * 1.4 * u).sin()
-                            * (2.0 * std::f32::consts::PI * 0.9 * v).cos());
-
-                let h = (0.6 * radial + 0.4 * sinus).clamp(0.0, 1.0);
-                data[(y as usize) * (width as usize) + (x as usize)] = h;
-            }
-        }
-
-        Self {
-            origin,
-            extent,
-            width,
-            height,
-            data,
-        }
-    }
-
-    /// Generate a pseudo moisture map:
-    /// - A left-to-right gradient (wetter on the left)
-    /// - Low-frequency sinusoidal variation
-    ///   final = clamp(0.6 * (1 - u) + 0.4 * sinus, 0, 1)
-    fn moisture(domain_extent: Vec2, width: u32, height: u32) -> Self {
-        let origin = Vec2::new(-domain_extent.x * 0.5, -domain_extent.y * 0.5);
-        let extent = domain_extent;
-
-        let mut data = vec![0.0; (width as usize) * (height as usize)];
-
-        for y in 0..height {
-            for x in 0..width {
-                let u = (x as f32 + 0.5) / width as f32;
-                let v = (y as f32 + 0.5) / height as f32;
-                let _wx = origin.x + u * extent.x;
-                let _wy = origin.y + v * extent.y;
-
-                let gradient = 1.0 - u; // wetter on the left
-                let sinus = 0.5
-                    + 0.5
-                        * ((2.0 * std::f32::consts::PI * 0.8 * u).sin()
-                            * (2.0 * std::f32::consts::PI * 1.1 * v).cos());
-
-                let m = (0.6 * gradient + 0.4 * sinus).clamp(0.0, 1.0);
-                data[(y as usize) * (width as usize) + (x as usize)] = m;
-            }
-        }
-
-        Self {
-            origin,
-            extent,
-            width,
-            height,
-            data,
-        }
-    }
-
-    #[inline]
-    fn sample_nearest(&self, p: Vec2) -> f32 {
-        if self.width == 0 || self.height == 0 {
-            return 0.0;
-        }
-        let u = if self.extent.x != 0.0 {
-            ((p.x - self.origin.x) / self.extent.x).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-        let v = if self.extent.y != 0.0 {
-            ((p.y - self.origin.y) / self.extent.y).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-
-        let x = ((u * self.width as f32) as u32).min(self.width - 1);
-        let y = ((v * self.height as f32) as u32).min(self.height - 1);
-        self.data[(y as usize) * (self.width as usize) + (x as usize)]
-    }
-}
+    // Elevation: a radial slope (higher towards the center) blended with waves
+    spec.add(
+        "elev_falloff",
+        NodeSpec::radial_gradient(Vec2::ZERO, 0.0, half.length()),
+    );
+    spec.add("elev_radial", NodeSpec::invert("elev_falloff".into()));
+    spec.add(
+        "elev_blend",
+        NodeSpec::lerp(
+            "elev_radial".into(),
+            "elev_waves".into(),
+            "wave_weight".into(),
+        ),
+    );
+    spec.add("elev_raw", NodeSpec::clamp("elev_blend".into(), 0.0, 1.0));
 
-impl Texture for SingleChannelGrid {
-    fn sample(&self, channel: TextureChannel, p: Vec2) -> f32 {
-        match channel {
-            TextureChannel::R => self.sample_nearest(p),
-            TextureChannel::A => 1.0,
-            _ => 0.0,
-        }
-    }
+    // Moisture: wetter on the left, blended with waves
+    spec.add(
+        "moist_gradient",
+        NodeSpec::remap("x".into(), (-half.x, half.x), (1.0, 0.0)),
+    );
+    spec.add(
+        "moist_blend",
+        NodeSpec::lerp(
+            "moist_gradient".into(),
+            "moist_waves".into(),
+            "wave_weight".into(),
+        ),
+    );
+    spec.add("moist_raw", NodeSpec::clamp("moist_blend".into(), 0.0, 1.0));
 }
 
 fn main() -> anyhow::Result<()> {
     init_tracing();
-    // Domain
-    let domain_extent = Vec2::new(100.0, 100.0);
-
-    // Bake grids (common gamedev workflow: heightmap + moisture map)
-    let elev = SingleChannelGrid::elevation(domain_extent, 256, 256);
-    let moist = SingleChannelGrid::moisture(domain_extent, 256, 256);
-
-    // Register as textures
-    let mut textures = TextureRegistry::new();
-    textures.register("elevation", elev);
-    textures.register("moisture", moist);
+    // Elevation and moisture are computed by the field graphs, so no textures are needed
+    let textures = TextureRegistry::new();
 
     // Biome-like kinds driven by elevation + moisture:
     // - water: low elevation, high moisture
@@ -157,7 +95,7 @@ fn main() -> anyhow::Result<()> {
     ));
 
     // Runner config
-    let config = RunConfig::new(domain_extent)
+    let config = RunConfig::new(DOMAIN_EXTENT)
         .with_chunk_extent(100.0)
         .with_raster_cell_size(1.0)
         .with_grid_halo(2);
@@ -179,14 +117,7 @@ fn main() -> anyhow::Result<()> {
 fn kind_water() -> Kind {
     let mut spec = FieldGraphSpec::default();
 
-    spec.add(
-        "elev_raw",
-        NodeSpec::texture("elevation", TextureChannel::R),
-    );
-    spec.add(
-        "moist_raw",
-        NodeSpec::texture("moisture", TextureChannel::R),
-    );
+    add_climate_fields(&mut spec);
 
     // Low elevation mask ~ 1 when elev is small
     spec.add(
@@ -214,14 +145,7 @@ fn kind_water() -> Kind {
 fn kind_desert() -> Kind {
     let mut spec = FieldGraphSpec::default();
 
-    spec.add(
-        "elev_raw",
-        NodeSpec::texture("elevation", TextureChannel::R),
-    );
-    spec.add(
-        "moist_raw",
-        NodeSpec::texture("moisture", TextureChannel::R),
-    );
+    add_climate_fields(&mut spec);
 
     // Elevation within mid band:
     // above water
@@ -271,14 +195,7 @@ fn kind_desert() -> Kind {
 fn kind_forest() -> Kind {
     let mut spec = FieldGraphSpec::default();
 
-    spec.add(
-        "elev_raw",
-        NodeSpec::texture("elevation", TextureChannel::R),
-    );
-    spec.add(
-        "moist_raw",
-        NodeSpec::texture("moisture", TextureChannel::R),
-    );
+    add_climate_fields(&mut spec);
 
     spec.add(
         "elev_above_water",
@@ -326,14 +243,7 @@ fn kind_forest() -> Kind {
 fn kind_mountain() -> Kind {
     let mut spec = FieldGraphSpec::default();
 
-    spec.add(
-        "elev_raw",
-        NodeSpec::texture("elevation", TextureChannel::R),
-    );
-    spec.add(
-        "moist_raw",
-        NodeSpec::texture("moisture", TextureChannel::R),
-    );
+    add_climate_fields(&mut spec);
 
     // High elevation
     spec.add(
@@ -364,10 +274,9 @@ fn kind_mountain() -> Kind {
 
 fn render(result: &RunResult) -> anyhow::Result<()> {
     let image_size = (1000, 1000);
-    let domain_extent = Vec2::new(100.0, 100.0);
     let background = [236, 238, 242];
 
-    let mut config = RenderConfig::new(image_size, domain_extent).with_background(background);
+    let mut config = RenderConfig::new(image_size, DOMAIN_EXTENT).with_background(background);
 
     config
         .set_kind_style(